@@ -0,0 +1,491 @@
+use crate::hasher::crypt::CryptScheme;
+use crate::hasher::database::{DatabaseHash, DatabaseScheme};
+use crate::hasher::htpasswd::{self, HtpasswdScheme};
+use crate::hasher::slow::SlowScheme;
+use crate::hasher::{self, lm, Algorithm, WordHash};
+use crate::header::{TableHashing, TableHeader};
+use crate::storage::{self, StorageFormat};
+use argon2::Argon2;
+use base64ct::{Base64, Encoding};
+use password_hash::{PasswordHasher, Salt};
+use pbkdf2::Pbkdf2;
+use pwhash::{md5_crypt, sha256_crypt, sha512_crypt, unix_crypt};
+use scrypt::Scrypt;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs, path, process, thread};
+
+pub const DEFAULT_DURATION_MS: u64 = 500;
+pub const DEFAULT_TABLE_ENTRIES: usize = 100_000;
+// Distinct words each thread cycles through, so every hash is of a different input
+const BENCHMARK_WORDS: usize = 1024;
+// Hashes between looking at the clock, which would otherwise be a good part of the work
+const HASHES_PER_CLOCK_CHECK: usize = 256;
+// Fixed keys, salts and costs, so that rates can be compared between runs and machines
+const BENCHMARK_HMAC_KEY: &[u8] = b"benchmark";
+const BENCHMARK_SALT: &str = "saltsalt";
+// "saltsaltsalt" in the unpadded base64 PHC strings use
+const BENCHMARK_PHC_SALT: &str = "c2FsdHNhbHRzYWx0";
+const BENCHMARK_BCRYPT_SALT: [u8; 16] = *b"saltsaltsaltsalt";
+const BCRYPT_COST: u32 = 10;
+const SCRYPT_LOG_N: u8 = 14;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const PBKDF2_ROUNDS: u32 = 10_000;
+// The argon2 crate's defaults: 19 MiB, 2 passes and 1 lane
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+const SLOW_HASH_LENGTH: usize = 32;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum BenchmarkHasher {
+    // A digest as written to a table: hex encoded
+    Digest(Algorithm),
+    // HMAC of the digest with a fixed key, hex encoded
+    Hmac(Algorithm),
+    Lm,
+    Ntlm,
+    // The schemes from here on are cracked by hashing every word with the target's salt
+    // and cost, so they are hashed with a fixed salt and cost
+    Crypt(CryptScheme),
+    Slow(SlowScheme),
+    Database(DatabaseScheme),
+    Htpasswd(HtpasswdScheme),
+}
+
+impl BenchmarkHasher {
+    pub fn name(&self) -> String {
+        let name = match self {
+            BenchmarkHasher::Digest(algorithm) => algorithm.to_string(),
+            BenchmarkHasher::Hmac(algorithm) => format!("hmac-{}", algorithm),
+            BenchmarkHasher::Lm => String::from("lm"),
+            BenchmarkHasher::Ntlm => String::from("ntlm"),
+            BenchmarkHasher::Crypt(scheme) => scheme.to_string(),
+            BenchmarkHasher::Slow(scheme) => scheme.to_string(),
+            BenchmarkHasher::Database(scheme) => scheme.to_string(),
+            BenchmarkHasher::Htpasswd(scheme) => scheme.to_string(),
+        };
+        match self.cost() {
+            Some(cost) => format!("{} ({})", name, cost),
+            None => name,
+        }
+    }
+
+    pub fn cost(&self) -> Option<String> {
+        // In the notation of the hashes themselves
+        match self {
+            BenchmarkHasher::Crypt(scheme) => Some(format!("rounds={}", scheme.default_rounds())),
+            BenchmarkHasher::Slow(SlowScheme::Bcrypt) => Some(format!("cost={}", BCRYPT_COST)),
+            BenchmarkHasher::Slow(SlowScheme::Scrypt) => {
+                Some(format!("ln={},r={},p={}", SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P))
+            }
+            BenchmarkHasher::Slow(SlowScheme::Pbkdf2Sha256 | SlowScheme::Pbkdf2Sha512) => {
+                Some(format!("i={}", PBKDF2_ROUNDS))
+            }
+            BenchmarkHasher::Slow(_) => Some(format!(
+                "m={},t={},p={}",
+                ARGON2_MEMORY_KIB, ARGON2_ITERATIONS, ARGON2_PARALLELISM
+            )),
+            _ => None,
+        }
+    }
+
+    fn is_slow(&self) -> bool {
+        matches!(self, BenchmarkHasher::Crypt(_) | BenchmarkHasher::Slow(_))
+    }
+
+    pub fn hash(&self, word: &str) -> String {
+        match self {
+            BenchmarkHasher::Digest(algorithm) => {
+                hasher::encode_hex(&algorithm.digest(word.as_bytes()))
+            }
+            BenchmarkHasher::Hmac(algorithm) => algorithm
+                .hmac(BENCHMARK_HMAC_KEY, word.as_bytes())
+                .map(|mac| hasher::encode_hex(&mac))
+                .unwrap_or_default(),
+            BenchmarkHasher::Lm => lm::lm_hash(word),
+            BenchmarkHasher::Ntlm => lm::ntlm_hash(word),
+            BenchmarkHasher::Crypt(scheme) => crypt(Some(*scheme), word),
+            BenchmarkHasher::Slow(scheme) => slow_hash(*scheme, word).unwrap_or_default(),
+            BenchmarkHasher::Database(scheme) => {
+                let database_hash = DatabaseHash {
+                    scheme: *scheme,
+                    salt: BENCHMARK_SALT.as_bytes().to_vec(),
+                    digest: Vec::new(),
+                    raw: String::new(),
+                };
+                hasher::encode_hex(&database_hash.compute(word))
+            }
+            BenchmarkHasher::Htpasswd(HtpasswdScheme::Apr1) => {
+                htpasswd::apr1_crypt(word, BENCHMARK_SALT)
+            }
+            BenchmarkHasher::Htpasswd(HtpasswdScheme::Sha1) => format!(
+                "{{SHA}}{}",
+                Base64::encode_string(&Algorithm::Sha1.digest(word.as_bytes()))
+            ),
+            BenchmarkHasher::Htpasswd(HtpasswdScheme::DesCrypt) => crypt(None, word),
+        }
+    }
+}
+
+impl FromStr for BenchmarkHasher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Only what a rainbow table can be generated for, which is what `plan` takes
+        match s.to_lowercase().as_str() {
+            "lm" => Ok(BenchmarkHasher::Lm),
+            "ntlm" => Ok(BenchmarkHasher::Ntlm),
+            _ => s.parse::<Algorithm>().map(BenchmarkHasher::Digest),
+        }
+    }
+}
+
+// pwhash deprecates these for new passwords, but they are what the cracked hashes use
+#[allow(deprecated)]
+fn crypt(scheme: Option<CryptScheme>, word: &str) -> String {
+    // Traditional DES crypt(3) without a scheme, which only takes 2 characters of salt
+    match scheme {
+        Some(CryptScheme::Md5Crypt) => {
+            md5_crypt::hash_with(format!("$1${}", BENCHMARK_SALT).as_str(), word)
+        }
+        Some(CryptScheme::Sha256Crypt) => {
+            sha256_crypt::hash_with(format!("$5${}", BENCHMARK_SALT).as_str(), word)
+        }
+        Some(CryptScheme::Sha512Crypt) => {
+            sha512_crypt::hash_with(format!("$6${}", BENCHMARK_SALT).as_str(), word)
+        }
+        None => unix_crypt::hash_with(&BENCHMARK_SALT[..2], word),
+    }
+    .unwrap_or_default()
+}
+
+fn slow_hash(scheme: SlowScheme, word: &str) -> Result<String, String> {
+    /*
+        Hash `word` with the fixed benchmark salt and cost, as a bcrypt string or
+        a PHC string that `parse_slow_hash` reads back
+    */
+    let password = word.as_bytes();
+    let salt = Salt::from_b64(BENCHMARK_PHC_SALT).map_err(|e| e.to_string())?;
+    let phc_hash = match scheme {
+        SlowScheme::Bcrypt => {
+            return bcrypt::hash_with_salt(password, BCRYPT_COST, BENCHMARK_BCRYPT_SALT)
+                .map(|hash_parts| hash_parts.to_string())
+                .map_err(|e| e.to_string())
+        }
+        SlowScheme::Scrypt => {
+            let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, SLOW_HASH_LENGTH)
+                .map_err(|e| e.to_string())?;
+            Scrypt.hash_password_customized(password, None, None, params, salt)
+        }
+        SlowScheme::Pbkdf2Sha256 | SlowScheme::Pbkdf2Sha512 => {
+            let algorithm = match scheme {
+                SlowScheme::Pbkdf2Sha256 => pbkdf2::Algorithm::Pbkdf2Sha256,
+                _ => pbkdf2::Algorithm::Pbkdf2Sha512,
+            };
+            let params = pbkdf2::Params {
+                rounds: PBKDF2_ROUNDS,
+                output_length: SLOW_HASH_LENGTH,
+            };
+            Pbkdf2.hash_password_customized(password, Some(algorithm.ident()), None, params, salt)
+        }
+        SlowScheme::Argon2i | SlowScheme::Argon2d | SlowScheme::Argon2id => {
+            let algorithm = match scheme {
+                SlowScheme::Argon2i => argon2::Algorithm::Argon2i,
+                SlowScheme::Argon2d => argon2::Algorithm::Argon2d,
+                _ => argon2::Algorithm::Argon2id,
+            };
+            let params = argon2::Params::new(
+                ARGON2_MEMORY_KIB,
+                ARGON2_ITERATIONS,
+                ARGON2_PARALLELISM,
+                Some(SLOW_HASH_LENGTH),
+            )
+            .map_err(|e| e.to_string())?;
+            Argon2::new(algorithm, argon2::Version::V0x13, params).hash_password(password, salt)
+        }
+    };
+    phc_hash
+        .map(|phc_hash| phc_hash.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub fn hashers() -> Vec<BenchmarkHasher> {
+    /*
+        Every algorithm a table can be generated with, plain and as HMAC where it
+        is supported, then every scheme that can be cracked
+    */
+    let mut hashers: Vec<BenchmarkHasher> = Algorithm::ALL
+        .into_iter()
+        .map(BenchmarkHasher::Digest)
+        .collect();
+    hashers.extend(
+        Algorithm::ALL
+            .into_iter()
+            .filter(|algorithm| algorithm.hmac(BENCHMARK_HMAC_KEY, b"").is_some())
+            .map(BenchmarkHasher::Hmac),
+    );
+    hashers.extend([BenchmarkHasher::Lm, BenchmarkHasher::Ntlm]);
+    hashers.extend(
+        [
+            CryptScheme::Md5Crypt,
+            CryptScheme::Sha256Crypt,
+            CryptScheme::Sha512Crypt,
+        ]
+        .map(BenchmarkHasher::Crypt),
+    );
+    hashers.extend(
+        [
+            SlowScheme::Bcrypt,
+            SlowScheme::Scrypt,
+            SlowScheme::Pbkdf2Sha256,
+            SlowScheme::Pbkdf2Sha512,
+            SlowScheme::Argon2i,
+            SlowScheme::Argon2d,
+            SlowScheme::Argon2id,
+        ]
+        .map(BenchmarkHasher::Slow),
+    );
+    hashers.extend(
+        [
+            DatabaseScheme::MySql,
+            DatabaseScheme::Postgres,
+            DatabaseScheme::Mssql2012,
+            DatabaseScheme::Oracle11g,
+        ]
+        .map(BenchmarkHasher::Database),
+    );
+    hashers.extend(
+        [
+            HtpasswdScheme::Apr1,
+            HtpasswdScheme::Sha1,
+            HtpasswdScheme::DesCrypt,
+        ]
+        .map(BenchmarkHasher::Htpasswd),
+    );
+    hashers
+}
+
+pub fn available_threads() -> usize {
+    thread::available_parallelism()
+        .map(|threads| threads.get())
+        .unwrap_or(1)
+}
+
+fn benchmark_words(prefix: &str, count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+pub fn hashes_per_second(hasher: BenchmarkHasher, threads: usize, duration: Duration) -> u64 {
+    /*
+        Hash on `threads` threads for `duration`, and count the hashes done
+        by all of them together
+    */
+    // A single slow hash can take longer than the whole measurement
+    let hashes_per_clock_check = if hasher.is_slow() {
+        1
+    } else {
+        HASHES_PER_CLOCK_CHECK
+    };
+    let started = Instant::now();
+    let hash_count: usize = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|thread_index| {
+                scope.spawn(move || {
+                    let words =
+                        benchmark_words(&format!("bench{}-", thread_index), BENCHMARK_WORDS);
+                    let mut hash_count = 0;
+                    while started.elapsed() < duration {
+                        for _ in 0..hashes_per_clock_check {
+                            let word = &words[hash_count % BENCHMARK_WORDS];
+                            black_box(hasher.hash(black_box(word)));
+                            hash_count += 1;
+                        }
+                    }
+                    hash_count
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("benchmark thread panicked"))
+            .sum()
+    });
+    (hash_count as f64 / started.elapsed().as_secs_f64()) as u64
+}
+
+// A directory for the benchmark tables, removed with everything in it when dropped
+struct BenchmarkDir(path::PathBuf);
+
+impl Drop for BenchmarkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn lookup_latency(
+    format: StorageFormat,
+    entry_count: usize,
+    duration: Duration,
+) -> Result<Duration, String> {
+    /*
+        Write a table of `entry_count` sha256 entries in hash order, as `--sort`
+        would, then look up entries from all over it for `duration`. Returns the
+        average time a lookup took
+    */
+    let unique = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let benchmark_dir = BenchmarkDir(env::temp_dir().join(format!(
+        "rust-rainbowtable-benchmark-{}-{}",
+        process::id(),
+        unique
+    )));
+    fs::create_dir(&benchmark_dir.0)
+        .map_err(|e| format!("Unable to create {}: {}", benchmark_dir.0.display(), e))?;
+    let table_path = benchmark_dir.0.join(format!("table.{}", format));
+    let table_path = table_path.to_string_lossy();
+
+    let mut word_hashes: Vec<WordHash> = benchmark_words("lookup", entry_count)
+        .into_iter()
+        .map(|word| WordHash {
+            hash: hasher::hash_word(&word),
+            word,
+        })
+        .collect();
+    word_hashes.sort_by(|a, b| a.hash.cmp(&b.hash));
+    let header = TableHeader::new(TableHashing::Plain, &word_hashes);
+    storage::create_table(&table_path, format).write(&header, &word_hashes)?;
+
+    let table = storage::open_table(&table_path)?;
+    // Spread the lookups over the table, a linear scan costs more the further in an entry is
+    let step = (entry_count / BENCHMARK_WORDS).max(1);
+    let hashes: Vec<&str> = word_hashes
+        .iter()
+        .step_by(step)
+        .map(|word_hash| word_hash.hash.as_str())
+        .collect();
+    let started = Instant::now();
+    let mut lookup_count: u32 = 0;
+    for hash in hashes.iter().cycle() {
+        if table.lookup(hash)?.is_none() {
+            return Err(format!(
+                "Benchmark table {} is missing hash {}",
+                table_path, hash
+            ));
+        }
+        lookup_count += 1;
+        if started.elapsed() >= duration || lookup_count == u32::MAX {
+            break;
+        }
+    }
+    Ok(started.elapsed() / lookup_count.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::{crypt, database, slow};
+
+    #[test]
+    fn test_hashers() {
+        let hashers = hashers();
+        let names: Vec<String> = hashers.iter().map(BenchmarkHasher::name).collect();
+        assert_eq!(40, names.len());
+        assert!(names.contains(&"blake2b_512".to_string()));
+        assert!(names.contains(&"hmac-sha256".to_string()));
+        assert!(names.contains(&"sha512crypt (rounds=5000)".to_string()));
+        assert!(names.contains(&"bcrypt (cost=10)".to_string()));
+        assert!(names.contains(&"argon2id (m=19456,t=2,p=1)".to_string()));
+        assert!(names.contains(&"mssql2012".to_string()));
+        assert!(names.contains(&"apr1".to_string()));
+        // BLAKE3 has its own keyed mode instead of HMAC
+        assert!(!hashers.contains(&BenchmarkHasher::Hmac(Algorithm::Blake3(32))));
+        assert_eq!(
+            hasher::hash_word("potato"),
+            BenchmarkHasher::Digest(Algorithm::Sha256).hash("potato")
+        );
+        assert_eq!(
+            lm::ntlm_hash("potato"),
+            BenchmarkHasher::Ntlm.hash("potato")
+        );
+        assert_eq!(Ok(BenchmarkHasher::Lm), "LM".parse::<BenchmarkHasher>());
+        assert_eq!(
+            Ok(BenchmarkHasher::Digest(Algorithm::Sha1)),
+            "sha1".parse::<BenchmarkHasher>()
+        );
+        assert!("crc32".parse::<BenchmarkHasher>().is_err());
+    }
+
+    #[test]
+    fn test_hash_schemes() {
+        // The hashes are ones the schemes crack, at the cost the name states
+        let crypt_schemes = [
+            CryptScheme::Md5Crypt,
+            CryptScheme::Sha256Crypt,
+            CryptScheme::Sha512Crypt,
+        ];
+        for scheme in crypt_schemes {
+            let crypt_hash =
+                crypt::parse_crypt_hash(&BenchmarkHasher::Crypt(scheme).hash("potato")).unwrap();
+            assert_eq!(scheme, crypt_hash.scheme);
+            assert!(crypt_hash.verify("potato"));
+        }
+        // The other Argon2 variants only differ in the algorithm given to the same code
+        let slow_schemes = [
+            SlowScheme::Bcrypt,
+            SlowScheme::Scrypt,
+            SlowScheme::Pbkdf2Sha256,
+            SlowScheme::Pbkdf2Sha512,
+            SlowScheme::Argon2id,
+        ];
+        for scheme in slow_schemes {
+            let hasher = BenchmarkHasher::Slow(scheme);
+            let slow_hash = slow::parse_slow_hash(&hasher.hash("potato")).unwrap();
+            assert_eq!(scheme, slow_hash.scheme);
+            assert!(slow_hash.cost.starts_with(&hasher.cost().unwrap()));
+            assert!(slow_hash.verify("potato"));
+        }
+        let htpasswd_schemes = [
+            HtpasswdScheme::Apr1,
+            HtpasswdScheme::Sha1,
+            HtpasswdScheme::DesCrypt,
+        ];
+        for scheme in htpasswd_schemes {
+            let htpasswd_hash =
+                htpasswd::parse_htpasswd_hash(&BenchmarkHasher::Htpasswd(scheme).hash("potato"))
+                    .unwrap();
+            assert_eq!(scheme, htpasswd_hash.scheme);
+            assert!(htpasswd_hash.verify("potato"));
+        }
+        let mysql_hash = BenchmarkHasher::Database(DatabaseScheme::MySql).hash("potato");
+        assert!(
+            database::parse_database_hash(&format!("*{}", mysql_hash), None)
+                .unwrap()
+                .verify("potato")
+        );
+    }
+
+    #[test]
+    fn test_hashes_per_second() {
+        let duration = Duration::from_millis(20);
+        assert!(hashes_per_second(BenchmarkHasher::Digest(Algorithm::Md5), 1, duration) > 0);
+        assert!(hashes_per_second(BenchmarkHasher::Lm, 2, duration) > 0);
+        // One slow hash takes longer than the whole measurement, but still counts
+        assert!(hashes_per_second(BenchmarkHasher::Slow(SlowScheme::Bcrypt), 1, duration) > 0);
+    }
+
+    #[test]
+    fn test_lookup_latency() {
+        for format in [
+            StorageFormat::Text,
+            StorageFormat::Sqlite,
+            StorageFormat::Binary,
+        ] {
+            assert!(lookup_latency(format, 100, Duration::from_millis(5)).is_ok());
+        }
+    }
+}
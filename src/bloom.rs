@@ -0,0 +1,179 @@
+use crate::hasher::Algorithm;
+use std::f64::consts::LN_2;
+use std::fs;
+use std::path;
+
+pub const DEFAULT_FALSE_POSITIVE_RATE: f64 = 0.01;
+// Appended to the table's path to get the path of its Bloom filter
+const SIDECAR_EXTENSION: &str = ".bloom";
+const MAGIC: &[u8; 8] = b"RTBLOOM1";
+// Magic, bit count, hash count and item count
+const HEADER_LENGTH: usize = 8 + 8 + 4 + 8;
+
+pub fn sidecar_path(rainbow_table_file_path: &str) -> String {
+    format!("{}{}", rainbow_table_file_path, SIDECAR_EXTENSION)
+}
+
+pub fn parse_false_positive_rate(rate: &str) -> Result<f64, String> {
+    match rate.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate < 1.0 => Ok(rate),
+        _ => Err(format!(
+            "Invalid false positive rate, expected a number between 0 and 1, got: {}",
+            rate
+        )),
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    bit_count: u64,
+    hash_count: u32,
+    item_count: u64,
+}
+
+impl BloomFilter {
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> BloomFilter {
+        /*
+            Size the filter for `expected_items` at the given false positive rate:
+            m = -n * ln(p) / ln(2)^2 bits and k = m / n * ln(2) hash functions
+        */
+        let items = expected_items.max(1) as f64;
+        let bit_count = (-items * false_positive_rate.ln() / (LN_2 * LN_2)).ceil() as u64;
+        let bit_count = bit_count.max(8);
+        let hash_count = ((bit_count as f64 / items) * LN_2).round().max(1.0) as u32;
+        BloomFilter {
+            bits: vec![0u8; bit_count.div_ceil(8) as usize],
+            bit_count,
+            hash_count,
+            item_count: 0,
+        }
+    }
+
+    fn bit_indexes(&self, item: &str) -> impl Iterator<Item = u64> {
+        // Double hashing: the i-th index is h1 + i * h2, with both halves taken from one MD5
+        let digest = Algorithm::Md5.digest(item.as_bytes());
+        let h1 = u64::from_le_bytes(digest[..8].try_into().expect("MD5 digests are 16 bytes"));
+        let h2 = u64::from_le_bytes(digest[8..].try_into().expect("MD5 digests are 16 bytes"));
+        let bit_count = self.bit_count;
+        (0..self.hash_count as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bit_count)
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        for index in self.bit_indexes(item) {
+            self.bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+        self.item_count += 1;
+    }
+
+    pub fn contains(&self, item: &str) -> bool {
+        // False means definitely absent, true means probably present
+        self.bit_indexes(item)
+            .all(|index| self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0)
+    }
+
+    pub fn item_count(&self) -> u64 {
+        self.item_count
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let mut content: Vec<u8> = Vec::with_capacity(HEADER_LENGTH + self.bits.len());
+        content.extend(MAGIC);
+        content.extend(self.bit_count.to_le_bytes());
+        content.extend(self.hash_count.to_le_bytes());
+        content.extend(self.item_count.to_le_bytes());
+        content.extend(&self.bits);
+        fs::write(path, content)
+            .map_err(|e| format!("Error while writing Bloom filter {}: {}", path, e))
+    }
+
+    pub fn open(path: &str) -> Result<Option<BloomFilter>, String> {
+        // Tables generated without a Bloom filter simply have no sidecar
+        if !path::Path::new(path).exists() {
+            return Ok(None);
+        }
+        let content = fs::read(path)
+            .map_err(|e| format!("Error while reading Bloom filter {}: {}", path, e))?;
+        let invalid = || format!("Invalid Bloom filter file: {}", path);
+        if content.len() < HEADER_LENGTH || &content[..8] != MAGIC {
+            return Err(invalid());
+        }
+        let bit_count = u64::from_le_bytes(content[8..16].try_into().map_err(|_| invalid())?);
+        let hash_count = u32::from_le_bytes(content[16..20].try_into().map_err(|_| invalid())?);
+        let item_count = u64::from_le_bytes(content[20..28].try_into().map_err(|_| invalid())?);
+        let bits = content[HEADER_LENGTH..].to_vec();
+        if bit_count == 0 || hash_count == 0 || bits.len() as u64 != bit_count.div_ceil(8) {
+            return Err(invalid());
+        }
+        Ok(Some(BloomFilter {
+            bits,
+            bit_count,
+            hash_count,
+            item_count,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn filled_filter(item_count: usize, false_positive_rate: f64) -> BloomFilter {
+        let mut bloom_filter = BloomFilter::new(item_count, false_positive_rate);
+        for i in 0..item_count {
+            bloom_filter.insert(&format!("present{}", i));
+        }
+        bloom_filter
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let bloom_filter = filled_filter(1000, 0.01);
+        assert_eq!(1000, bloom_filter.item_count());
+        assert!((0..1000).all(|i| bloom_filter.contains(&format!("present{}", i))));
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        for false_positive_rate in [0.1, 0.01] {
+            let bloom_filter = filled_filter(2000, false_positive_rate);
+            let false_positives = (0..10000)
+                .filter(|i| bloom_filter.contains(&format!("absent{}", i)))
+                .count();
+            // Allow for some slack, the rate is only what the filter is sized for
+            let observed_rate = false_positives as f64 / 10000.0;
+            assert!(
+                observed_rate < false_positive_rate * 2.0,
+                "expected around {}, got {}",
+                false_positive_rate,
+                observed_rate
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_and_open() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let bloom_filter = filled_filter(100, 0.05);
+        bloom_filter
+            .write(&temp_file_handler.temp_file_path)
+            .unwrap();
+        assert_eq!(
+            Ok(Some(bloom_filter)),
+            BloomFilter::open(&temp_file_handler.temp_file_path)
+        );
+
+        assert_eq!(Ok(None), BloomFilter::open("/abc/defghi/jkl.bloom"));
+        fs::write(&temp_file_handler.temp_file_path, b"RTBLOOM1 truncated").unwrap();
+        assert!(BloomFilter::open(&temp_file_handler.temp_file_path).is_err());
+    }
+
+    #[test]
+    fn test_parse_false_positive_rate() {
+        assert_eq!(Ok(0.001), parse_false_positive_rate("0.001"));
+        assert!(parse_false_positive_rate("0").is_err());
+        assert!(parse_false_positive_rate("1.5").is_err());
+        assert!(parse_false_positive_rate("abc").is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use crate::reader;
+
+const LOWER_CHARSET: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER_CHARSET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGIT_CHARSET: &str = "0123456789";
+const SPECIAL_CHARSET: &str = " !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+pub enum CandidateSource {
+    Wordlist {
+        word_file_path: String,
+        rule_file_path: Option<String>,
+    },
+    Mask(String),
+}
+
+impl CandidateSource {
+    pub fn candidates(&self) -> Result<Box<dyn Iterator<Item = String>>, String> {
+        /*
+            Build a lazy stream of candidate words. The word list is read up front, but
+            rules and masks are expanded on the fly, so their output is never held in memory
+        */
+        match self {
+            CandidateSource::Wordlist {
+                word_file_path,
+                rule_file_path,
+            } => {
+                let words = reader::read_words(word_file_path)?;
+                let rules = match rule_file_path {
+                    Some(rule_file_path) => parse_rules(reader::read_words(rule_file_path)?)?,
+                    None => return Ok(Box::new(words.into_iter())),
+                };
+                Ok(Box::new(words.into_iter().flat_map(move |word| {
+                    rules
+                        .iter()
+                        .map(|rule| apply_rule(&word, rule))
+                        .collect::<Vec<String>>()
+                })))
+            }
+            CandidateSource::Mask(mask) => Ok(Box::new(MaskIterator::new(mask)?)),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum RuleFunction {
+    // `:` - leave the word as it is
+    Noop,
+    // `l` - lowercase all letters
+    Lowercase,
+    // `u` - uppercase all letters
+    Uppercase,
+    // `c` - uppercase the first letter, lowercase the rest
+    Capitalize,
+    // `r` - reverse the word
+    Reverse,
+    // `d` - duplicate the word
+    Duplicate,
+    // `$X` - append character X
+    Append(char),
+    // `^X` - prepend character X
+    Prepend(char),
+    // `[` - delete the first character
+    DeleteFirst,
+    // `]` - delete the last character
+    DeleteLast,
+    // `sXY` - replace all X with Y
+    Replace(char, char),
+}
+
+pub type Rule = Vec<RuleFunction>;
+
+pub fn parse_rule(rule_str: &str) -> Result<Rule, String> {
+    /*
+        Parse a single rule line, a subset of the hashcat rule syntax. Functions are
+        applied left to right, and whitespace between functions is ignored
+    */
+    let mut rule: Rule = Vec::new();
+    let mut chars = rule_str.chars();
+    let missing_argument = |function: char| {
+        format!(
+            "Rule function '{}' is missing an argument in rule: {}",
+            function, rule_str
+        )
+    };
+    while let Some(function) = chars.next() {
+        let rule_function = match function {
+            ' ' => continue,
+            ':' => RuleFunction::Noop,
+            'l' => RuleFunction::Lowercase,
+            'u' => RuleFunction::Uppercase,
+            'c' => RuleFunction::Capitalize,
+            'r' => RuleFunction::Reverse,
+            'd' => RuleFunction::Duplicate,
+            '[' => RuleFunction::DeleteFirst,
+            ']' => RuleFunction::DeleteLast,
+            '$' => RuleFunction::Append(chars.next().ok_or_else(|| missing_argument(function))?),
+            '^' => RuleFunction::Prepend(chars.next().ok_or_else(|| missing_argument(function))?),
+            's' => {
+                let from = chars.next().ok_or_else(|| missing_argument(function))?;
+                let to = chars.next().ok_or_else(|| missing_argument(function))?;
+                RuleFunction::Replace(from, to)
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown rule function '{}' in rule: {}",
+                    function, rule_str
+                ))
+            }
+        };
+        rule.push(rule_function);
+    }
+    Ok(rule)
+}
+
+pub fn parse_rules(rule_lines: Vec<String>) -> Result<Vec<Rule>, String> {
+    // Blank lines and lines starting with `#` are skipped
+    rule_lines
+        .iter()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| parse_rule(line))
+        .collect()
+}
+
+pub fn apply_rule(word: &str, rule: &[RuleFunction]) -> String {
+    let mut word = String::from(word);
+    for rule_function in rule {
+        word = match rule_function {
+            RuleFunction::Noop => word,
+            RuleFunction::Lowercase => word.to_lowercase(),
+            RuleFunction::Uppercase => word.to_uppercase(),
+            RuleFunction::Capitalize => {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first
+                        .to_uppercase()
+                        .chain(chars.as_str().to_lowercase().chars())
+                        .collect(),
+                    None => word,
+                }
+            }
+            RuleFunction::Reverse => word.chars().rev().collect(),
+            RuleFunction::Duplicate => word.repeat(2),
+            RuleFunction::Append(c) => {
+                word.push(*c);
+                word
+            }
+            RuleFunction::Prepend(c) => format!("{}{}", c, word),
+            RuleFunction::DeleteFirst => word.chars().skip(1).collect(),
+            RuleFunction::DeleteLast => {
+                word.pop();
+                word
+            }
+            RuleFunction::Replace(from, to) => word.replace(*from, &to.to_string()),
+        };
+    }
+    word
+}
+
+pub fn parse_mask(mask: &str) -> Result<Vec<Vec<char>>, String> {
+    /*
+        Parse a hashcat style mask into one charset per position.
+        ?l = lowercase, ?u = uppercase, ?d = digits, ?s = specials, ?a = all of them,
+        ?? = a literal '?', and any other character is used as is
+    */
+    let mut charsets: Vec<Vec<char>> = Vec::new();
+    let mut chars = mask.chars();
+    while let Some(c) = chars.next() {
+        if c != '?' {
+            charsets.push(vec![c]);
+            continue;
+        }
+        let charset = match chars.next() {
+            Some('l') => LOWER_CHARSET.chars().collect(),
+            Some('u') => UPPER_CHARSET.chars().collect(),
+            Some('d') => DIGIT_CHARSET.chars().collect(),
+            Some('s') => SPECIAL_CHARSET.chars().collect(),
+            Some('a') => [LOWER_CHARSET, UPPER_CHARSET, DIGIT_CHARSET, SPECIAL_CHARSET]
+                .concat()
+                .chars()
+                .collect(),
+            Some('?') => vec!['?'],
+            Some(other) => {
+                return Err(format!("Unknown mask charset ?{} in mask: {}", other, mask))
+            }
+            None => return Err(format!("Mask ends with an incomplete charset: {}", mask)),
+        };
+        charsets.push(charset);
+    }
+    if charsets.is_empty() {
+        return Err(String::from("Mask must not be empty"));
+    }
+    Ok(charsets)
+}
+
+pub struct MaskIterator {
+    charsets: Vec<Vec<char>>,
+    // Current index into each position's charset, like the digits of an odometer
+    indices: Vec<usize>,
+    exhausted: bool,
+}
+
+impl MaskIterator {
+    pub fn new(mask: &str) -> Result<MaskIterator, String> {
+        let charsets = parse_mask(mask)?;
+        let indices = vec![0; charsets.len()];
+        Ok(MaskIterator {
+            charsets,
+            indices,
+            exhausted: false,
+        })
+    }
+}
+
+impl Iterator for MaskIterator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.exhausted {
+            return None;
+        }
+        let candidate: String = self
+            .indices
+            .iter()
+            .zip(&self.charsets)
+            .map(|(&index, charset)| charset[index])
+            .collect();
+
+        // Advance the rightmost position, carrying over to the left
+        self.exhausted = true;
+        for position in (0..self.indices.len()).rev() {
+            self.indices[position] += 1;
+            if self.indices[position] < self.charsets[position].len() {
+                self.exhausted = false;
+                break;
+            }
+            self.indices[position] = 0;
+        }
+        Some(candidate)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // The candidates left, counting the odometer positions still to come, unless too many to count
+        if self.exhausted {
+            return (0, Some(0));
+        }
+        let remaining = self
+            .indices
+            .iter()
+            .zip(&self.charsets)
+            .try_fold(0usize, |remaining, (&index, charset)| {
+                remaining
+                    .checked_mul(charset.len())?
+                    .checked_add(charset.len() - 1 - index)
+            })
+            .and_then(|remaining| remaining.checked_add(1));
+        match remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        let expected_rule = vec![
+            RuleFunction::Capitalize,
+            RuleFunction::Replace('a', '@'),
+            RuleFunction::Append('1'),
+            RuleFunction::Prepend('!'),
+        ];
+        assert_eq!(Ok(expected_rule), parse_rule("c sa@ $1 ^!"));
+
+        assert!(parse_rule("$").is_err());
+        assert!(parse_rule("x").is_err());
+    }
+
+    #[test]
+    fn test_apply_rule() {
+        let cases = [
+            (":", "Password"),
+            ("l", "password"),
+            ("u", "PASSWORD"),
+            ("c", "Password"),
+            ("r", "drowssaP"),
+            ("d", "PasswordPassword"),
+            ("$1$2", "Password12"),
+            ("^x", "xPassword"),
+            ("[", "assword"),
+            ("]", "Passwor"),
+            ("ss$", "Pa$$word"),
+            ("l sa@ $!", "p@ssword!"),
+        ];
+        for (rule, expected_word) in cases {
+            let rule = parse_rule(rule).unwrap();
+            assert_eq!(expected_word, apply_rule("Password", &rule));
+        }
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rule_lines = vec![
+            "# comment".to_string(),
+            ":".to_string(),
+            "".to_string(),
+            "u".to_string(),
+        ];
+        let expected_rules = vec![vec![RuleFunction::Noop], vec![RuleFunction::Uppercase]];
+        assert_eq!(Ok(expected_rules), parse_rules(rule_lines));
+    }
+
+    #[test]
+    fn test_mask_iterator() {
+        let candidates: Vec<String> = MaskIterator::new("a?d").unwrap().collect();
+        assert_eq!(10, candidates.len());
+        assert_eq!("a0", candidates[0]);
+        assert_eq!("a9", candidates[9]);
+
+        let candidates: Vec<String> = MaskIterator::new("?l??").unwrap().collect();
+        assert_eq!(26, candidates.len());
+        assert_eq!("z?", candidates[25]);
+
+        let count = MaskIterator::new("?u?d?d").unwrap().count();
+        assert_eq!(26 * 10 * 10, count);
+    }
+
+    #[test]
+    fn test_mask_iterator_size_hint() {
+        let mut mask_iterator = MaskIterator::new("?u?d?d").unwrap();
+        assert_eq!((2600, Some(2600)), mask_iterator.size_hint());
+        mask_iterator.nth(1234);
+        assert_eq!((1365, Some(1365)), mask_iterator.size_hint());
+        assert_eq!(1365, mask_iterator.by_ref().count());
+        assert_eq!((0, Some(0)), mask_iterator.size_hint());
+        // Too many candidates to count in a usize
+        let mask_iterator = MaskIterator::new(&"?a".repeat(12)).unwrap();
+        assert_eq!((usize::MAX, None), mask_iterator.size_hint());
+    }
+
+    #[test]
+    fn test_parse_mask_invalid() {
+        assert!(parse_mask("").is_err());
+        assert!(parse_mask("?x").is_err());
+        assert!(parse_mask("abc?").is_err());
+    }
+}
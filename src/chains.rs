@@ -0,0 +1,616 @@
+use crate::header::TableHashing;
+use crate::progress::Progress;
+use crate::rainbowcrack::{self, PlainSpace, TableName};
+use std::fs;
+use std::path;
+
+pub const DP_EXTENSION: &str = "dpt";
+// Times the expected chain length a chain may run before it is dropped
+const MAX_CHAIN_LENGTH_FACTOR: u64 = 20;
+const DP_MAGIC: &[u8; 8] = b"RTDPCHN1";
+const HEADER_FIELD_DELIMITER: char = '\t';
+const HEADER_KEY_VALUE_DELIMITER: char = '=';
+
+pub fn parse_distinguished_mask(mask: &str) -> Result<u64, String> {
+    // Either hex, e.g. 0xfff, or decimal, e.g. 4095
+    let parsed = match mask.strip_prefix("0x").or_else(|| mask.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => mask.parse::<u64>(),
+    };
+    match parsed {
+        Ok(mask) if mask > 0 => Ok(mask),
+        _ => Err(format!(
+            "Invalid distinguished point mask, expected a non-zero number such as 0xfff, got: {}",
+            mask
+        )),
+    }
+}
+
+pub fn parse_max_chain_length(max_chain_length: &str) -> Result<u64, String> {
+    match max_chain_length.parse::<u64>() {
+        Ok(max_chain_length) if max_chain_length > 0 => Ok(max_chain_length),
+        _ => Err(format!(
+            "Invalid maximum chain length, expected a number above 0, got: {}",
+            max_chain_length
+        )),
+    }
+}
+
+pub fn default_max_chain_length(mask: u64) -> u64 {
+    /*
+        A hash is distinguished with probability 2^-k for a mask of k bits, so
+        chains are 2^k hashes long on average. Few chains run 20 times that
+        without looping
+    */
+    1u64.checked_shl(mask.count_ones())
+        .unwrap_or(u64::MAX)
+        .saturating_mul(MAX_CHAIN_LENGTH_FACTOR)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ChainMode {
+    // Chains of a fixed length with a reduction per position, as RainbowCrack generates them
+    Rainbow { chain_length: u64 },
+    /*
+        Chains end at the first hash whose digest prefix has every bit of `mask`
+        clear, using a single reduction. Chains that get no such hash within
+        `max_chain_length` hashes, e.g. because they loop, are dropped
+    */
+    DistinguishedPoint { mask: u64, max_chain_length: u64 },
+}
+
+fn is_distinguished(hash: &str, mask: u64) -> Result<bool, String> {
+    Ok(rainbowcrack::digest_prefix(hash)? & mask == 0)
+}
+
+// The plaintexts a chain table covers and how they are hashed and reduced
+pub struct ChainSpace {
+    pub algorithm: String,
+    pub charset: String,
+    pub min_length: usize,
+    pub max_length: usize,
+    // Tables of a set differ in their reduction, so they cover different plaintexts
+    pub table_index: u64,
+    plain_space: PlainSpace,
+    hashing: TableHashing,
+}
+
+impl ChainSpace {
+    pub fn new(
+        algorithm: &str,
+        charset: &str,
+        min_length: usize,
+        max_length: usize,
+        table_index: u64,
+    ) -> Result<ChainSpace, String> {
+        Ok(ChainSpace {
+            algorithm: String::from(algorithm),
+            charset: String::from(charset),
+            min_length,
+            max_length,
+            table_index,
+            plain_space: PlainSpace::new(charset, min_length, max_length)?,
+            hashing: rainbowcrack::table_hashing(algorithm)?,
+        })
+    }
+
+    fn plain_and_hash(&self, index: u64) -> (String, String) {
+        let plain = self.plain_space.index_to_plain(index);
+        let hash = self
+            .hashing
+            .hash(&plain)
+            .expect("RainbowCrack algorithms need nothing but the word");
+        (plain, hash)
+    }
+
+    fn reduce(&self, hash: &str, position: u64) -> Result<u64, String> {
+        self.plain_space.reduce(hash, self.table_index, position)
+    }
+
+    fn walk(&self, start: u64, mode: ChainMode) -> Result<Option<(u64, u64)>, String> {
+        /*
+            Follow a chain from `start`. Returns its end point and how many hashes
+            it took, or None for a distinguished point chain that never got to one.
+            A RainbowCrack chain of length L holds L - 1 hashes
+        */
+        let mut index = start;
+        match mode {
+            ChainMode::Rainbow { chain_length } => {
+                for position in 0..chain_length.saturating_sub(1) {
+                    let (_, hash) = self.plain_and_hash(index);
+                    index = self.reduce(&hash, position)?;
+                }
+                Ok(Some((index, chain_length.saturating_sub(1))))
+            }
+            ChainMode::DistinguishedPoint {
+                mask,
+                max_chain_length,
+            } => {
+                for length in 1..=max_chain_length {
+                    let (_, hash) = self.plain_and_hash(index);
+                    index = self.reduce(&hash, 0)?;
+                    if is_distinguished(&hash, mask)? {
+                        return Ok(Some((index, length)));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn find_in_chain(
+        &self,
+        start: u64,
+        mode: ChainMode,
+        hash: &str,
+    ) -> Result<Option<String>, String> {
+        // Regenerate a chain from its start, looking for the plaintext of `hash`
+        let mut index = start;
+        let (steps, mask) = match mode {
+            ChainMode::Rainbow { chain_length } => (chain_length.saturating_sub(1), None),
+            ChainMode::DistinguishedPoint {
+                mask,
+                max_chain_length,
+            } => (max_chain_length, Some(mask)),
+        };
+        for position in 0..steps {
+            let (plain, chain_hash) = self.plain_and_hash(index);
+            if chain_hash.eq_ignore_ascii_case(hash) {
+                return Ok(Some(plain));
+            }
+            if let Some(mask) = mask {
+                if is_distinguished(&chain_hash, mask)? {
+                    break;
+                }
+            }
+            index = self.reduce(&chain_hash, mask.map_or(position, |_| 0))?;
+        }
+        Ok(None)
+    }
+}
+
+// How a chain table came out of generation
+pub struct GeneratedChains {
+    pub table: ChainTable,
+    // Distinguished point chains that went past the maximum length without ending
+    pub dropped: u64,
+    // Distinguished point chains that ended where a longer one did, and so added nothing
+    pub merged: u64,
+}
+
+pub struct ChainTable {
+    pub space: ChainSpace,
+    pub mode: ChainMode,
+    // Start and end points, sorted by end
+    pub chains: Vec<(u64, u64)>,
+}
+
+impl ChainTable {
+    pub fn generate(
+        space: ChainSpace,
+        mode: ChainMode,
+        chain_count: u64,
+        progress: &mut Progress,
+    ) -> Result<GeneratedChains, String> {
+        /*
+            Walk `chain_count` chains from consecutive start points, each table of
+            a set starting where the one before it stopped
+        */
+        let total = space.plain_space.total();
+        let first_start = space.table_index.wrapping_mul(chain_count) % total;
+        let mut chains: Vec<(u64, u64, u64)> = Vec::new();
+        let mut dropped = 0;
+        for chain_number in 0..chain_count {
+            let start = ((first_start as u128 + chain_number as u128) % total as u128) as u64;
+            match space.walk(start, mode)? {
+                Some((end, length)) => {
+                    chains.push((start, end, length));
+                    progress.advance(length, chain_number + 1);
+                }
+                None => {
+                    dropped += 1;
+                    if let ChainMode::DistinguishedPoint {
+                        max_chain_length, ..
+                    } = mode
+                    {
+                        progress.advance(max_chain_length, chain_number + 1);
+                    }
+                }
+            }
+        }
+        progress.finish();
+
+        // Longest first within an end, so that is the chain kept when they are merged
+        chains.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)));
+        let mut merged = 0;
+        if let ChainMode::DistinguishedPoint { .. } = mode {
+            let before = chains.len();
+            chains.dedup_by_key(|(_, end, _)| *end);
+            merged = (before - chains.len()) as u64;
+        }
+        Ok(GeneratedChains {
+            table: ChainTable {
+                space,
+                mode,
+                chains: chains
+                    .into_iter()
+                    .map(|(start, end, _)| (start, end))
+                    .collect(),
+            },
+            dropped,
+            merged,
+        })
+    }
+
+    pub fn file_name(&self) -> String {
+        // Rainbow tables are named the way RainbowCrack expects, so it can read them too
+        let space = &self.space;
+        match self.mode {
+            ChainMode::Rainbow { chain_length } => TableName {
+                algorithm: space.algorithm.clone(),
+                charset: space.charset.clone(),
+                min_length: space.min_length,
+                max_length: space.max_length,
+                table_index: space.table_index,
+                chain_length,
+                chain_count: self.chains.len() as u64,
+                part: 0,
+            }
+            .to_string(),
+            ChainMode::DistinguishedPoint { mask, .. } => format!(
+                "{}_{}#{}-{}_{}_dp{:x}x{}.{}",
+                space.algorithm,
+                space.charset,
+                space.min_length,
+                space.max_length,
+                space.table_index,
+                mask,
+                self.chains.len(),
+                DP_EXTENSION
+            ),
+        }
+    }
+
+    fn dp_header(&self, mask: u64, max_chain_length: u64) -> String {
+        let space = &self.space;
+        [
+            ("algorithm", space.algorithm.clone()),
+            ("charset", space.charset.clone()),
+            ("min_length", space.min_length.to_string()),
+            ("max_length", space.max_length.to_string()),
+            ("table_index", space.table_index.to_string()),
+            ("mask", format!("{:#x}", mask)),
+            ("max_chain_length", max_chain_length.to_string()),
+        ]
+        .iter()
+        .map(|(key, value)| format!("{}{}{}", key, HEADER_KEY_VALUE_DELIMITER, value))
+        .collect::<Vec<String>>()
+        .join(&HEADER_FIELD_DELIMITER.to_string())
+    }
+
+    pub fn path(&self, output_dir: &str) -> String {
+        path::Path::new(output_dir)
+            .join(self.file_name())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub fn write(&self, output_dir: &str) -> Result<String, String> {
+        /*
+            Write the table to `output_dir`, returning its path. Rainbow tables are
+            plain .rt files, distinguished point tables start with a header saying
+            how they were generated, as the file name cannot hold all of it
+        */
+        let table_path = self.path(output_dir);
+        let mut content: Vec<u8> = Vec::new();
+        if let ChainMode::DistinguishedPoint {
+            mask,
+            max_chain_length,
+        } = self.mode
+        {
+            let header = self.dp_header(mask, max_chain_length);
+            content.extend(DP_MAGIC);
+            content.extend((header.len() as u32).to_le_bytes());
+            content.extend(header.as_bytes());
+        }
+        content.reserve(self.chains.len() * rainbowcrack::CHAIN_LENGTH_BYTES);
+        for (start, end) in &self.chains {
+            content.extend(start.to_le_bytes());
+            content.extend(end.to_le_bytes());
+        }
+        fs::create_dir_all(output_dir)
+            .and_then(|_| fs::write(&table_path, content))
+            .map_err(|e| format!("Error while writing chains to file: {}", e))?;
+        Ok(table_path)
+    }
+
+    pub fn open(table_path: &str) -> Result<ChainTable, String> {
+        // Told apart by their contents, so a renamed distinguished point table still opens
+        let content = fs::read(table_path).map_err(|e| {
+            format!(
+                "Error opening rainbow table {} for reading: {}",
+                table_path, e
+            )
+        })?;
+        let (space, mode, chain_bytes) = match content.strip_prefix(DP_MAGIC) {
+            Some(rest) => {
+                let (space, mode, header_end) = parse_dp_header(table_path, rest)?;
+                (space, mode, &rest[header_end..])
+            }
+            None => {
+                let table_name = table_path.parse::<TableName>()?;
+                let space = ChainSpace::new(
+                    &table_name.algorithm,
+                    &table_name.charset,
+                    table_name.min_length,
+                    table_name.max_length,
+                    table_name.table_index,
+                )?;
+                let mode = ChainMode::Rainbow {
+                    chain_length: table_name.chain_length,
+                };
+                (space, mode, &content[..])
+            }
+        };
+        if !chain_bytes
+            .len()
+            .is_multiple_of(rainbowcrack::CHAIN_LENGTH_BYTES)
+        {
+            return Err(format!(
+                "Invalid chain table {}, its chains are not a multiple of {} bytes",
+                table_path,
+                rainbowcrack::CHAIN_LENGTH_BYTES
+            ));
+        }
+        let mut chains: Vec<(u64, u64)> = chain_bytes
+            .chunks_exact(rainbowcrack::CHAIN_LENGTH_BYTES)
+            .map(|chain| {
+                (
+                    u64::from_le_bytes(chain[..8].try_into().expect("chains are 16 bytes")),
+                    u64::from_le_bytes(chain[8..].try_into().expect("chains are 16 bytes")),
+                )
+            })
+            .collect();
+        // Tables straight out of rtgen are not sorted until rtsort has been run on them
+        chains.sort_by_key(|(_, end)| *end);
+        Ok(ChainTable {
+            space,
+            mode,
+            chains,
+        })
+    }
+
+    fn starts_ending_at(&self, end: u64) -> impl Iterator<Item = u64> + '_ {
+        let first = self
+            .chains
+            .partition_point(|(_, chain_end)| *chain_end < end);
+        self.chains[first..]
+            .iter()
+            .take_while(move |(_, chain_end)| *chain_end == end)
+            .map(|(start, _)| *start)
+    }
+
+    pub fn lookup(&self, hash: &str) -> Result<Option<String>, String> {
+        /*
+            Find the end point the hash would lead to and regenerate the chains
+            ending there. A rainbow table needs that for every position the hash
+            could be at, a distinguished point table only for the one end point
+            its chain runs into
+        */
+        match self.mode {
+            ChainMode::Rainbow { chain_length } => {
+                let hash_count = chain_length.saturating_sub(1);
+                for position in (0..hash_count).rev() {
+                    let mut index = self.space.reduce(hash, position)?;
+                    for next_position in position + 1..hash_count {
+                        let (_, next_hash) = self.space.plain_and_hash(index);
+                        index = self.space.reduce(&next_hash, next_position)?;
+                    }
+                    for start in self.starts_ending_at(index) {
+                        if let Some(plain) = self.space.find_in_chain(start, self.mode, hash)? {
+                            return Ok(Some(plain));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            ChainMode::DistinguishedPoint {
+                mask,
+                max_chain_length,
+            } => {
+                let mut chain_hash = String::from(hash);
+                for _ in 0..max_chain_length {
+                    let index = self.space.reduce(&chain_hash, 0)?;
+                    if is_distinguished(&chain_hash, mask)? {
+                        for start in self.starts_ending_at(index) {
+                            if let Some(plain) = self.space.find_in_chain(start, self.mode, hash)? {
+                                return Ok(Some(plain));
+                            }
+                        }
+                        return Ok(None);
+                    }
+                    chain_hash = self.space.plain_and_hash(index).1;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn parse_dp_header(
+    table_path: &str,
+    content: &[u8],
+) -> Result<(ChainSpace, ChainMode, usize), String> {
+    /*
+        Read the header after the magic bytes: its length as a little-endian u32,
+        then tab separated key=value fields. Returns where the chains start
+    */
+    let invalid = |reason: &str| {
+        format!(
+            "Invalid distinguished point table {}, {}",
+            table_path, reason
+        )
+    };
+    let header_length = content
+        .get(..4)
+        .map(|length| u32::from_le_bytes(length.try_into().expect("4 bytes")) as usize)
+        .ok_or_else(|| invalid("its header is cut short"))?;
+    let header = content
+        .get(4..4 + header_length)
+        .ok_or_else(|| invalid("its header is cut short"))?;
+    let header = std::str::from_utf8(header).map_err(|_| invalid("its header is not text"))?;
+    let field = |key: &str| {
+        header
+            .split(HEADER_FIELD_DELIMITER)
+            .filter_map(|field| field.split_once(HEADER_KEY_VALUE_DELIMITER))
+            .find(|(field_key, _)| *field_key == key)
+            .map(|(_, value)| value)
+            .ok_or_else(|| invalid(&format!("its header has no {}", key)))
+    };
+    let number = |key: &str| {
+        field(key).and_then(|value| {
+            value
+                .parse::<u64>()
+                .map_err(|_| invalid(&format!("its {} is not a number", key)))
+        })
+    };
+    let space = ChainSpace::new(
+        field("algorithm")?,
+        field("charset")?,
+        number("min_length")? as usize,
+        number("max_length")? as usize,
+        number("table_index")?,
+    )?;
+    let mode = ChainMode::DistinguishedPoint {
+        mask: parse_distinguished_mask(field("mask")?)?,
+        max_chain_length: parse_max_chain_length(field("max_chain_length")?)?,
+    };
+    Ok((space, mode, 4 + header_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::ProgressMode;
+    use crate::test_utils;
+
+    fn space() -> ChainSpace {
+        ChainSpace::new("md5", "numeric", 1, 3, 0).unwrap()
+    }
+
+    fn generate(mode: ChainMode, chain_count: u64) -> GeneratedChains {
+        let mut progress = Progress::new("Generating", "hashes", None, ProgressMode::Hidden);
+        ChainTable::generate(space(), mode, chain_count, &mut progress).unwrap()
+    }
+
+    fn md5(word: &str) -> String {
+        space().hashing.hash(word).unwrap()
+    }
+
+    #[test]
+    fn test_parse_distinguished_mask() {
+        assert_eq!(Ok(0xfff), parse_distinguished_mask("0xfff"));
+        assert_eq!(Ok(7), parse_distinguished_mask("7"));
+        for mask in ["0", "0x0", "0xzz", "-1"] {
+            assert!(parse_distinguished_mask(mask).is_err());
+        }
+        assert!(parse_max_chain_length("0").is_err());
+        assert_eq!(160, default_max_chain_length(0x7));
+        assert_eq!(u64::MAX, default_max_chain_length(u64::MAX));
+    }
+
+    #[test]
+    fn test_distinguished_point_chains() {
+        let mode = ChainMode::DistinguishedPoint {
+            mask: 0x7,
+            max_chain_length: 64,
+        };
+        let generated = generate(mode, 200);
+        let table = &generated.table;
+        assert_eq!(
+            200,
+            table.chains.len() as u64 + generated.dropped + generated.merged
+        );
+        // Every end is the reduction of a distinguished point, and only one chain is kept per end
+        for window in table.chains.windows(2) {
+            assert!(window[0].1 < window[1].1);
+        }
+        for (start, end) in &table.chains {
+            assert_eq!(
+                Some(*end),
+                space().walk(*start, mode).unwrap().map(|(end, _)| end)
+            );
+        }
+    }
+
+    #[test]
+    fn test_chains_past_max_length_are_dropped() {
+        // Almost no hash is distinguished with every bit of the prefix masked
+        let mode = ChainMode::DistinguishedPoint {
+            mask: u64::MAX,
+            max_chain_length: 4,
+        };
+        let generated = generate(mode, 50);
+        assert_eq!(50, generated.dropped);
+        assert!(generated.table.chains.is_empty());
+    }
+
+    #[test]
+    fn test_write_open_and_lookup() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.chains", temp_file_handler.temp_file_path);
+        let modes = [
+            ChainMode::Rainbow { chain_length: 20 },
+            ChainMode::DistinguishedPoint {
+                mask: 0x7,
+                max_chain_length: 64,
+            },
+        ];
+        for mode in modes {
+            let generated = generate(mode, 300);
+            let table_path = generated.table.write(&output_dir).unwrap();
+            let table = ChainTable::open(&table_path).unwrap();
+            assert_eq!(mode, table.mode);
+            assert_eq!(generated.table.chains, table.chains);
+
+            // The first plaintext of a chain is always in the table
+            let (start, _) = table.chains[0];
+            let plain = table.space.plain_space.index_to_plain(start);
+            assert_eq!(Some(plain.clone()), table.lookup(&md5(&plain)).unwrap());
+            assert_eq!(None, table.lookup(&md5("not numeric")).unwrap());
+        }
+        assert!(fs::read_dir(&output_dir).unwrap().any(|entry| entry
+            .unwrap()
+            .path()
+            .to_string_lossy()
+            .ends_with(".rt")));
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rainbow_chains_import() {
+        // Rainbow tables are valid RainbowCrack tables
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.chains", temp_file_handler.temp_file_path);
+        let generated = generate(ChainMode::Rainbow { chain_length: 5 }, 10);
+        let table_path = generated.table.write(&output_dir).unwrap();
+        let (_, word_hashes) = rainbowcrack::import_table(&table_path).unwrap();
+        assert_eq!(40, word_hashes.len());
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_invalid_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let mut content = DP_MAGIC.to_vec();
+        content.extend(100u32.to_le_bytes());
+        fs::write(&temp_file_handler.temp_file_path, &content).unwrap();
+        assert!(ChainTable::open(&temp_file_handler.temp_file_path).is_err());
+        let header = "algorithm=md5\tcharset=numeric";
+        content.truncate(DP_MAGIC.len());
+        content.extend((header.len() as u32).to_le_bytes());
+        content.extend(header.as_bytes());
+        fs::write(&temp_file_handler.temp_file_path, &content).unwrap();
+        assert!(ChainTable::open(&temp_file_handler.temp_file_path).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path;
+
+pub const CHECKPOINT_FILE_NAME: &str = "checkpoint.txt";
+// Written first and renamed over the checkpoint, so a crash never leaves half of one
+const CHECKPOINT_TEMP_FILE_NAME: &str = "checkpoint.txt.tmp";
+const CHECKPOINT_VERSION: u32 = 1;
+const CHECKPOINT_DELIMITER: char = '=';
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 1_000_000;
+
+pub fn checkpoint_dir(rainbow_table_path: &str) -> String {
+    // Next to the table, where the sorted runs it points to are kept
+    format!("{}.checkpoint", rainbow_table_path)
+}
+
+pub fn parse_checkpoint_interval(checkpoint_interval: &str) -> Result<usize, String> {
+    match checkpoint_interval.parse::<usize>() {
+        Ok(checkpoint_interval) if checkpoint_interval > 0 => Ok(checkpoint_interval),
+        _ => Err(format!(
+            "Invalid checkpoint interval, expected a number of entries above 0, got: {}",
+            checkpoint_interval
+        )),
+    }
+}
+
+/*
+    How far a sorted generation run had come: every entry from the first
+    `input_offset` bytes of the word file is in one of the first `run_count`
+    sorted runs, and nothing else is
+*/
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Checkpoint {
+    // Digest of the generation settings, so a run is only resumed the way it was started
+    pub settings: String,
+    // Size of the word file when the run started, to notice a word file that changed
+    pub word_file_size: u64,
+    pub input_offset: u64,
+    pub entry_count: usize,
+    pub run_count: usize,
+}
+
+impl Checkpoint {
+    pub fn new(settings: String, word_file_size: u64) -> Checkpoint {
+        Checkpoint {
+            settings,
+            word_file_size,
+            input_offset: 0,
+            entry_count: 0,
+            run_count: 0,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "version{d}{}\nsettings{d}{}\nword_file_size{d}{}\ninput_offset{d}{}\nentries{d}{}\nruns{d}{}\n",
+            CHECKPOINT_VERSION,
+            self.settings,
+            self.word_file_size,
+            self.input_offset,
+            self.entry_count,
+            self.run_count,
+            d = CHECKPOINT_DELIMITER
+        )
+    }
+
+    fn deserialize(checkpoint: &str) -> Result<Checkpoint, String> {
+        let mut fields: Vec<(&str, &str)> = Vec::new();
+        for line in checkpoint.lines().filter(|line| !line.trim().is_empty()) {
+            let field = line
+                .split_once(CHECKPOINT_DELIMITER)
+                .ok_or_else(|| format!("Invalid checkpoint line, got: {}", line))?;
+            fields.push(field);
+        }
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|(field_key, _)| *field_key == key)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| format!("Checkpoint is missing its {}", key))
+        };
+        let number = |key: &str| {
+            let value = field(key)?;
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid checkpoint {}, got: {}", key, value))
+        };
+        let version = number("version")?;
+        if version != CHECKPOINT_VERSION as u64 {
+            return Err(format!(
+                "Unsupported checkpoint version {}, expected {}",
+                version, CHECKPOINT_VERSION
+            ));
+        }
+        Ok(Checkpoint {
+            settings: String::from(field("settings")?),
+            word_file_size: number("word_file_size")?,
+            input_offset: number("input_offset")?,
+            entry_count: number("entries")? as usize,
+            run_count: number("runs")? as usize,
+        })
+    }
+
+    pub fn open(checkpoint_dir: &str) -> Result<Option<Checkpoint>, String> {
+        // None when no checkpoint has been written to the directory yet
+        let checkpoint_path = path::Path::new(checkpoint_dir).join(CHECKPOINT_FILE_NAME);
+        if !checkpoint_path.exists() {
+            return Ok(None);
+        }
+        let checkpoint = fs::read_to_string(&checkpoint_path).map_err(|e| {
+            format!(
+                "Unable to read checkpoint {}: {}",
+                checkpoint_path.display(),
+                e
+            )
+        })?;
+        Checkpoint::deserialize(&checkpoint).map(Some)
+    }
+
+    pub fn write(&self, checkpoint_dir: &str) -> Result<(), String> {
+        let temp_path = path::Path::new(checkpoint_dir).join(CHECKPOINT_TEMP_FILE_NAME);
+        let checkpoint_path = path::Path::new(checkpoint_dir).join(CHECKPOINT_FILE_NAME);
+        fs::write(&temp_path, self.serialize())
+            .and_then(|_| fs::rename(&temp_path, &checkpoint_path))
+            .map_err(|e| {
+                format!(
+                    "Error while writing checkpoint {}: {}",
+                    checkpoint_path.display(),
+                    e
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn test_parse_checkpoint_interval() {
+        assert_eq!(Ok(5000), parse_checkpoint_interval("5000"));
+        for checkpoint_interval in ["0", "-1", "abc", ""] {
+            assert!(parse_checkpoint_interval(checkpoint_interval).is_err());
+        }
+    }
+
+    #[test]
+    fn test_write_and_open() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let checkpoint_dir = path::Path::new(&temp_file_handler.temp_file_path)
+            .parent()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(Ok(None), Checkpoint::open(&checkpoint_dir));
+
+        let mut checkpoint = Checkpoint::new("abc123".to_string(), 4096);
+        checkpoint.input_offset = 2048;
+        checkpoint.entry_count = 300;
+        checkpoint.run_count = 2;
+        checkpoint.write(&checkpoint_dir).unwrap();
+        // Writing again replaces the checkpoint as a whole
+        checkpoint.write(&checkpoint_dir).unwrap();
+        assert_eq!(
+            Ok(Some(checkpoint.clone())),
+            Checkpoint::open(&checkpoint_dir)
+        );
+        assert!(!path::Path::new(&checkpoint_dir)
+            .join(CHECKPOINT_TEMP_FILE_NAME)
+            .exists());
+    }
+
+    #[test]
+    fn test_deserialize_invalid_checkpoint() {
+        let checkpoint = Checkpoint::new("abc123".to_string(), 4096).serialize();
+        assert!(Checkpoint::deserialize(&checkpoint).is_ok());
+        assert!(Checkpoint::deserialize(&checkpoint.replace("version=1", "version=2")).is_err());
+        assert!(Checkpoint::deserialize(&checkpoint.replace("runs=0", "runs=x")).is_err());
+        assert!(Checkpoint::deserialize(&checkpoint.replace("entries=0\n", "")).is_err());
+        assert!(Checkpoint::deserialize("not a checkpoint").is_err());
+    }
+}
@@ -0,0 +1,436 @@
+use crate::hasher::{self, WordHash};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Write};
+use std::mem;
+use std::path;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 30;
+// The smallest limit accepted, anything less would spill a run every few entries
+const MIN_MEMORY_LIMIT: usize = 1 << 16;
+// What a buffered entry costs besides the bytes of its word and hash
+const ENTRY_OVERHEAD: usize = mem::size_of::<WordHash>();
+const RUN_FILE_PREFIX: &str = "run-";
+
+pub fn parse_memory_limit(memory_limit: &str) -> Result<usize, String> {
+    /*
+        Parse a size in bytes, with an optional K, M or G suffix for
+        kibibytes, mebibytes and gibibytes, e.g. 512M
+    */
+    let invalid = || {
+        format!(
+            "Invalid memory limit, expected a size of at least 64K such as 512M or 4G, got: {}",
+            memory_limit
+        )
+    };
+    let upper = memory_limit.trim().to_uppercase();
+    let upper = upper.strip_suffix('B').unwrap_or(&upper);
+    let (number, multiplier) = match upper.char_indices().last() {
+        Some((index, 'K')) => (&upper[..index], 1 << 10),
+        Some((index, 'M')) => (&upper[..index], 1 << 20),
+        Some((index, 'G')) => (&upper[..index], 1 << 30),
+        _ => (upper, 1),
+    };
+    let bytes = number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(invalid)?;
+    if bytes < MIN_MEMORY_LIMIT {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+fn entry_size(word_hash: &WordHash) -> usize {
+    word_hash.word.len() + word_hash.hash.len() + ENTRY_OVERHEAD
+}
+
+fn sort_key(word_hash: &WordHash) -> (&str, &str) {
+    // Hash order is what lookups need, the word only makes the order stable
+    (&word_hash.hash, &word_hash.word)
+}
+
+pub struct ExternalSorter {
+    // Holds the spilled runs, and is removed with everything in it when the sorter is dropped
+    temp_dir: path::PathBuf,
+    // Leave the runs behind when dropped, so that a checkpointed run can be resumed
+    keep_on_drop: bool,
+    memory_limit: usize,
+    buffer: Vec<WordHash>,
+    buffer_size: usize,
+    runs: Vec<path::PathBuf>,
+}
+
+impl ExternalSorter {
+    pub fn new(output_path: &str, memory_limit: usize) -> Result<ExternalSorter, String> {
+        /*
+            Runs are spilled next to the table being written, which is where there
+            is known to be room for about as much again
+        */
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        let temp_dir =
+            path::PathBuf::from(format!("{}.sort-{}-{}", output_path, process::id(), unique));
+        fs::create_dir(&temp_dir).map_err(|e| {
+            format!(
+                "Unable to create temporary directory {}: {}",
+                temp_dir.display(),
+                e
+            )
+        })?;
+        Ok(ExternalSorter {
+            temp_dir,
+            keep_on_drop: false,
+            memory_limit,
+            buffer: Vec::new(),
+            buffer_size: 0,
+            runs: Vec::new(),
+        })
+    }
+
+    pub fn checkpointed(
+        temp_dir: &str,
+        memory_limit: usize,
+        run_count: Option<usize>,
+    ) -> Result<ExternalSorter, String> {
+        /*
+            A sorter whose runs are kept in `temp_dir` until it is told otherwise.
+            Given the `run_count` of a checkpoint, it picks up those runs and removes
+            any spilled after the checkpoint. Otherwise it starts over in an empty directory
+        */
+        let dir_error = |e: std::io::Error| {
+            format!("Unable to prepare checkpoint directory {}: {}", temp_dir, e)
+        };
+        let temp_path = path::PathBuf::from(temp_dir);
+        let run_path =
+            |run_index: usize| temp_path.join(format!("{}{}", RUN_FILE_PREFIX, run_index));
+        let runs = match run_count {
+            Some(run_count) => {
+                let runs: Vec<path::PathBuf> = (0..run_count).map(run_path).collect();
+                if let Some(missing) = runs.iter().find(|run| !run.is_file()) {
+                    return Err(format!(
+                        "Sorted run {} of the checkpoint is missing",
+                        missing.display()
+                    ));
+                }
+                for entry in fs::read_dir(&temp_path).map_err(dir_error)? {
+                    let entry = entry.map_err(dir_error)?;
+                    let is_later_run = entry
+                        .file_name()
+                        .to_string_lossy()
+                        .strip_prefix(RUN_FILE_PREFIX)
+                        .and_then(|run_index| run_index.parse::<usize>().ok())
+                        .is_some_and(|run_index| run_index >= run_count);
+                    if is_later_run {
+                        fs::remove_file(entry.path()).map_err(dir_error)?;
+                    }
+                }
+                runs
+            }
+            None => {
+                if temp_path.exists() {
+                    fs::remove_dir_all(&temp_path).map_err(dir_error)?;
+                }
+                fs::create_dir_all(&temp_path).map_err(dir_error)?;
+                Vec::new()
+            }
+        };
+        Ok(ExternalSorter {
+            temp_dir: temp_path,
+            keep_on_drop: true,
+            memory_limit,
+            buffer: Vec::new(),
+            buffer_size: 0,
+            runs,
+        })
+    }
+
+    pub fn set_keep_on_drop(&mut self, keep_on_drop: bool) {
+        self.keep_on_drop = keep_on_drop;
+    }
+
+    pub fn push(&mut self, word_hash: WordHash) -> Result<(), String> {
+        self.buffer_size += entry_size(&word_hash);
+        self.buffer.push(word_hash);
+        if self.buffer_size >= self.memory_limit {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    pub fn run_count(&self) -> usize {
+        self.runs.len()
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        // Spill whatever is buffered, so that every entry pushed so far is on disk
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.spill()
+    }
+
+    fn spill(&mut self) -> Result<(), String> {
+        // Sort what is buffered and write it out as the next run
+        self.buffer
+            .sort_unstable_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        let run_path = self
+            .temp_dir
+            .join(format!("{}{}", RUN_FILE_PREFIX, self.runs.len()));
+        let write_error = |e: std::io::Error| {
+            format!(
+                "Error while writing sorted run {}: {}",
+                run_path.display(),
+                e
+            )
+        };
+        let mut writer = BufWriter::new(fs::File::create(&run_path).map_err(write_error)?);
+        for word_hash in &self.buffer {
+            writeln!(
+                writer,
+                "{}{}{}",
+                word_hash.word,
+                hasher::HASH_DELIMITER,
+                word_hash.hash
+            )
+            .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)?;
+        self.buffer.clear();
+        self.buffer_size = 0;
+        self.runs.push(run_path);
+        Ok(())
+    }
+
+    pub fn sorted(&mut self) -> Result<SortedEntries<'_>, String> {
+        /*
+            Every entry pushed so far, in hash order and with exact duplicates left
+            out. Can be called again to go over the entries once more
+        */
+        if self.runs.is_empty() {
+            self.buffer
+                .sort_unstable_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+            return Ok(SortedEntries {
+                source: SortedSource::Memory(self.buffer.iter()),
+                previous: None,
+            });
+        }
+        self.flush()?;
+        let mut runs = Vec::new();
+        for run_path in &self.runs {
+            let file = fs::File::open(run_path)
+                .map_err(|e| format!("Unable to open sorted run {}: {}", run_path.display(), e))?;
+            runs.push(BufReader::new(file).lines());
+        }
+        let mut merge = Merge {
+            runs,
+            heads: BinaryHeap::new(),
+        };
+        for run_index in 0..merge.runs.len() {
+            merge.advance(run_index)?;
+        }
+        Ok(SortedEntries {
+            source: SortedSource::Merge(merge),
+            previous: None,
+        })
+    }
+}
+
+impl Drop for ExternalSorter {
+    fn drop(&mut self) {
+        // Whether the table was written or not, the runs are of no more use
+        if !self.keep_on_drop {
+            let _ = fs::remove_dir_all(&self.temp_dir);
+        }
+    }
+}
+
+// A k-way merge of the sorted runs, holding the next entry of each run
+struct Merge {
+    runs: Vec<Lines<BufReader<fs::File>>>,
+    heads: BinaryHeap<Reverse<(String, String, usize)>>,
+}
+
+impl Merge {
+    fn advance(&mut self, run_index: usize) -> Result<(), String> {
+        // Queue up the next entry of the run, unless it has run out
+        let line = match self.runs[run_index].next() {
+            Some(line) => line.map_err(|e| format!("Error while reading sorted run: {}", e))?,
+            None => return Ok(()),
+        };
+        let word_hash = hasher::split_serialized_hash(&line)
+            .ok_or_else(|| format!("Invalid entry in sorted run, got: {}", line))?;
+        self.heads
+            .push(Reverse((word_hash.hash, word_hash.word, run_index)));
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<WordHash>, String> {
+        let Reverse((hash, word, run_index)) = match self.heads.pop() {
+            Some(head) => head,
+            None => return Ok(None),
+        };
+        self.advance(run_index)?;
+        Ok(Some(WordHash { word, hash }))
+    }
+}
+
+enum SortedSource<'a> {
+    // Nothing was spilled, so the buffer is all there is
+    Memory(std::slice::Iter<'a, WordHash>),
+    Merge(Merge),
+}
+
+pub struct SortedEntries<'a> {
+    source: SortedSource<'a>,
+    previous: Option<WordHash>,
+}
+
+impl Iterator for SortedEntries<'_> {
+    type Item = Result<WordHash, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let word_hash = match &mut self.source {
+                SortedSource::Memory(entries) => entries.next().cloned(),
+                SortedSource::Merge(merge) => match merge.next() {
+                    Ok(word_hash) => word_hash,
+                    Err(e) => return Some(Err(e)),
+                },
+            }?;
+            // Duplicates are next to each other once sorted
+            if self.previous.as_ref() == Some(&word_hash) {
+                continue;
+            }
+            self.previous = Some(word_hash.clone());
+            return Some(Ok(word_hash));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn word_hash(word: &str) -> WordHash {
+        WordHash {
+            word: word.to_string(),
+            hash: hasher::hash_word(word),
+        }
+    }
+
+    #[test]
+    fn test_parse_memory_limit() {
+        assert_eq!(Ok(512 << 20), parse_memory_limit("512M"));
+        assert_eq!(Ok(4 << 30), parse_memory_limit("4gb"));
+        assert_eq!(Ok(100_000), parse_memory_limit("100000"));
+        assert_eq!(Ok(64 << 10), parse_memory_limit("64K"));
+        for invalid in ["", "M", "-1G", "1K", "12Q", "99999999999999999999G"] {
+            assert!(parse_memory_limit(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_sort_in_runs() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_path = &temp_file_handler.temp_file_path;
+        let words: Vec<String> = (0..500).map(|i| format!("word:{}", i)).collect();
+        // Small enough to spill a run every few dozen entries
+        let mut sorter = ExternalSorter::new(output_path, 4096).unwrap();
+        for word in words.iter().chain(words.iter().take(10)) {
+            sorter.push(word_hash(word)).unwrap();
+        }
+        assert!(sorter.run_count() > 1);
+
+        let mut expected: Vec<WordHash> = words.iter().map(|word| word_hash(word)).collect();
+        expected.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        for _ in 0..2 {
+            let sorted = sorter
+                .sorted()
+                .unwrap()
+                .collect::<Result<Vec<WordHash>, String>>();
+            assert_eq!(Ok(expected.clone()), sorted);
+        }
+
+        let temp_dir = sorter.temp_dir.clone();
+        assert!(temp_dir.exists());
+        drop(sorter);
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn test_sort_in_memory() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let mut sorter =
+            ExternalSorter::new(&temp_file_handler.temp_file_path, DEFAULT_MEMORY_LIMIT).unwrap();
+        for word in ["potato", "rice", "potato"] {
+            sorter.push(word_hash(word)).unwrap();
+        }
+        assert_eq!(0, sorter.run_count());
+        let sorted = sorter
+            .sorted()
+            .unwrap()
+            .collect::<Result<Vec<WordHash>, String>>();
+        // 209f... sorts before e91c...
+        assert_eq!(Ok(vec![word_hash("rice"), word_hash("potato")]), sorted);
+    }
+
+    #[test]
+    fn test_checkpointed_runs() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let checkpoint_dir = format!("{}.checkpoint", temp_file_handler.temp_file_path);
+        let mut sorter = ExternalSorter::checkpointed(&checkpoint_dir, 4096, None).unwrap();
+        sorter.push(word_hash("potato")).unwrap();
+        sorter.flush().unwrap();
+        sorter.push(word_hash("rice")).unwrap();
+        sorter.flush().unwrap();
+        // Dropped as if generation was interrupted, the runs stay for resuming
+        drop(sorter);
+
+        // Resuming from a checkpoint taken after the first run drops the second
+        let mut sorter = ExternalSorter::checkpointed(&checkpoint_dir, 4096, Some(1)).unwrap();
+        assert_eq!(1, sorter.run_count());
+        sorter.push(word_hash("salad")).unwrap();
+        let sorted = sorter
+            .sorted()
+            .unwrap()
+            .collect::<Result<Vec<WordHash>, String>>();
+        assert_eq!(Ok(vec![word_hash("salad"), word_hash("potato")]), sorted);
+        sorter.set_keep_on_drop(false);
+        drop(sorter);
+        assert!(!path::Path::new(&checkpoint_dir).exists());
+
+        assert!(ExternalSorter::checkpointed(&checkpoint_dir, 4096, Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_cleanup_on_failure() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_path = &temp_file_handler.temp_file_path;
+        let mut sorter = ExternalSorter::new(output_path, MIN_MEMORY_LIMIT).unwrap();
+        let temp_dir = sorter.temp_dir.clone();
+        let long_word = "a".repeat(MIN_MEMORY_LIMIT);
+        sorter.push(word_hash(&long_word)).unwrap();
+        // A run that cannot be read back, as if the disk had been tampered with
+        fs::write(
+            temp_dir.join(format!("{}0", RUN_FILE_PREFIX)),
+            "not a run\n",
+        )
+        .unwrap();
+        let sorted = sorter
+            .sorted()
+            .and_then(|sorted| sorted.collect::<Result<Vec<WordHash>, String>>());
+        assert!(sorted.is_err());
+        drop(sorter);
+        assert!(!temp_dir.exists());
+
+        assert!(ExternalSorter::new("/abc/defghi/jkl.qwerty", MIN_MEMORY_LIMIT).is_err());
+    }
+}
@@ -1,4 +1,23 @@
-use sha2::{Digest, Sha256};
+pub mod accounts;
+pub mod crypt;
+pub mod database;
+pub mod expression;
+pub mod htpasswd;
+pub mod identify;
+pub mod lm;
+pub mod slow;
+
+use blake2::digest::consts::{U16, U20, U28, U32, U48};
+use blake2::digest::VariableOutput;
+use blake2::{Blake2b, Blake2b512, Blake2bVar, Blake2s, Blake2s256, Blake2sVar};
+use hmac::{Hmac, Mac, SimpleHmac};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
 pub const HASH_DELIMITER: &str = ":";
 
@@ -8,12 +27,297 @@ pub struct WordHash {
     pub hash: String,
 }
 
-fn hash_word(word: &str) -> String {
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+    Sha3_224,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+    // The variable length BLAKE variants hold their output length in bytes
+    Blake2b(usize),
+    Blake2s(usize),
+    Blake3(usize),
+}
+
+impl Algorithm {
+    // Every algorithm, the BLAKE variants at the output length they default to when parsed
+    pub const ALL: [Algorithm; 11] = [
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Sha256,
+        Algorithm::Sha512,
+        Algorithm::Sha3_224,
+        Algorithm::Sha3_256,
+        Algorithm::Sha3_384,
+        Algorithm::Sha3_512,
+        Algorithm::Blake2b(64),
+        Algorithm::Blake2s(32),
+        Algorithm::Blake3(32),
+    ];
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Md5 => Md5::digest(data).to_vec(),
+            Algorithm::Sha1 => Sha1::digest(data).to_vec(),
+            Algorithm::Sha256 => Sha256::digest(data).to_vec(),
+            Algorithm::Sha512 => Sha512::digest(data).to_vec(),
+            Algorithm::Sha3_224 => Sha3_224::digest(data).to_vec(),
+            Algorithm::Sha3_256 => Sha3_256::digest(data).to_vec(),
+            Algorithm::Sha3_384 => Sha3_384::digest(data).to_vec(),
+            Algorithm::Sha3_512 => Sha3_512::digest(data).to_vec(),
+            Algorithm::Blake2b(output_length) => {
+                let mut output = vec![0; *output_length];
+                let mut hasher =
+                    Blake2bVar::new(*output_length).expect("output length checked when parsed");
+                blake2::digest::Update::update(&mut hasher, data);
+                hasher
+                    .finalize_variable(&mut output)
+                    .expect("output buffer has the requested length");
+                output
+            }
+            Algorithm::Blake2s(output_length) => {
+                let mut output = vec![0; *output_length];
+                let mut hasher =
+                    Blake2sVar::new(*output_length).expect("output length checked when parsed");
+                blake2::digest::Update::update(&mut hasher, data);
+                hasher
+                    .finalize_variable(&mut output)
+                    .expect("output buffer has the requested length");
+                output
+            }
+            Algorithm::Blake3(output_length) => {
+                let mut output = vec![0; *output_length];
+                let mut hasher = blake3::Hasher::new();
+                hasher.update(data);
+                hasher.finalize_xof().fill(&mut output);
+                output
+            }
+        }
+    }
+
+    pub fn hmac(&self, key: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+        /*
+            HMAC from the hmac crate. The BLAKE2 cores buffer lazily, so they need
+            SimpleHmac, which only has types for the standard output lengths.
+            None for everything else, BLAKE3 included as it has a keyed mode of its own
+        */
+        // HMAC accepts keys of any length, so `new_from_slice` cannot fail
+        fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(key)
+                .expect("HMAC can take a key of any size");
+            mac.update(data);
+            mac.finalize().into_bytes().to_vec()
+        }
+        let mac = match self {
+            Algorithm::Md5 => mac::<Hmac<Md5>>,
+            Algorithm::Sha1 => mac::<Hmac<Sha1>>,
+            Algorithm::Sha256 => mac::<Hmac<Sha256>>,
+            Algorithm::Sha512 => mac::<Hmac<Sha512>>,
+            Algorithm::Sha3_224 => mac::<Hmac<Sha3_224>>,
+            Algorithm::Sha3_256 => mac::<Hmac<Sha3_256>>,
+            Algorithm::Sha3_384 => mac::<Hmac<Sha3_384>>,
+            Algorithm::Sha3_512 => mac::<Hmac<Sha3_512>>,
+            Algorithm::Blake2b(20) => mac::<SimpleHmac<Blake2b<U20>>>,
+            Algorithm::Blake2b(32) => mac::<SimpleHmac<Blake2b<U32>>>,
+            Algorithm::Blake2b(48) => mac::<SimpleHmac<Blake2b<U48>>>,
+            Algorithm::Blake2b(64) => mac::<SimpleHmac<Blake2b512>>,
+            Algorithm::Blake2s(16) => mac::<SimpleHmac<Blake2s<U16>>>,
+            Algorithm::Blake2s(20) => mac::<SimpleHmac<Blake2s<U20>>>,
+            Algorithm::Blake2s(28) => mac::<SimpleHmac<Blake2s<U28>>>,
+            Algorithm::Blake2s(32) => mac::<SimpleHmac<Blake2s256>>,
+            Algorithm::Blake2b(_) | Algorithm::Blake2s(_) | Algorithm::Blake3(_) => return None,
+        };
+        Some(mac(key, data))
+    }
+}
+
+fn parse_output_length(
+    name: &str,
+    bits: Option<&str>,
+    default_bits: usize,
+    max_bits: usize,
+) -> Result<usize, String> {
+    let bits = match bits {
+        Some(bits) => bits
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid output length for {}, got: {}", name, bits))?,
+        None => default_bits,
+    };
+    if bits == 0 || !bits.is_multiple_of(8) || bits > max_bits {
+        return Err(format!(
+            "Output length for {} must be a multiple of 8 between 8 and {} bits, got: {}",
+            name, max_bits, bits
+        ));
+    }
+    Ok(bits / 8)
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /*
+            Names use underscores so they can double as hash expression functions,
+            but hyphens are accepted too. BLAKE variants take an optional output
+            length in bits, e.g. blake2b_256
+        */
+        let name = s.to_lowercase().replace('-', "_");
+        let (family, bits) = match name.split_once('_') {
+            Some((family, bits)) if family.starts_with("blake") => (family, Some(bits)),
+            _ => (name.as_str(), None),
+        };
+        match family {
+            "md5" => Ok(Algorithm::Md5),
+            "sha1" => Ok(Algorithm::Sha1),
+            "sha256" => Ok(Algorithm::Sha256),
+            "sha512" => Ok(Algorithm::Sha512),
+            "sha3_224" => Ok(Algorithm::Sha3_224),
+            "sha3_256" => Ok(Algorithm::Sha3_256),
+            "sha3_384" => Ok(Algorithm::Sha3_384),
+            "sha3_512" => Ok(Algorithm::Sha3_512),
+            "blake2b" => Ok(Algorithm::Blake2b(parse_output_length(s, bits, 512, 512)?)),
+            "blake2s" => Ok(Algorithm::Blake2s(parse_output_length(s, bits, 256, 256)?)),
+            // BLAKE3 is an XOF, the cap only guards against absurd lengths
+            "blake3" => Ok(Algorithm::Blake3(parse_output_length(s, bits, 256, 8192)?)),
+            _ => Err(format!("Unsupported algorithm, got: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::Md5 => write!(f, "md5"),
+            Algorithm::Sha1 => write!(f, "sha1"),
+            Algorithm::Sha256 => write!(f, "sha256"),
+            Algorithm::Sha512 => write!(f, "sha512"),
+            Algorithm::Sha3_224 => write!(f, "sha3_224"),
+            Algorithm::Sha3_256 => write!(f, "sha3_256"),
+            Algorithm::Sha3_384 => write!(f, "sha3_384"),
+            Algorithm::Sha3_512 => write!(f, "sha3_512"),
+            Algorithm::Blake2b(output_length) => write!(f, "blake2b_{}", output_length * 8),
+            Algorithm::Blake2s(output_length) => write!(f, "blake2s_{}", output_length * 8),
+            Algorithm::Blake3(output_length) => write!(f, "blake3_{}", output_length * 8),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HmacKey {
+    pub algorithm: Algorithm,
+    pub key: Vec<u8>,
+}
+
+impl HmacKey {
+    pub fn new(algorithm: Algorithm, key: Vec<u8>) -> Result<HmacKey, String> {
+        if algorithm.hmac(&key, b"").is_none() {
+            return Err(format!(
+                "HMAC is not supported with {}, only with md5, sha1, sha256, sha512, sha3, \
+                blake2b_160/256/384/512 and blake2s_128/160/224/256",
+                algorithm
+            ));
+        }
+        Ok(HmacKey { algorithm, key })
+    }
+
+    pub fn from_hex(algorithm: Algorithm, hex_key: &str) -> Result<HmacKey, String> {
+        HmacKey::new(algorithm, decode_hex(hex_key)?)
+    }
+
+    pub fn hash(&self, word: &str) -> String {
+        let mac = self
+            .algorithm
+            .hmac(&self.key, word.as_bytes())
+            .expect("HMAC support checked when the key was made");
+        encode_hex(&mac)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SaltPosition {
+    // sha256(salt || password)
+    Prefix,
+    // sha256(password || salt)
+    Suffix,
+}
+
+impl FromStr for SaltPosition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "prefix" => Ok(SaltPosition::Prefix),
+            "suffix" => Ok(SaltPosition::Suffix),
+            _ => Err(format!(
+                "Invalid salt position, expected 'prefix' or 'suffix', got: {}",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Salt {
+    pub value: String,
+    pub position: SaltPosition,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SaltedHash {
+    pub hash: String,
+    pub salt: String,
+}
+
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid hex string, got: {}", hex);
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+pub fn hash_word(word: &str) -> String {
     let mut hash = Sha256::new();
     hash.update(word);
     format!("{:x}", hash.finalize())
 }
 
+pub fn hash_salted_word(word: &str, salt: &Salt) -> String {
+    let salted_word = match salt.position {
+        SaltPosition::Prefix => format!("{}{}", salt.value, word),
+        SaltPosition::Suffix => format!("{}{}", word, salt.value),
+    };
+    hash_word(&salted_word)
+}
+
+pub fn parse_salted_hash(salted_hash: &str) -> Result<SaltedHash, String> {
+    /*
+        Parse a `hash:salt` string. Only the first HASH_DELIMITER is used to split,
+        so the salt itself is allowed to contain the delimiter
+    */
+    match salted_hash.split_once(HASH_DELIMITER) {
+        Some((hash, salt)) if !hash.is_empty() => Ok(SaltedHash {
+            hash: String::from(hash),
+            salt: String::from(salt),
+        }),
+        _ => Err(format!(
+            "Invalid salted hash, expected hash{}salt, got: {}",
+            HASH_DELIMITER, salted_hash
+        )),
+    }
+}
+
 fn hash_word_vec(word_vec: Vec<String>) -> Vec<WordHash> {
     let mut hash_vec: Vec<WordHash> = Vec::new();
     for word in word_vec {
@@ -34,6 +338,18 @@ fn generate_hash_str(word_hash: WordHash) -> String {
     hash_str
 }
 
+pub fn split_serialized_hash(serialized_hash: &str) -> Option<WordHash> {
+    /*
+        Split a line produced by the serialize functions back into its word and
+        hash. Words may contain HASH_DELIMITER themselves, hashes never do
+    */
+    let (word, hash) = serialized_hash.rsplit_once(HASH_DELIMITER)?;
+    Some(WordHash {
+        word: String::from(word),
+        hash: String::from(hash),
+    })
+}
+
 fn deserialize_single_hash(serialized_hash: String) -> Result<WordHash, String> {
     let split_vec = serialized_hash.split(HASH_DELIMITER).collect::<Vec<&str>>();
     if split_vec.len() != 2 {
@@ -57,6 +373,65 @@ pub fn serialize_hashes(word_vec: Vec<String>) -> Vec<String> {
     serialized_hashes
 }
 
+pub fn serialize_salted_hashes(word_vec: Vec<String>, salt: &Salt) -> Vec<String> {
+    /*
+        Same as `serialize_hashes`, but every word is hashed with the same global salt.
+        The unsalted word is what gets written to the table
+    */
+    word_vec
+        .into_iter()
+        .map(|word| {
+            let hash = hash_salted_word(&word, salt);
+            generate_hash_str(WordHash { word, hash })
+        })
+        .collect()
+}
+
+pub fn serialize_hmac_hashes(word_vec: Vec<String>, hmac_key: &HmacKey) -> Vec<String> {
+    word_vec
+        .into_iter()
+        .map(|word| {
+            let hash = hmac_key.hash(&word);
+            generate_hash_str(WordHash { word, hash })
+        })
+        .collect()
+}
+
+pub fn serialize_pipeline_hashes(
+    word_vec: Vec<String>,
+    pipeline: &expression::Pipeline,
+    salt: Option<&str>,
+) -> Vec<String> {
+    word_vec
+        .into_iter()
+        .map(|word| {
+            let hash = pipeline.hash(&word, salt);
+            generate_hash_str(WordHash { word, hash })
+        })
+        .collect()
+}
+
+pub fn serialize_lm_hashes(word_vec: Vec<String>) -> Vec<String> {
+    /*
+        LM tables hold password halves rather than whole words: every word is
+        uppercased and split into its 7 character halves, and each distinct half
+        is written once with its LM half hash
+    */
+    let mut seen_halves: HashSet<String> = HashSet::new();
+    let mut serialized_hashes: Vec<String> = Vec::new();
+    for word in word_vec {
+        let (first_half, second_half) = lm::lm_halves(&word);
+        for half in [first_half, second_half] {
+            if half.is_empty() || !seen_halves.insert(half.clone()) {
+                continue;
+            }
+            let hash = lm::lm_half_hash(&half);
+            serialized_hashes.push(generate_hash_str(WordHash { word: half, hash }));
+        }
+    }
+    serialized_hashes
+}
+
 pub fn deserialize_hashes(serialized_hashes: Vec<String>) -> Result<Vec<WordHash>, String> {
     let mut deserialized_hashes: Vec<WordHash> = Vec::new();
     for serialized_hash in serialized_hashes {
@@ -146,4 +521,188 @@ mod tests {
         let deserialized_hash = deserialize_single_hash(invalid_str.to_string());
         assert!(deserialized_hash.is_err());
     }
+
+    #[test]
+    fn test_hash_salted_word() {
+        let salt = Salt {
+            value: "salt".to_string(),
+            position: SaltPosition::Prefix,
+        };
+        let expected_hash = "93181c2a53fd65c1d7da3a86554dd44c64a4150c03e25634f15306626cf973c9";
+        assert_eq!(expected_hash, hash_salted_word("pepper", &salt));
+
+        let salt = Salt {
+            value: "abc".to_string(),
+            position: SaltPosition::Suffix,
+        };
+        let expected_hash = "6733b7ffeace4887c3b31258079c780d8db3018db9cbc05c500df3521f968df8";
+        assert_eq!(expected_hash, hash_salted_word("password", &salt));
+    }
+
+    #[test]
+    fn test_parse_salted_hash() {
+        let expected = SaltedHash {
+            hash: "c5ae5f176fadad3c9fe337ac7d4846b2603faffc66dfa47295d638021671a547".to_string(),
+            salt: "a:bc".to_string(),
+        };
+        let parsed = parse_salted_hash(
+            "c5ae5f176fadad3c9fe337ac7d4846b2603faffc66dfa47295d638021671a547:a:bc",
+        );
+        assert_eq!(Ok(expected), parsed);
+
+        assert!(parse_salted_hash("nosalt").is_err());
+        assert!(parse_salted_hash(":salt").is_err());
+    }
+
+    #[test]
+    fn test_algorithm_digest() {
+        let cases = [
+            (Algorithm::Md5, "5f4dcc3b5aa765d61d8327deb882cf99"),
+            (Algorithm::Sha1, "5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8"),
+            (
+                Algorithm::Sha256,
+                "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+            ),
+            (
+                Algorithm::Sha512,
+                "b109f3bbbc244eb82441917ed06d618b9008dd09b3befd1b5e07394c706a8bb980b1d7785e5976ec049b46df5f1326af5a2ea6d103fd07c95385ffab0cacbc86",
+            ),
+        ];
+        for (algorithm, expected_hash) in cases {
+            assert_eq!(expected_hash, encode_hex(&algorithm.digest(b"password")));
+        }
+    }
+
+    #[test]
+    fn test_algorithm_digest_sha3_blake() {
+        let cases = [
+            ("sha3_224", "c3f847612c3780385a859a1993dfd9fe7c4e6d7f477148e527e9374c"),
+            (
+                "sha3_256",
+                "c0067d4af4e87f00dbac63b6156828237059172d1bbeac67427345d6a9fda484",
+            ),
+            (
+                "sha3-384",
+                "9c1565e99afa2ce7800e96a73c125363c06697c5674d59f227b3368fd00b85ead506eefa90702673d873cb2c9357eafc",
+            ),
+            (
+                "sha3_512",
+                "e9a75486736a550af4fea861e2378305c4a555a05094dee1dca2f68afea49cc3a50e8de6ea131ea521311f4d6fb054a146e8282f8e35ff2e6368c1a62e909716",
+            ),
+            (
+                "blake2b",
+                "7c863950ac93c93692995e4732ce1e1466ad74a775352ffbaaf2a4a4ce9b549d0b414a1f3150452be6c7c72c694a7cb46f76452917298d33e67611f0a42addb8",
+            ),
+            (
+                "blake2b_256",
+                "344b8a854221bd1eaf9382daaea1996fbcd496f158e983f8835c7ef5084c55bb",
+            ),
+            (
+                "blake2s",
+                "4c81099df884bd6e14a639d648bccd808512e48af211ae4f44d545ea6d5e5f2b",
+            ),
+            ("blake2s-128", "66dbe857cca5cc0abd3441c4838b09f6"),
+        ];
+        for (algorithm, expected_hash) in cases {
+            let algorithm = algorithm.parse::<Algorithm>().unwrap();
+            assert_eq!(expected_hash, encode_hex(&algorithm.digest(b"password")));
+        }
+
+        // Official BLAKE3 test vectors, plus the XOF extension which shares its prefix
+        let blake3 = "blake3".parse::<Algorithm>().unwrap();
+        assert_eq!(
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            encode_hex(&blake3.digest(b""))
+        );
+        assert_eq!(
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            encode_hex(&blake3.digest(b"abc"))
+        );
+        let blake3_512 = "blake3_512".parse::<Algorithm>().unwrap();
+        assert!(encode_hex(&blake3_512.digest(b"abc"))
+            .starts_with("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"));
+    }
+
+    #[test]
+    fn test_algorithm_from_str() {
+        assert_eq!(Ok(Algorithm::Blake2b(64)), "blake2b".parse());
+        assert_eq!(Ok(Algorithm::Blake3(16)), "BLAKE3-128".parse());
+        assert_eq!(
+            "blake2s_256",
+            "blake2s".parse::<Algorithm>().unwrap().to_string()
+        );
+        assert!("blake2b_1024".parse::<Algorithm>().is_err());
+        assert!("blake2s_12".parse::<Algorithm>().is_err());
+        assert!("blake2b_abc".parse::<Algorithm>().is_err());
+        assert!("sha3".parse::<Algorithm>().is_err());
+        // The BLAKE variants are listed at their default length, so their family name parses to them
+        for algorithm in Algorithm::ALL {
+            let name = algorithm.to_string();
+            let family = match name.split_once('_') {
+                Some((family, _)) if family.starts_with("blake") => family,
+                _ => &name,
+            };
+            assert_eq!(Ok(algorithm), family.parse());
+        }
+    }
+
+    #[test]
+    fn test_hmac_key_hash() {
+        // RFC 2104 and RFC 4231 test vectors, with the key "Jefe"
+        let cases = [
+            (Algorithm::Md5, "750c783e6ab0b503eaa86e310a5db738"),
+            (Algorithm::Sha1, "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"),
+            (
+                Algorithm::Sha256,
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                Algorithm::Sha512,
+                "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+            ),
+        ];
+        for (algorithm, expected_hash) in cases {
+            let hmac_key = HmacKey::from_hex(algorithm, "4a656665").unwrap();
+            assert_eq!(expected_hash, hmac_key.hash("what do ya want for nothing?"));
+        }
+
+        // Same key and message, checked against Python's hmac module
+        let hmac_key = HmacKey::from_hex(Algorithm::Sha3_256, "4a656665").unwrap();
+        assert_eq!(
+            "c7d4072e788877ae3596bbb0da73b887c9171f93095b294ae857fbe2645e1ba5",
+            hmac_key.hash("what do ya want for nothing?")
+        );
+        let hmac_key = HmacKey::from_hex(Algorithm::Blake2b(64), "4a656665").unwrap();
+        assert_eq!(
+            "6ff884f8ddc2a6586b3c98a4cd6ebdf14ec10204b6710073eb5865ade37a2643b8807c1335d107ecdb9ffeaeb6828c4625ba172c66379efcd222c2de11727ab4",
+            hmac_key.hash("what do ya want for nothing?")
+        );
+
+        // The hmac crate has no type for other BLAKE2 lengths, and BLAKE3 has its own keyed mode
+        assert!(HmacKey::from_hex(Algorithm::Blake2b(24), "4a656665").is_err());
+        assert!(HmacKey::from_hex(Algorithm::Blake3(32), "4a656665").is_err());
+
+        // Keys longer than the block size are hashed first
+        let long_key = "aa".repeat(131);
+        let hmac_key = HmacKey::from_hex(Algorithm::Sha256, &long_key).unwrap();
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            hmac_key.hash("Test Using Larger Than Block-Size Key - Hash Key First")
+        );
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(Ok(vec![0x4a, 0x65, 0x66, 0x65]), decode_hex("4A656665"));
+        assert_eq!(Ok(vec![]), decode_hex(""));
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_salt_position_from_str() {
+        assert_eq!(Ok(SaltPosition::Prefix), "prefix".parse());
+        assert_eq!(Ok(SaltPosition::Suffix), "Suffix".parse());
+        assert!("middle".parse::<SaltPosition>().is_err());
+    }
 }
@@ -0,0 +1,200 @@
+use super::crypt;
+use std::str::FromStr;
+
+const ACCOUNT_DELIMITER: char = ':';
+// pwdump writes this instead of a hash for accounts without one
+const PWDUMP_NO_PASSWORD: &str = "NO PASSWORD";
+// In hex characters
+const NTLM_HASH_LENGTH: usize = 32;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AccountFormat {
+    // account:hash, where the hash is anything `attack` accepts, including hash:salt
+    UserHash,
+    // /etc/shadow
+    Shadow,
+    // Apache htpasswd: account:hash with apr1, {SHA}, bcrypt or DES crypt hashes
+    Htpasswd,
+    // account:rid:lm:ntlm::: as written by pwdump and secretsdump
+    Pwdump,
+}
+
+impl FromStr for AccountFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "user-hash" => Ok(AccountFormat::UserHash),
+            "shadow" => Ok(AccountFormat::Shadow),
+            "htpasswd" => Ok(AccountFormat::Htpasswd),
+            "pwdump" => Ok(AccountFormat::Pwdump),
+            _ => Err(format!(
+                "Invalid account format, expected user-hash, shadow, htpasswd or pwdump, got: {}",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct AccountHash {
+    pub account: String,
+    // Left unparsed, what the hash means depends on the format it was read from
+    pub hash: String,
+}
+
+fn parse_pwdump_line(line: &str) -> Result<Option<AccountHash>, String> {
+    /*
+        Only the NTLM hash is kept, since it is case sensitive and not split in
+        halves. Machine accounts and accounts without a password are skipped
+    */
+    let fields = line.split(ACCOUNT_DELIMITER).collect::<Vec<&str>>();
+    if fields.len() < 4 || fields[0].is_empty() {
+        return Err(format!("Invalid pwdump line, got: {}", line));
+    }
+    let ntlm_hash = fields[3];
+    if ntlm_hash.starts_with(PWDUMP_NO_PASSWORD) || fields[0].ends_with('$') {
+        return Ok(None);
+    }
+    if ntlm_hash.len() != NTLM_HASH_LENGTH || !ntlm_hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid NTLM hash for account {}, got: {}",
+            fields[0], ntlm_hash
+        ));
+    }
+    Ok(Some(AccountHash {
+        account: String::from(fields[0]),
+        hash: ntlm_hash.to_lowercase(),
+    }))
+}
+
+pub fn parse_account_line(
+    line: &str,
+    format: AccountFormat,
+) -> Result<Option<AccountHash>, String> {
+    /*
+        Parse one line of an account list. Returns None for blank lines and
+        accounts that have nothing to crack
+    */
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    match format {
+        AccountFormat::Shadow => {
+            Ok(
+                crypt::parse_shadow_line(line)?.map(|shadow_entry| AccountHash {
+                    account: shadow_entry.account,
+                    hash: shadow_entry.hash,
+                }),
+            )
+        }
+        AccountFormat::Pwdump => parse_pwdump_line(line),
+        AccountFormat::UserHash | AccountFormat::Htpasswd => {
+            match line.split_once(ACCOUNT_DELIMITER) {
+                Some((account, hash)) if !account.is_empty() && !hash.is_empty() => {
+                    Ok(Some(AccountHash {
+                        account: String::from(account),
+                        hash: String::from(hash.trim()),
+                    }))
+                }
+                _ => Err(format!(
+                    "Invalid account line, expected account{}hash, got: {}",
+                    ACCOUNT_DELIMITER, line
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_hash(account: &str, hash: &str) -> Option<AccountHash> {
+        Some(AccountHash {
+            account: account.to_string(),
+            hash: hash.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_account_format_from_str() {
+        assert_eq!(Ok(AccountFormat::Pwdump), "PWDUMP".parse::<AccountFormat>());
+        assert_eq!(
+            Ok(AccountFormat::UserHash),
+            "user-hash".parse::<AccountFormat>()
+        );
+        assert!("passwd".parse::<AccountFormat>().is_err());
+    }
+
+    #[test]
+    fn test_parse_user_hash_line() {
+        assert_eq!(
+            Ok(account_hash("alice", "5f4dcc3b5aa765d61d8327deb882cf99")),
+            parse_account_line(
+                "alice:5f4dcc3b5aa765d61d8327deb882cf99",
+                AccountFormat::UserHash
+            )
+        );
+        // Everything after the account is the hash, so salted hashes keep their salt
+        assert_eq!(
+            Ok(account_hash("bob", "abc:salt")),
+            parse_account_line("bob:abc:salt", AccountFormat::UserHash)
+        );
+        assert_eq!(
+            Ok(account_hash(
+                "carol",
+                "$apr1$r31abcde$ouL8QL9v/FwrkrtBccxbL."
+            )),
+            parse_account_line(
+                "carol:$apr1$r31abcde$ouL8QL9v/FwrkrtBccxbL.",
+                AccountFormat::Htpasswd
+            )
+        );
+        assert_eq!(Ok(None), parse_account_line("  ", AccountFormat::UserHash));
+        assert!(parse_account_line("alice", AccountFormat::UserHash).is_err());
+        assert!(parse_account_line("alice:", AccountFormat::Htpasswd).is_err());
+    }
+
+    #[test]
+    fn test_parse_shadow_account_line() {
+        assert_eq!(
+            Ok(account_hash("root", "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/")),
+            parse_account_line(
+                "root:$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/:19000:0:99999:7:::",
+                AccountFormat::Shadow
+            )
+        );
+        assert_eq!(
+            Ok(None),
+            parse_account_line("daemon:*:19000:0:99999:7:::", AccountFormat::Shadow)
+        );
+    }
+
+    #[test]
+    fn test_parse_pwdump_line() {
+        assert_eq!(
+            Ok(account_hash("Administrator", "8846f7eaee8fb117ad06bdd830b7586c")),
+            parse_account_line(
+                "Administrator:500:E52CAC67419A9A224A3B108F3FA6CB6D:8846F7EAEE8FB117AD06BDD830B7586C:::",
+                AccountFormat::Pwdump
+            )
+        );
+        assert_eq!(
+            Ok(None),
+            parse_account_line(
+                "Guest:501:NO PASSWORD*********************:NO PASSWORD*********************:::",
+                AccountFormat::Pwdump
+            )
+        );
+        assert_eq!(
+            Ok(None),
+            parse_account_line(
+                "DC01$:1000:aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0:::",
+                AccountFormat::Pwdump
+            )
+        );
+        assert!(parse_account_line("Administrator:500:abc", AccountFormat::Pwdump).is_err());
+        assert!(parse_account_line("Administrator:500:abc:xyz:::", AccountFormat::Pwdump).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use pwhash::{md5_crypt, sha256_crypt, sha512_crypt};
+use std::fmt;
+
+const ROUNDS_PREFIX: &str = "rounds=";
+const SHADOW_DELIMITER: char = ':';
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum CryptScheme {
+    // $1$
+    Md5Crypt,
+    // $5$
+    Sha256Crypt,
+    // $6$
+    Sha512Crypt,
+}
+
+impl CryptScheme {
+    fn from_id(id: &str) -> Option<CryptScheme> {
+        match id {
+            "1" => Some(CryptScheme::Md5Crypt),
+            "5" => Some(CryptScheme::Sha256Crypt),
+            "6" => Some(CryptScheme::Sha512Crypt),
+            _ => None,
+        }
+    }
+
+    pub fn default_rounds(&self) -> u32 {
+        match self {
+            CryptScheme::Md5Crypt => 1000,
+            CryptScheme::Sha256Crypt | CryptScheme::Sha512Crypt => 5000,
+        }
+    }
+}
+
+impl fmt::Display for CryptScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CryptScheme::Md5Crypt => "md5crypt",
+            CryptScheme::Sha256Crypt => "sha256crypt",
+            CryptScheme::Sha512Crypt => "sha512crypt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CryptHash {
+    pub scheme: CryptScheme,
+    // Only set when the hash explicitly specifies `rounds=N`
+    pub rounds: Option<u32>,
+    pub salt: String,
+    pub hash: String,
+    // The full crypt string, which is also what verification runs against
+    pub raw: String,
+}
+
+impl CryptHash {
+    pub fn verify(&self, word: &str) -> bool {
+        match self.scheme {
+            CryptScheme::Md5Crypt => md5_crypt::verify(word, &self.raw),
+            CryptScheme::Sha256Crypt => sha256_crypt::verify(word, &self.raw),
+            CryptScheme::Sha512Crypt => sha512_crypt::verify(word, &self.raw),
+        }
+    }
+
+    pub fn effective_rounds(&self) -> u32 {
+        self.rounds.unwrap_or_else(|| self.scheme.default_rounds())
+    }
+}
+
+pub fn parse_crypt_hash(crypt_hash: &str) -> Result<CryptHash, String> {
+    /*
+        Parse a modular crypt string: $id$salt$hash or $id$rounds=N$salt$hash
+    */
+    let invalid = || format!("Invalid crypt hash, got: {}", crypt_hash);
+    let fields = crypt_hash.split('$').collect::<Vec<&str>>();
+    if fields.len() < 4 || !fields[0].is_empty() {
+        return Err(invalid());
+    }
+    let scheme = CryptScheme::from_id(fields[1]).ok_or_else(|| {
+        format!(
+            "Unsupported crypt scheme ${}$ in hash: {}",
+            fields[1], crypt_hash
+        )
+    })?;
+
+    let (rounds, salt, hash) = match fields[2].strip_prefix(ROUNDS_PREFIX) {
+        Some(rounds) if scheme != CryptScheme::Md5Crypt && fields.len() == 5 => {
+            let rounds = rounds.parse::<u32>().map_err(|_| invalid())?;
+            (Some(rounds), fields[3], fields[4])
+        }
+        _ if fields.len() == 4 => (None, fields[2], fields[3]),
+        _ => return Err(invalid()),
+    };
+    if hash.is_empty() {
+        return Err(invalid());
+    }
+    Ok(CryptHash {
+        scheme,
+        rounds,
+        salt: String::from(salt),
+        hash: String::from(hash),
+        raw: String::from(crypt_hash),
+    })
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ShadowEntry {
+    pub account: String,
+    // Left unparsed, since shadow files can also hold schemes other than crypt(3)
+    pub hash: String,
+}
+
+pub fn parse_shadow_line(line: &str) -> Result<Option<ShadowEntry>, String> {
+    /*
+        Parse a line from /etc/shadow. Returns None for accounts that have no
+        crackable password, such as `*`, `!!` or an empty password field.
+        Locked accounts (`!` in front of the hash) still have a crackable hash
+    */
+    let mut fields = line.split(SHADOW_DELIMITER);
+    let account = match fields.next() {
+        Some(account) if !account.is_empty() => account,
+        _ => return Err(format!("Invalid shadow line, got: {}", line)),
+    };
+    let password_field = match fields.next() {
+        Some(password_field) => password_field.trim_start_matches('!'),
+        None => return Err(format!("Invalid shadow line, got: {}", line)),
+    };
+    if !password_field.starts_with('$') {
+        return Ok(None);
+    }
+    Ok(Some(ShadowEntry {
+        account: String::from(account),
+        hash: String::from(password_field),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD5_CRYPT_HASH: &str = "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/";
+    const SHA256_CRYPT_HASH: &str = "$5$saltsalt$gOjOtoMpVhru2uyjeJSEc/JaLQWOXMNmlOnj6T4AtC.";
+    const SHA512_CRYPT_HASH: &str = "$6$saltsalt$qFmFH.bQmmtXzyBY0s9v7Oicd2z4XSIecDzlB5KiA2/jctKu9YterLp8wwnSq.qc.eoxqOmSuNp2xS0ktL3nh/";
+    const SHA512_CRYPT_ROUNDS_HASH: &str = "$6$rounds=1000$abc$cdoAGQGxUujH1PHBiAw35gXAeIHP01NR2wd/ZbzMqNc.Ga9Wv2rR7SJljziPkfho8k85LucFIl5hWKb5KJ7VP/";
+
+    #[test]
+    fn test_parse_crypt_hash() {
+        let crypt_hash = parse_crypt_hash(MD5_CRYPT_HASH).unwrap();
+        assert_eq!(CryptScheme::Md5Crypt, crypt_hash.scheme);
+        assert_eq!(None, crypt_hash.rounds);
+        assert_eq!("saltsalt", crypt_hash.salt);
+        assert_eq!("qjXMvbEw8oaL.CzflDtaK/", crypt_hash.hash);
+        assert_eq!(1000, crypt_hash.effective_rounds());
+
+        let crypt_hash = parse_crypt_hash(SHA512_CRYPT_ROUNDS_HASH).unwrap();
+        assert_eq!(CryptScheme::Sha512Crypt, crypt_hash.scheme);
+        assert_eq!(Some(1000), crypt_hash.rounds);
+        assert_eq!("abc", crypt_hash.salt);
+    }
+
+    #[test]
+    fn test_parse_crypt_hash_invalid() {
+        assert!(parse_crypt_hash("abc").is_err());
+        assert!(parse_crypt_hash("$2$saltsalt$abc").is_err());
+        assert!(parse_crypt_hash("$6$saltsalt$").is_err());
+        assert!(parse_crypt_hash("$6$rounds=abc$salt$hash").is_err());
+        assert!(parse_crypt_hash("$1$rounds=1000$salt$hash").is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        for crypt_hash in [MD5_CRYPT_HASH, SHA256_CRYPT_HASH, SHA512_CRYPT_HASH] {
+            let crypt_hash = parse_crypt_hash(crypt_hash).unwrap();
+            assert!(crypt_hash.verify("password"));
+            assert!(!crypt_hash.verify("Password"));
+        }
+        let crypt_hash = parse_crypt_hash(SHA512_CRYPT_ROUNDS_HASH).unwrap();
+        assert!(crypt_hash.verify("dragon"));
+    }
+
+    #[test]
+    fn test_parse_shadow_line() {
+        let line = format!("root:{}:19000:0:99999:7:::", SHA256_CRYPT_HASH);
+        let expected_entry = ShadowEntry {
+            account: "root".to_string(),
+            hash: SHA256_CRYPT_HASH.to_string(),
+        };
+        assert_eq!(Ok(Some(expected_entry.clone())), parse_shadow_line(&line));
+
+        // Locked accounts can still be cracked
+        let line = format!("root:!{}:19000:0:99999:7:::", SHA256_CRYPT_HASH);
+        assert_eq!(Ok(Some(expected_entry)), parse_shadow_line(&line));
+
+        assert_eq!(Ok(None), parse_shadow_line("daemon:*:19000:0:99999:7:::"));
+        assert_eq!(Ok(None), parse_shadow_line("nobody:!!:19000::::::"));
+        assert!(parse_shadow_line("garbage").is_err());
+    }
+}
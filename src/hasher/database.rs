@@ -0,0 +1,279 @@
+use super::{decode_hex, Algorithm, HASH_DELIMITER};
+use std::fmt;
+
+const MYSQL_PREFIX: &str = "*";
+const POSTGRES_PREFIX: &str = "md5";
+const MSSQL_2012_PREFIX: &str = "0x0200";
+const ORACLE_11G_PREFIX: &str = "S:";
+// 12c and later append further verifiers such as `;T:...` after the 11g one
+const ORACLE_VERIFIER_DELIMITER: char = ';';
+const MSSQL_2012_SALT_LENGTH: usize = 4;
+const ORACLE_11G_SALT_LENGTH: usize = 10;
+// Column delimiters used by `mysql -B`, `psql -A` and `user:hash` style exports
+const DUMP_DELIMITERS: [char; 3] = [':', '\t', '|'];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DatabaseScheme {
+    // *HEX(sha1(sha1_raw(p)))
+    MySql,
+    // md5 + hex(md5(p + username))
+    Postgres,
+    // 0x0200 + salt + HEX(sha512(utf16le(p) + salt))
+    Mssql2012,
+    // S: + HEX(sha1(p + salt)) + salt
+    Oracle11g,
+}
+
+impl fmt::Display for DatabaseScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DatabaseScheme::MySql => "mysql",
+            DatabaseScheme::Postgres => "postgres-md5",
+            DatabaseScheme::Mssql2012 => "mssql2012",
+            DatabaseScheme::Oracle11g => "oracle11g",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DatabaseHash {
+    pub scheme: DatabaseScheme,
+    // Raw salt bytes. For PostgreSQL this is the username
+    pub salt: Vec<u8>,
+    pub digest: Vec<u8>,
+    // The hash as it appears in the dump, which is also how it is reported
+    pub raw: String,
+}
+
+impl DatabaseHash {
+    pub fn compute(&self, word: &str) -> Vec<u8> {
+        match self.scheme {
+            DatabaseScheme::MySql => {
+                Algorithm::Sha1.digest(&Algorithm::Sha1.digest(word.as_bytes()))
+            }
+            DatabaseScheme::Postgres => {
+                Algorithm::Md5.digest(&[word.as_bytes(), &self.salt].concat())
+            }
+            DatabaseScheme::Mssql2012 => {
+                let mut input: Vec<u8> = word.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                input.extend(&self.salt);
+                Algorithm::Sha512.digest(&input)
+            }
+            DatabaseScheme::Oracle11g => {
+                Algorithm::Sha1.digest(&[word.as_bytes(), &self.salt].concat())
+            }
+        }
+    }
+
+    pub fn verify(&self, word: &str) -> bool {
+        self.compute(word) == self.digest
+    }
+}
+
+fn decode_digest(
+    hex: &str,
+    length: usize,
+    raw: &str,
+    scheme: DatabaseScheme,
+) -> Result<Vec<u8>, String> {
+    match decode_hex(hex) {
+        Ok(bytes) if bytes.len() == length => Ok(bytes),
+        _ => Err(format!("Invalid {} hash, got: {}", scheme, raw)),
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    // SQL Server tools print the 0x prefix in either case
+    match s.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => Some(&s[prefix.len()..]),
+        _ => None,
+    }
+}
+
+pub fn parse_database_hash(hash: &str, account: Option<&str>) -> Result<DatabaseHash, String> {
+    /*
+        Parse a database password hash as stored by the server. The account is
+        only needed for PostgreSQL, which salts the hash with the username
+    */
+    let (scheme, salt, digest) = if let Some(hex) = hash.strip_prefix(MYSQL_PREFIX) {
+        let scheme = DatabaseScheme::MySql;
+        (scheme, Vec::new(), decode_digest(hex, 20, hash, scheme)?)
+    } else if let Some(hex) = hash.strip_prefix(POSTGRES_PREFIX) {
+        let scheme = DatabaseScheme::Postgres;
+        let username = account.ok_or_else(|| {
+            format!(
+                "PostgreSQL md5 hashes are salted with the username, use the user:hash format, got: {}",
+                hash
+            )
+        })?;
+        let digest = decode_digest(hex, 16, hash, scheme)?;
+        (scheme, username.as_bytes().to_vec(), digest)
+    } else if let Some(hex) = strip_prefix_ignore_case(hash, MSSQL_2012_PREFIX) {
+        let scheme = DatabaseScheme::Mssql2012;
+        let bytes = decode_digest(hex, MSSQL_2012_SALT_LENGTH + 64, hash, scheme)?;
+        let (salt, digest) = bytes.split_at(MSSQL_2012_SALT_LENGTH);
+        (scheme, salt.to_vec(), digest.to_vec())
+    } else if let Some(verifiers) = hash.strip_prefix(ORACLE_11G_PREFIX) {
+        let scheme = DatabaseScheme::Oracle11g;
+        let verifier = verifiers
+            .split(ORACLE_VERIFIER_DELIMITER)
+            .next()
+            .unwrap_or_default();
+        let bytes = decode_digest(verifier, 20 + ORACLE_11G_SALT_LENGTH, hash, scheme)?;
+        let (digest, salt) = bytes.split_at(20);
+        (scheme, salt.to_vec(), digest.to_vec())
+    } else {
+        return Err(format!("Unsupported database hash, got: {}", hash));
+    };
+    Ok(DatabaseHash {
+        scheme,
+        salt,
+        digest,
+        raw: String::from(hash),
+    })
+}
+
+pub fn is_database_hash(hash: &str) -> bool {
+    // None of the prefixes are valid hex, so plain digests never match
+    [MYSQL_PREFIX, POSTGRES_PREFIX, ORACLE_11G_PREFIX]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+        || strip_prefix_ignore_case(hash, MSSQL_2012_PREFIX).is_some()
+}
+
+pub fn parse_database_target(target: &str) -> Result<DatabaseHash, String> {
+    /*
+        Parse a hash given on its own rather than in a dump. PostgreSQL hashes
+        then take the username in the hash:salt format, e.g. md5...:postgres
+    */
+    match target.rsplit_once(HASH_DELIMITER) {
+        Some((hash, username)) if hash.starts_with(POSTGRES_PREFIX) => {
+            parse_database_hash(hash, Some(username))
+        }
+        _ => parse_database_hash(target, None),
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct DatabaseEntry {
+    pub account: String,
+    pub hash: DatabaseHash,
+}
+
+pub fn parse_dump_line(line: &str) -> Result<DatabaseEntry, String> {
+    /*
+        Parse an `account<delimiter>hash` line as exported from the servers' user
+        tables, e.g. mysql.user, pg_shadow, sys.sql_logins or sys.user$ (spare4).
+        The delimiter can be ':', a tab or '|'
+    */
+    let (account, hash) = line
+        .split_once(&DUMP_DELIMITERS[..])
+        .ok_or_else(|| format!("Invalid database dump line, got: {}", line))?;
+    let account = account.trim();
+    if account.is_empty() {
+        return Err(format!("Invalid database dump line, got: {}", line));
+    }
+    let hash = parse_database_hash(hash.trim(), Some(account))
+        .map_err(|e| format!("Account {}: {}", account, e))?;
+    Ok(DatabaseEntry {
+        account: String::from(account),
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // All of these are for the password "password"
+    const MYSQL_HASH: &str = "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19";
+    // Salted with the username "postgres"
+    const POSTGRES_HASH: &str = "md532e12f215ba27cb750c9e093ce4b5127";
+    const MSSQL_2012_HASH: &str = "0x02006A4B3C2DB7D5B56441F0F1B249BB8621954AE271869D34840DC5875F63B4D9F976CC45F9FE2B5674F758E6FE11F6E9D65E7E0894EBB5196E1AF55B9F4169F2FE2A15989F";
+    const ORACLE_11G_HASH: &str = "S:8E4B6DE728AE6411A2E276E451BCEA6ABF04B81DD1A2B3C4E5F60718293A";
+
+    #[test]
+    fn test_parse_database_hash() {
+        let database_hash = parse_database_hash(MYSQL_HASH, None).unwrap();
+        assert_eq!(DatabaseScheme::MySql, database_hash.scheme);
+        assert!(database_hash.salt.is_empty());
+
+        let database_hash = parse_database_hash(POSTGRES_HASH, Some("postgres")).unwrap();
+        assert_eq!(DatabaseScheme::Postgres, database_hash.scheme);
+        assert_eq!(b"postgres".to_vec(), database_hash.salt);
+
+        let database_hash = parse_database_hash(MSSQL_2012_HASH, None).unwrap();
+        assert_eq!(DatabaseScheme::Mssql2012, database_hash.scheme);
+        assert_eq!(vec![0x6a, 0x4b, 0x3c, 0x2d], database_hash.salt);
+
+        // 12c verifiers after the 11g one are ignored
+        let oracle_12c_hash = format!("{};T:ABCDEF", ORACLE_11G_HASH);
+        let database_hash = parse_database_hash(&oracle_12c_hash, None).unwrap();
+        assert_eq!(DatabaseScheme::Oracle11g, database_hash.scheme);
+        assert_eq!(10, database_hash.salt.len());
+    }
+
+    #[test]
+    fn test_parse_database_hash_invalid() {
+        assert!(parse_database_hash("abc", None).is_err());
+        assert!(parse_database_hash("*2470C0C06DEE42FD", None).is_err());
+        assert!(parse_database_hash(POSTGRES_HASH, None).is_err());
+        assert!(parse_database_hash("0x0200ZZ", None).is_err());
+        assert!(parse_database_hash("S:8E4B6DE728AE6411", None).is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let cases = [
+            (MYSQL_HASH, None),
+            (POSTGRES_HASH, Some("postgres")),
+            (MSSQL_2012_HASH, None),
+            (ORACLE_11G_HASH, None),
+        ];
+        for (hash, account) in cases {
+            let database_hash = parse_database_hash(hash, account).unwrap();
+            assert!(database_hash.verify("password"), "{}", hash);
+            assert!(!database_hash.verify("Password"), "{}", hash);
+        }
+        // The username is part of the PostgreSQL salt
+        let database_hash = parse_database_hash(POSTGRES_HASH, Some("admin")).unwrap();
+        assert!(!database_hash.verify("password"));
+    }
+
+    #[test]
+    fn test_parse_database_target() {
+        let target = format!("{}:postgres", POSTGRES_HASH);
+        assert!(is_database_hash(&target));
+        assert!(parse_database_target(&target).unwrap().verify("password"));
+        assert!(parse_database_target(ORACLE_11G_HASH).is_ok());
+        assert!(parse_database_target(POSTGRES_HASH).is_err());
+
+        assert!(is_database_hash("0X0200ABCD"));
+        assert!(!is_database_hash("5f4dcc3b5aa765d61d8327deb882cf99"));
+        assert!(!is_database_hash("5f4dcc3b5aa765d61d8327deb882cf99:salt"));
+    }
+
+    #[test]
+    fn test_parse_dump_line() {
+        let entry = parse_dump_line(&format!("root\t{}", MYSQL_HASH)).unwrap();
+        assert_eq!("root", entry.account);
+        assert_eq!(DatabaseScheme::MySql, entry.hash.scheme);
+
+        let entry = parse_dump_line(&format!(" postgres | {}", POSTGRES_HASH)).unwrap();
+        assert_eq!("postgres", entry.account);
+        assert!(entry.hash.verify("password"));
+
+        let entry = parse_dump_line(&format!("sa:{}", MSSQL_2012_HASH)).unwrap();
+        assert_eq!(DatabaseScheme::Mssql2012, entry.hash.scheme);
+
+        // The Oracle verifier itself contains a ':'
+        let entry = parse_dump_line(&format!("SYSTEM:{}", ORACLE_11G_HASH)).unwrap();
+        assert_eq!("SYSTEM", entry.account);
+        assert_eq!(DatabaseScheme::Oracle11g, entry.hash.scheme);
+
+        assert!(parse_dump_line(MYSQL_HASH).is_err());
+        assert!(parse_dump_line(":*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").is_err());
+        assert!(parse_dump_line("root:plain").is_err());
+    }
+}
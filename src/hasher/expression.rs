@@ -0,0 +1,346 @@
+use super::{encode_hex, Algorithm};
+use std::fmt;
+use std::str::FromStr;
+
+const PASSWORD_VARIABLE: &str = "p";
+const SALT_VARIABLE: &str = "salt";
+const RAW_SUFFIX: &str = "_raw";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Encoding {
+    // Lowercase hex digest, e.g. PHP's md5()
+    Hex,
+    // Raw digest bytes, e.g. PHP's md5($p, true)
+    Raw,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Expression {
+    Password,
+    Salt,
+    Literal(String),
+    Concat(Vec<Expression>),
+    Hash {
+        algorithm: Algorithm,
+        encoding: Encoding,
+        input: Box<Expression>,
+    },
+}
+
+impl Expression {
+    fn uses_salt(&self) -> bool {
+        match self {
+            Expression::Salt => true,
+            Expression::Password | Expression::Literal(_) => false,
+            Expression::Concat(parts) => parts.iter().any(|part| part.uses_salt()),
+            Expression::Hash { input, .. } => input.uses_salt(),
+        }
+    }
+
+    fn evaluate(&self, word: &str, salt: &str) -> Vec<u8> {
+        match self {
+            Expression::Password => word.as_bytes().to_vec(),
+            Expression::Salt => salt.as_bytes().to_vec(),
+            Expression::Literal(literal) => literal.as_bytes().to_vec(),
+            Expression::Concat(parts) => parts
+                .iter()
+                .flat_map(|part| part.evaluate(word, salt))
+                .collect(),
+            Expression::Hash {
+                algorithm,
+                encoding,
+                input,
+            } => {
+                let digest = algorithm.digest(&input.evaluate(word, salt));
+                match encoding {
+                    Encoding::Hex => encode_hex(&digest).into_bytes(),
+                    Encoding::Raw => digest,
+                }
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    OpenParen,
+    CloseParen,
+    Dot,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '.' => Token::Dot,
+            '\'' => {
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => literal.push(c),
+                        None => return Err(format!("Unterminated literal in: {}", source)),
+                    }
+                }
+                Token::Literal(literal)
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut identifier = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() && c != '_' {
+                        break;
+                    }
+                    identifier.push(c);
+                    chars.next();
+                }
+                Token::Identifier(identifier)
+            }
+            _ => return Err(format!("Unexpected character '{}' in: {}", c, source)),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} in hash expression: {}", message, self.source)
+    }
+
+    // expression := term ('.' term)*
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        let mut parts = vec![self.parse_term()?];
+        while self.peek() == Some(&Token::Dot) {
+            self.next();
+            parts.push(self.parse_term()?);
+        }
+        if parts.len() == 1 {
+            return Ok(parts.remove(0));
+        }
+        Ok(Expression::Concat(parts))
+    }
+
+    // term := 'p' | 'salt' | literal | function '(' expression ')'
+    fn parse_term(&mut self) -> Result<Expression, String> {
+        let identifier = match self.next() {
+            Some(Token::Literal(literal)) => return Ok(Expression::Literal(literal)),
+            Some(Token::Identifier(identifier)) => identifier,
+            _ => return Err(self.error("Expected a variable, literal or function")),
+        };
+        match identifier.as_str() {
+            PASSWORD_VARIABLE => return Ok(Expression::Password),
+            SALT_VARIABLE => return Ok(Expression::Salt),
+            _ => (),
+        };
+
+        let (algorithm_name, encoding) = match identifier.strip_suffix(RAW_SUFFIX) {
+            Some(algorithm_name) => (algorithm_name, Encoding::Raw),
+            None => (identifier.as_str(), Encoding::Hex),
+        };
+        let algorithm = algorithm_name
+            .parse::<Algorithm>()
+            .map_err(|_| self.error(&format!("Unknown function '{}'", identifier)))?;
+        if self.next() != Some(Token::OpenParen) {
+            return Err(self.error(&format!("Expected '(' after '{}'", identifier)));
+        }
+        let input = self.parse_expression()?;
+        if self.next() != Some(Token::CloseParen) {
+            return Err(self.error(&format!("Expected ')' to close '{}'", identifier)));
+        }
+        Ok(Expression::Hash {
+            algorithm,
+            encoding,
+            input: Box::new(input),
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Pipeline {
+    source: String,
+    expression: Expression,
+}
+
+impl Pipeline {
+    pub fn compile(source: &str) -> Result<Pipeline, String> {
+        /*
+            Compile a hash expression such as `sha1(md5(p))` or `md5(md5(p).salt)`.
+            `p` is the password, `salt` the salt, `'...'` a literal and `.` concatenates.
+            Hash functions output lowercase hex, or raw bytes with the `_raw` suffix
+        */
+        let mut parser = Parser {
+            source,
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+        let expression = parser.parse_expression()?;
+        if parser.peek().is_some() {
+            return Err(parser.error("Unexpected trailing input"));
+        }
+        if !matches!(expression, Expression::Hash { .. }) {
+            return Err(parser.error("The outermost term must be a hash function"));
+        }
+        Ok(Pipeline {
+            source: String::from(source),
+            expression,
+        })
+    }
+
+    pub fn uses_salt(&self) -> bool {
+        self.expression.uses_salt()
+    }
+
+    pub fn single_algorithm(&self) -> Option<Algorithm> {
+        // The algorithm when the expression is just one hex hash of the password, e.g. md5(p)
+        match &self.expression {
+            Expression::Hash {
+                algorithm,
+                encoding: Encoding::Hex,
+                input,
+            } if **input == Expression::Password => Some(*algorithm),
+            _ => None,
+        }
+    }
+
+    pub fn hash(&self, word: &str, salt: Option<&str>) -> String {
+        let output = self.expression.evaluate(word, salt.unwrap_or_default());
+        match self.expression {
+            Expression::Hash {
+                encoding: Encoding::Raw,
+                ..
+            } => encode_hex(&output),
+            _ => String::from_utf8_lossy(&output).into_owned(),
+        }
+    }
+}
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pipeline::compile(s)
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile() {
+        let pipeline = Pipeline::compile("md5( md5(p) . salt )").unwrap();
+        let expected_expression = Expression::Hash {
+            algorithm: Algorithm::Md5,
+            encoding: Encoding::Hex,
+            input: Box::new(Expression::Concat(vec![
+                Expression::Hash {
+                    algorithm: Algorithm::Md5,
+                    encoding: Encoding::Hex,
+                    input: Box::new(Expression::Password),
+                },
+                Expression::Salt,
+            ])),
+        };
+        assert_eq!(expected_expression, pipeline.expression);
+        assert!(pipeline.uses_salt());
+        assert!(!Pipeline::compile("sha1(p)").unwrap().uses_salt());
+    }
+
+    #[test]
+    fn test_single_algorithm() {
+        let single = |source: &str| Pipeline::compile(source).unwrap().single_algorithm();
+        assert_eq!(Some(Algorithm::Sha1), single("sha1( p )"));
+        assert_eq!(None, single("sha1_raw(p)"));
+        assert_eq!(None, single("md5(md5(p))"));
+        assert_eq!(None, single("md5(p.salt)"));
+    }
+
+    #[test]
+    fn test_compile_invalid() {
+        let invalid_sources = [
+            "",
+            "p",
+            "md5(p",
+            "md5(p))",
+            "md4(p)",
+            "md5 p",
+            "md5(p.)",
+            "md5('abc)",
+            "md5(p) sha1(p)",
+            "md5(p#)",
+        ];
+        for source in invalid_sources {
+            assert!(Pipeline::compile(source).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        let cases = [
+            (
+                "sha1(md5(p))",
+                None,
+                "55c3b5386c486feb662a0785f340938f518d547f",
+            ),
+            (
+                "md5(md5(p).salt)",
+                Some("abc"),
+                "f24a0e9ce12069c72f77046ad708b655",
+            ),
+            (
+                "sha1(sha1_raw(p))",
+                None,
+                "2470c0c06dee42fd1618bb99005adca2ec9d1e19",
+            ),
+            (
+                "sha256('abc'.p.'xyz')",
+                None,
+                "eaf491e15145ab885b66289e4460fa9ef4605364d8aefaa973d8e121b18c9482",
+            ),
+            (
+                "sha256(p)",
+                None,
+                "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+            ),
+            (
+                "blake2b_256(p)",
+                None,
+                "344b8a854221bd1eaf9382daaea1996fbcd496f158e983f8835c7ef5084c55bb",
+            ),
+            // Raw output at the top level is hex encoded
+            ("md5_raw(p)", None, "5f4dcc3b5aa765d61d8327deb882cf99"),
+        ];
+        for (source, salt, expected_hash) in cases {
+            let pipeline = Pipeline::compile(source).unwrap();
+            assert_eq!(expected_hash, pipeline.hash("password", salt), "{}", source);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::Algorithm;
+use base64ct::{Base64, Encoding};
+use md5::{Digest, Md5};
+use pwhash::unix_crypt;
+use std::fmt;
+
+const APR1_PREFIX: &str = "$apr1$";
+const SHA1_PREFIX: &str = "{SHA}";
+const APR1_MAX_SALT_LENGTH: usize = 8;
+const APR1_ROUNDS: usize = 1000;
+const DES_CRYPT_LENGTH: usize = 13;
+// The alphabet crypt(3) uses for salts and encoded digests
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HtpasswdScheme {
+    // $apr1$, Apache's variant of md5crypt and the htpasswd default
+    Apr1,
+    // {SHA} + base64(sha1(p))
+    Sha1,
+    // Traditional 13 character DES crypt(3)
+    DesCrypt,
+}
+
+impl fmt::Display for HtpasswdScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HtpasswdScheme::Apr1 => "apr1",
+            HtpasswdScheme::Sha1 => "htpasswd-sha1",
+            HtpasswdScheme::DesCrypt => "descrypt",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HtpasswdHash {
+    pub scheme: HtpasswdScheme,
+    // The hash as it appears in the htpasswd file, which is also how it is reported
+    pub raw: String,
+}
+
+impl HtpasswdHash {
+    pub fn verify(&self, word: &str) -> bool {
+        match self.scheme {
+            HtpasswdScheme::Apr1 => {
+                let salt = self.raw[APR1_PREFIX.len()..]
+                    .split('$')
+                    .next()
+                    .unwrap_or_default();
+                apr1_crypt(word, salt) == self.raw
+            }
+            HtpasswdScheme::Sha1 => {
+                Base64::encode_string(&Algorithm::Sha1.digest(word.as_bytes()))
+                    == self.raw[SHA1_PREFIX.len()..]
+            }
+            HtpasswdScheme::DesCrypt => unix_crypt::verify(word, &self.raw),
+        }
+    }
+}
+
+fn crypt_encode(value: u32, length: usize) -> String {
+    (0..length)
+        .map(|i| CRYPT_ALPHABET[((value >> (6 * i)) & 0x3f) as usize] as char)
+        .collect()
+}
+
+pub fn apr1_crypt(word: &str, salt: &str) -> String {
+    /*
+        md5crypt with `$apr1$` mixed in instead of `$1$`, so the two never produce
+        the same hash. Only the first 8 characters of the salt are used
+    */
+    let salt = &salt[..salt.len().min(APR1_MAX_SALT_LENGTH)];
+    let word = word.as_bytes();
+    let alternate = Md5::new()
+        .chain_update(word)
+        .chain_update(salt)
+        .chain_update(word)
+        .finalize();
+
+    let mut context = Md5::new()
+        .chain_update(word)
+        .chain_update(APR1_PREFIX)
+        .chain_update(salt);
+    let mut remaining = word.len();
+    while remaining > 0 {
+        let length = remaining.min(alternate.len());
+        context.update(&alternate[..length]);
+        remaining -= length;
+    }
+    let mut length = word.len();
+    while length > 0 {
+        match length & 1 {
+            1 => context.update([0u8]),
+            _ => context.update(&word[..1]),
+        }
+        length >>= 1;
+    }
+    let mut digest = context.finalize();
+
+    for round in 0..APR1_ROUNDS {
+        let mut context = Md5::new();
+        match round % 2 {
+            1 => context.update(word),
+            _ => context.update(digest),
+        }
+        if round % 3 != 0 {
+            context.update(salt);
+        }
+        if round % 7 != 0 {
+            context.update(word);
+        }
+        match round % 2 {
+            1 => context.update(digest),
+            _ => context.update(word),
+        }
+        digest = context.finalize();
+    }
+
+    let mut encoded = String::new();
+    for (a, b, c) in [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        let value = (digest[a] as u32) << 16 | (digest[b] as u32) << 8 | digest[c] as u32;
+        encoded.push_str(&crypt_encode(value, 4));
+    }
+    encoded.push_str(&crypt_encode(digest[11] as u32, 2));
+    format!("{}{}${}", APR1_PREFIX, salt, encoded)
+}
+
+pub fn is_htpasswd_hash(hash: &str) -> bool {
+    // DES crypt has no prefix, so it is only recognised in files known to be htpasswd
+    hash.starts_with(APR1_PREFIX) || hash.starts_with(SHA1_PREFIX)
+}
+
+pub fn parse_htpasswd_hash(hash: &str) -> Result<HtpasswdHash, String> {
+    /*
+        Parse the password field of an htpasswd file. bcrypt (`$2y$`) entries are
+        left to the slow hash parser
+    */
+    let is_crypt_char = |c: u8| CRYPT_ALPHABET.contains(&c);
+    let scheme = if let Some(rest) = hash.strip_prefix(APR1_PREFIX) {
+        match rest.split_once('$') {
+            Some((salt, digest)) if !salt.is_empty() && digest.len() == 22 => HtpasswdScheme::Apr1,
+            _ => return Err(format!("Invalid apr1 hash, got: {}", hash)),
+        }
+    } else if let Some(digest) = hash.strip_prefix(SHA1_PREFIX) {
+        match Base64::decode_vec(digest) {
+            Ok(digest) if digest.len() == 20 => HtpasswdScheme::Sha1,
+            _ => return Err(format!("Invalid {} hash, got: {}", SHA1_PREFIX, hash)),
+        }
+    } else if hash.len() == DES_CRYPT_LENGTH && hash.bytes().all(is_crypt_char) {
+        HtpasswdScheme::DesCrypt
+    } else {
+        return Err(format!("Unsupported htpasswd hash, got: {}", hash));
+    };
+    Ok(HtpasswdHash {
+        scheme,
+        raw: String::from(hash),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // All of these are for the password "password"
+    const APR1_HASH: &str = "$apr1$r31abcde$ouL8QL9v/FwrkrtBccxbL.";
+    const SHA1_HASH: &str = "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=";
+    const DES_CRYPT_HASH: &str = "abJnggxhB/yWI";
+
+    #[test]
+    fn test_apr1_crypt() {
+        assert_eq!(APR1_HASH, apr1_crypt("password", "r31abcde"));
+        // Salts are cut off at 8 characters
+        assert_eq!(APR1_HASH, apr1_crypt("password", "r31abcdefgh"));
+    }
+
+    #[test]
+    fn test_parse_htpasswd_hash() {
+        let cases = [
+            (APR1_HASH, HtpasswdScheme::Apr1),
+            (SHA1_HASH, HtpasswdScheme::Sha1),
+            (DES_CRYPT_HASH, HtpasswdScheme::DesCrypt),
+        ];
+        for (hash, expected_scheme) in cases {
+            let htpasswd_hash = parse_htpasswd_hash(hash).unwrap();
+            assert_eq!(expected_scheme, htpasswd_hash.scheme);
+            assert!(htpasswd_hash.verify("password"), "{}", hash);
+            assert!(!htpasswd_hash.verify("Password"), "{}", hash);
+        }
+        assert!(parse_htpasswd_hash("$apr1$r31abcde$short").is_err());
+        assert!(parse_htpasswd_hash("{SHA}bm90IHNoYTE=").is_err());
+        assert!(parse_htpasswd_hash("$2y$05$abc").is_err());
+        assert!(is_htpasswd_hash(APR1_HASH));
+        assert!(!is_htpasswd_hash(DES_CRYPT_HASH));
+    }
+}
@@ -0,0 +1,261 @@
+use super::{crypt, database, htpasswd, slow};
+use std::fmt;
+
+const BASE64_PADDING: char = '=';
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub enum Likelihood {
+    // The prefix or structure leaves no doubt, e.g. `$2b$` or `*` + 40 hex
+    Certain,
+    // Matches on length and charset, and is a common choice for it
+    Likely,
+    // Matches on length and charset, but is rarely seen in the wild
+    Possible,
+}
+
+impl fmt::Display for Likelihood {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Likelihood::Certain => "certain",
+            Likelihood::Likely => "likely",
+            Likelihood::Possible => "possible",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct HashCandidate {
+    pub algorithm: String,
+    pub likelihood: Likelihood,
+}
+
+// Algorithms producing bare hex digests, by digest length in bytes, most common first
+const HEX_DIGESTS: [(usize, &[&str], &[&str]); 7] = [
+    (8, &["lm_half"], &[]),
+    (
+        16,
+        &["md5", "ntlm", "lm"],
+        &["blake2b_128", "blake2s_128", "blake3_128"],
+    ),
+    (20, &["sha1"], &["blake2b_160", "blake2s_160", "blake3_160"]),
+    (
+        28,
+        &["sha3_224"],
+        &["blake2b_224", "blake2s_224", "blake3_224"],
+    ),
+    (
+        32,
+        &["sha256", "sha3_256", "blake3", "blake2s"],
+        &["blake2b_256", "hmac-sha256"],
+    ),
+    (48, &["sha3_384"], &["blake2b_384", "blake3_384"]),
+    (
+        64,
+        &["sha512", "sha3_512", "blake2b"],
+        &["blake3_512", "hmac-sha512"],
+    ),
+];
+
+fn is_hex(hash: &str) -> bool {
+    !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_base64(hash: &str) -> bool {
+    let unpadded = hash.trim_end_matches(BASE64_PADDING);
+    hash.len().is_multiple_of(4)
+        && hash.len() - unpadded.len() <= 2
+        && unpadded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+fn certain(algorithm: String) -> Vec<HashCandidate> {
+    vec![HashCandidate {
+        algorithm,
+        likelihood: Likelihood::Certain,
+    }]
+}
+
+fn digest_candidates(digest_length: usize, encoding: &str) -> Vec<HashCandidate> {
+    let mut candidates: Vec<HashCandidate> = Vec::new();
+    for (length, likely, possible) in HEX_DIGESTS.iter() {
+        if *length != digest_length {
+            continue;
+        }
+        for (algorithms, likelihood) in [
+            (likely, Likelihood::Likely),
+            (possible, Likelihood::Possible),
+        ] {
+            candidates.extend(algorithms.iter().map(|algorithm| HashCandidate {
+                algorithm: format!("{}{}", algorithm, encoding),
+                likelihood,
+            }));
+        }
+    }
+    candidates
+}
+
+pub fn identify_hash(hash: &str) -> Vec<HashCandidate> {
+    /*
+        List the algorithms a hash could have come from, most likely first.
+        Self-describing formats (crypt strings, PHC strings, database hashes) are
+        recognised by their prefix, and bare digests by their length and charset
+    */
+    let hash = hash.trim();
+    if let Ok(crypt_hash) = crypt::parse_crypt_hash(hash) {
+        return certain(crypt_hash.scheme.to_string());
+    }
+    if let Ok(slow_hash) = slow::parse_slow_hash(hash) {
+        return certain(slow_hash.scheme.to_string());
+    }
+    if htpasswd::is_htpasswd_hash(hash) {
+        if let Ok(htpasswd_hash) = htpasswd::parse_htpasswd_hash(hash) {
+            return certain(htpasswd_hash.scheme.to_string());
+        }
+    }
+    if database::is_database_hash(hash) {
+        // The username only matters for verifying PostgreSQL hashes, not for recognising them
+        if let Ok(database_hash) = database::parse_database_hash(hash, Some("")) {
+            return certain(database_hash.scheme.to_string());
+        }
+    }
+    if is_hex(hash) && hash.len().is_multiple_of(2) {
+        let mut candidates = digest_candidates(hash.len() / 2, "");
+        // pwdump style tools print LM and NTLM hashes in uppercase
+        if hash.chars().any(|c| c.is_ascii_uppercase()) {
+            candidates.sort_by_key(|candidate| {
+                (
+                    candidate.likelihood,
+                    !matches!(candidate.algorithm.as_str(), "ntlm" | "lm"),
+                )
+            });
+        }
+        return candidates;
+    }
+    if is_base64(hash) {
+        let padding = hash.len() - hash.trim_end_matches(BASE64_PADDING).len();
+        return digest_candidates(hash.len() / 4 * 3 - padding, " (base64)");
+    }
+    Vec::new()
+}
+
+pub fn normalize_hash(hash: &str) -> String {
+    // Tables hold hex digests in lowercase, so hex input is looked up that way whatever its case
+    match is_hex(hash) {
+        true => hash.to_ascii_lowercase(),
+        false => String::from(hash),
+    }
+}
+
+pub fn hash_length_mismatch(hash: &str, table_hash: &str) -> Option<String> {
+    /*
+        Describe why `hash` cannot be in a table whose hashes look like `table_hash`,
+        or None when their lengths match
+    */
+    if hash.trim().len() == table_hash.len() {
+        return None;
+    }
+    let describe = |hash: &str| match identify_hash(hash).first() {
+        Some(candidate) => format!("{} characters, likely {}", hash.len(), candidate.algorithm),
+        None => format!("{} characters", hash.len()),
+    };
+    Some(format!(
+        "hash {} is {}, but the table holds hashes of {}",
+        hash,
+        describe(hash.trim()),
+        describe(table_hash)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_hash() {
+        assert_eq!("e91c", normalize_hash("E91C"));
+        assert_eq!("$1$SaLt$AbC", normalize_hash("$1$SaLt$AbC"));
+    }
+
+    fn algorithms(hash: &str) -> Vec<String> {
+        identify_hash(hash)
+            .into_iter()
+            .map(|candidate| candidate.algorithm)
+            .collect()
+    }
+
+    #[test]
+    fn test_identify_prefixed_hash() {
+        let cases = [
+            ("$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/", "md5crypt"),
+            (
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+                "bcrypt",
+            ),
+            (
+                "$argon2id$v=19$m=256,t=1,p=1$c2FsdHNhbHRzYWx0$h5si+9sDoiH+8LtozZo7oUdJD/KVE1KfOXN/zAbaH4Q",
+                "argon2id",
+            ),
+            ("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19", "mysql"),
+            ("md532e12f215ba27cb750c9e093ce4b5127", "postgres-md5"),
+            ("$apr1$r31abcde$ouL8QL9v/FwrkrtBccxbL.", "apr1"),
+        ];
+        for (hash, expected_algorithm) in cases {
+            let candidates = identify_hash(hash);
+            assert_eq!(1, candidates.len(), "{}", hash);
+            assert_eq!(expected_algorithm, candidates[0].algorithm);
+            assert_eq!(Likelihood::Certain, candidates[0].likelihood);
+        }
+    }
+
+    #[test]
+    fn test_identify_hex_digest() {
+        let candidates = algorithms("5f4dcc3b5aa765d61d8327deb882cf99");
+        assert_eq!("md5", candidates[0]);
+        assert!(candidates.contains(&"blake3_128".to_string()));
+
+        // Uppercase hex is more likely to come from a Windows dump
+        let candidates = algorithms("8846F7EAEE8FB117AD06BDD830B7586C");
+        assert_eq!(vec!["ntlm", "lm", "md5"], candidates[..3].to_vec());
+
+        let candidates =
+            algorithms("5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8");
+        assert_eq!("sha256", candidates[0]);
+        assert_eq!(
+            Likelihood::Possible,
+            identify_hash(&"a".repeat(64))[5].likelihood
+        );
+
+        assert_eq!("sha3_384", algorithms(&"a".repeat(96))[0]);
+        assert!(identify_hash("abc").is_empty());
+        assert!(identify_hash("not a hash!").is_empty());
+    }
+
+    #[test]
+    fn test_identify_base64_digest() {
+        // base64 of the SHA-256 of "password"
+        let candidates = algorithms("XohImNooBHFR0OVvjcYpJ3NgPQ1qq73WKhHvch0VQtg=");
+        assert_eq!("sha256 (base64)", candidates[0]);
+        // base64 of the MD5 of "password"
+        assert_eq!("md5 (base64)", algorithms("X03MO1qnZdYdgyfeuILPmQ==")[0]);
+    }
+
+    #[test]
+    fn test_hash_length_mismatch() {
+        let sha256_hash = "5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8";
+        assert_eq!(None, hash_length_mismatch(sha256_hash, sha256_hash));
+        let mismatch =
+            hash_length_mismatch("5f4dcc3b5aa765d61d8327deb882cf99", sha256_hash).unwrap();
+        assert!(
+            mismatch.contains("32 characters, likely md5"),
+            "{}",
+            mismatch
+        );
+        assert!(
+            mismatch.contains("64 characters, likely sha256"),
+            "{}",
+            mismatch
+        );
+    }
+}
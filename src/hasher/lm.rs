@@ -0,0 +1,182 @@
+use super::{decode_hex, encode_hex};
+use des::cipher::{BlockEncrypt, KeyInit};
+use des::Des;
+use md4::{Digest, Md4};
+
+pub const LM_HALF_LENGTH: usize = 7;
+const LM_PASSWORD_LENGTH: usize = 2 * LM_HALF_LENGTH;
+// The constant each password half is used as a DES key to encrypt
+const LM_MAGIC: &[u8; 8] = b"KGS!@#$%";
+// LM hash of an empty half, i.e. the second half of any password of 7 characters or less
+pub const EMPTY_LM_HALF_HASH: &str = "aad3b435b51404ee";
+// Cap on the 2^n case variants tried against an NTLM hash. LM passwords are at most 14 characters
+const MAX_CASE_LETTERS: usize = LM_PASSWORD_LENGTH;
+
+fn des_key(half: &[u8; LM_HALF_LENGTH]) -> [u8; 8] {
+    /*
+        Spread 56 key bits over 8 bytes, leaving the lowest bit of each byte for
+        the DES parity bit, which is ignored
+    */
+    let mut key = [0u8; 8];
+    for (i, key_byte) in key.iter_mut().enumerate() {
+        let bits = match i {
+            0 => half[0] >> 1,
+            7 => half[6] & 0x7f,
+            _ => ((half[i - 1] << (7 - i)) | (half[i] >> (i + 1))) & 0x7f,
+        };
+        *key_byte = bits << 1;
+    }
+    key
+}
+
+pub fn lm_half_hash(half: &str) -> String {
+    /*
+        Hash a single, already uppercased half of at most 7 bytes. Longer input is
+        truncated, just like Windows does
+    */
+    let mut key_material = [0u8; LM_HALF_LENGTH];
+    for (key_byte, byte) in key_material.iter_mut().zip(half.bytes()) {
+        *key_byte = byte;
+    }
+    let cipher = Des::new_from_slice(&des_key(&key_material)).expect("DES keys are 8 bytes");
+    let mut block = (*LM_MAGIC).into();
+    cipher.encrypt_block(&mut block);
+    encode_hex(&block)
+}
+
+pub fn lm_halves(word: &str) -> (String, String) {
+    // LM uppercases the password and cuts it off at 14 characters before splitting
+    let word: String = word
+        .to_ascii_uppercase()
+        .chars()
+        .take(LM_PASSWORD_LENGTH)
+        .collect();
+    let split = word
+        .char_indices()
+        .nth(LM_HALF_LENGTH)
+        .map_or(word.len(), |(index, _)| index);
+    let (first, second) = word.split_at(split);
+    (String::from(first), String::from(second))
+}
+
+pub fn lm_hash(word: &str) -> String {
+    let (first, second) = lm_halves(word);
+    format!("{}{}", lm_half_hash(&first), lm_half_hash(&second))
+}
+
+pub fn ntlm_hash(word: &str) -> String {
+    let utf16_word: Vec<u8> = word.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    encode_hex(&Md4::digest(utf16_word))
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct LmHash {
+    pub first_half: String,
+    pub second_half: String,
+}
+
+pub fn parse_lm_hash(lm_hash: &str) -> Result<LmHash, String> {
+    let lm_hash = lm_hash.to_lowercase();
+    match decode_hex(&lm_hash) {
+        Ok(bytes) if bytes.len() == 16 => {
+            let (first_half, second_half) = lm_hash.split_at(16);
+            Ok(LmHash {
+                first_half: String::from(first_half),
+                second_half: String::from(second_half),
+            })
+        }
+        _ => Err(format!(
+            "Invalid LM hash, expected 32 hex characters, got: {}",
+            lm_hash
+        )),
+    }
+}
+
+pub fn recover_case(uppercase_word: &str, ntlm: &str) -> Option<String> {
+    /*
+        LM only tells us the uppercased password. Try every combination of upper
+        and lower case letters until one matches the NTLM hash of the same account
+    */
+    let ntlm = ntlm.to_lowercase();
+    let letters: Vec<usize> = uppercase_word
+        .char_indices()
+        .filter(|(_, c)| c.is_ascii_alphabetic())
+        .map(|(index, _)| index)
+        .collect();
+    if letters.len() > MAX_CASE_LETTERS {
+        return None;
+    }
+    (0..1u32 << letters.len()).find_map(|mask| {
+        let mut candidate = uppercase_word.as_bytes().to_vec();
+        for (bit, &index) in letters.iter().enumerate() {
+            if mask & (1 << bit) != 0 {
+                candidate[index] = candidate[index].to_ascii_lowercase();
+            }
+        }
+        // Only ASCII letters were changed, so the candidate is still valid UTF-8
+        let candidate = String::from_utf8(candidate).ok()?;
+        (ntlm_hash(&candidate) == ntlm).then_some(candidate)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lm_hash() {
+        let cases = [
+            ("password", "e52cac67419a9a224a3b108f3fa6cb6d"),
+            ("PassWord", "e52cac67419a9a224a3b108f3fa6cb6d"),
+            ("welcome", "c23413a8a1e7665faad3b435b51404ee"),
+            ("", "aad3b435b51404eeaad3b435b51404ee"),
+        ];
+        for (word, expected_hash) in cases {
+            assert_eq!(expected_hash, lm_hash(word), "{}", word);
+        }
+        assert_eq!(EMPTY_LM_HALF_HASH, lm_half_hash(""));
+    }
+
+    #[test]
+    fn test_lm_halves() {
+        assert_eq!(
+            ("PASSWOR".to_string(), "D".to_string()),
+            lm_halves("password")
+        );
+        assert_eq!(
+            ("ABCDEFG".to_string(), "HIJKLMN".to_string()),
+            lm_halves("abcdefghijklmnopq")
+        );
+        assert_eq!(("ABC".to_string(), "".to_string()), lm_halves("abc"));
+    }
+
+    #[test]
+    fn test_ntlm_hash() {
+        assert_eq!("8846f7eaee8fb117ad06bdd830b7586c", ntlm_hash("password"));
+        assert_eq!("31d6cfe0d16ae931b73c59d7e0c089c0", ntlm_hash(""));
+    }
+
+    #[test]
+    fn test_parse_lm_hash() {
+        let expected_hash = LmHash {
+            first_half: "e52cac67419a9a22".to_string(),
+            second_half: "4a3b108f3fa6cb6d".to_string(),
+        };
+        assert_eq!(
+            Ok(expected_hash),
+            parse_lm_hash("E52CAC67419A9A224A3B108F3FA6CB6D")
+        );
+        assert!(parse_lm_hash("e52cac67419a9a22").is_err());
+        assert!(parse_lm_hash("zz2cac67419a9a224a3b108f3fa6cb6d").is_err());
+    }
+
+    #[test]
+    fn test_recover_case() {
+        let ntlm = ntlm_hash("PaSsWoRd1");
+        assert_eq!(
+            Some("PaSsWoRd1".to_string()),
+            recover_case("PASSWORD1", &ntlm)
+        );
+        assert_eq!(None, recover_case("DRAGON", &ntlm));
+    }
+}
@@ -0,0 +1,171 @@
+use argon2::Argon2;
+use password_hash::{PasswordHash, PasswordVerifier};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+use std::fmt;
+
+const BCRYPT_IDS: [&str; 4] = ["2a", "2b", "2x", "2y"];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SlowScheme {
+    // $2a$, $2b$, $2x$ and $2y$ modular crypt strings
+    Bcrypt,
+    // PHC strings from here on
+    Scrypt,
+    Pbkdf2Sha256,
+    Pbkdf2Sha512,
+    Argon2i,
+    Argon2d,
+    Argon2id,
+}
+
+impl SlowScheme {
+    fn from_phc_id(id: &str) -> Option<SlowScheme> {
+        match id {
+            "scrypt" => Some(SlowScheme::Scrypt),
+            "pbkdf2-sha256" => Some(SlowScheme::Pbkdf2Sha256),
+            "pbkdf2-sha512" => Some(SlowScheme::Pbkdf2Sha512),
+            "argon2i" => Some(SlowScheme::Argon2i),
+            "argon2d" => Some(SlowScheme::Argon2d),
+            "argon2id" => Some(SlowScheme::Argon2id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SlowScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SlowScheme::Bcrypt => "bcrypt",
+            SlowScheme::Scrypt => "scrypt",
+            SlowScheme::Pbkdf2Sha256 => "pbkdf2-sha256",
+            SlowScheme::Pbkdf2Sha512 => "pbkdf2-sha512",
+            SlowScheme::Argon2i => "argon2i",
+            SlowScheme::Argon2d => "argon2d",
+            SlowScheme::Argon2id => "argon2id",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct SlowHash {
+    pub scheme: SlowScheme,
+    // Human readable cost parameters, e.g. `cost=12` or `m=65536,t=3,p=4`
+    pub cost: String,
+    pub raw: String,
+}
+
+impl SlowHash {
+    pub fn verify(&self, word: &str) -> bool {
+        match self.scheme {
+            SlowScheme::Bcrypt => bcrypt::verify(word, &self.raw).unwrap_or(false),
+            _ => {
+                // Already validated in `parse_slow_hash`
+                let phc_hash = match PasswordHash::new(&self.raw) {
+                    Ok(phc_hash) => phc_hash,
+                    Err(_) => return false,
+                };
+                let verifiers: [&dyn PasswordVerifier; 3] = [&Argon2::default(), &Scrypt, &Pbkdf2];
+                phc_hash.verify_password(&verifiers, word).is_ok()
+            }
+        }
+    }
+}
+
+fn parse_bcrypt_hash(bcrypt_hash: &str) -> Result<SlowHash, String> {
+    // $2b$12$ followed by 22 characters of salt and 31 characters of hash
+    let fields = bcrypt_hash.split('$').collect::<Vec<&str>>();
+    let valid = fields.len() == 4 && fields[0].is_empty() && fields[3].len() == 53;
+    let cost = match fields.get(2).map(|cost| cost.parse::<u32>()) {
+        Some(Ok(cost)) if valid && (4..=31).contains(&cost) => cost,
+        _ => return Err(format!("Invalid bcrypt hash, got: {}", bcrypt_hash)),
+    };
+    Ok(SlowHash {
+        scheme: SlowScheme::Bcrypt,
+        cost: format!("cost={} ({} rounds)", cost, 1u64 << cost),
+        raw: String::from(bcrypt_hash),
+    })
+}
+
+pub fn parse_slow_hash(slow_hash: &str) -> Result<SlowHash, String> {
+    /*
+        Parse a bcrypt modular crypt string, or a PHC string for scrypt,
+        PBKDF2 and Argon2, e.g. $argon2id$v=19$m=65536,t=3,p=4$salt$hash
+    */
+    let id = slow_hash.split('$').nth(1).unwrap_or_default();
+    if BCRYPT_IDS.contains(&id) {
+        return parse_bcrypt_hash(slow_hash);
+    }
+    let scheme = SlowScheme::from_phc_id(id)
+        .ok_or_else(|| format!("Unsupported slow hash scheme, got: {}", slow_hash))?;
+    let phc_hash = PasswordHash::new(slow_hash)
+        .map_err(|e| format!("Invalid {} hash: {}, got: {}", scheme, e, slow_hash))?;
+    if phc_hash.hash.is_none() {
+        return Err(format!("{} hash has no digest, got: {}", scheme, slow_hash));
+    }
+    Ok(SlowHash {
+        scheme,
+        cost: phc_hash.params.to_string(),
+        raw: String::from(slow_hash),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // OpenBSD bcrypt test vector for the password "U*U"
+    const BCRYPT_HASH: &str = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+    // The rest are all for the password "password", with cheap parameters to keep tests fast
+    const ARGON2ID_HASH: &str =
+        "$argon2id$v=19$m=256,t=1,p=1$c2FsdHNhbHRzYWx0$h5si+9sDoiH+8LtozZo7oUdJD/KVE1KfOXN/zAbaH4Q";
+    const SCRYPT_HASH: &str =
+        "$scrypt$ln=4,r=8,p=1$c2FsdHNhbHRzYWx0$OMhHbSEaEtsu/I0j8kYPfsrH+u3TT8X+IGRK4Kq6rWE";
+    const PBKDF2_HASH: &str =
+        "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0$sYIePhT5IXESDKvnouJXtE5pTJ6Znbmef4vViYmc9Uc";
+
+    #[test]
+    fn test_parse_slow_hash() {
+        let slow_hash = parse_slow_hash(BCRYPT_HASH).unwrap();
+        assert_eq!(SlowScheme::Bcrypt, slow_hash.scheme);
+        assert_eq!("cost=5 (32 rounds)", slow_hash.cost);
+
+        let slow_hash = parse_slow_hash(ARGON2ID_HASH).unwrap();
+        assert_eq!(SlowScheme::Argon2id, slow_hash.scheme);
+        assert_eq!("m=256,t=1,p=1", slow_hash.cost);
+
+        let slow_hash = parse_slow_hash(SCRYPT_HASH).unwrap();
+        assert_eq!(SlowScheme::Scrypt, slow_hash.scheme);
+        assert_eq!("ln=4,r=8,p=1", slow_hash.cost);
+
+        let slow_hash = parse_slow_hash(PBKDF2_HASH).unwrap();
+        assert_eq!(SlowScheme::Pbkdf2Sha256, slow_hash.scheme);
+        assert_eq!("i=1000,l=32", slow_hash.cost);
+    }
+
+    #[test]
+    fn test_parse_slow_hash_invalid() {
+        assert!(parse_slow_hash("abc").is_err());
+        assert!(parse_slow_hash("$6$saltsalt$abc").is_err());
+        assert!(
+            parse_slow_hash("$2b$99$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW")
+                .is_err()
+        );
+        assert!(parse_slow_hash("$2b$05$short").is_err());
+        assert!(parse_slow_hash("$argon2id$v=19$m=256,t=1,p=1$c2FsdHNhbHRzYWx0").is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let slow_hash = parse_slow_hash(BCRYPT_HASH).unwrap();
+        assert!(slow_hash.verify("U*U"));
+        assert!(!slow_hash.verify("U*V"));
+
+        for slow_hash in [ARGON2ID_HASH, SCRYPT_HASH, PBKDF2_HASH] {
+            let slow_hash = parse_slow_hash(slow_hash).unwrap();
+            assert!(slow_hash.verify("password"), "{}", slow_hash.raw);
+            assert!(!slow_hash.verify("Password"), "{}", slow_hash.raw);
+        }
+    }
+}
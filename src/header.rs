@@ -0,0 +1,353 @@
+use crate::hasher::expression::Pipeline;
+use crate::hasher::{self, lm, Algorithm, Salt, SaltPosition, WordHash};
+use sha2::{Digest, Sha256};
+
+// The first line of a text table, followed by its tab separated key=value fields
+pub const HEADER_PREFIX: &str = "#rust-rainbowtable";
+pub const HEADER_VERSION: u32 = 1;
+const FIELD_DELIMITER: char = '\t';
+const KEY_VALUE_DELIMITER: char = '=';
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum TableHashing {
+    // sha256(p), the default
+    Plain,
+    // sha256 of the word with a global salt
+    Salted(Salt),
+    // HMAC with the given digest. The key itself is never written to the table
+    Hmac(Algorithm),
+    // A hash expression, and the salt that fills in its `salt` variable
+    Pipeline(Pipeline, Option<String>),
+    // LM half hashes of uppercased password halves
+    Lm,
+}
+
+impl TableHashing {
+    pub fn name(&self) -> String {
+        match self {
+            TableHashing::Plain => String::from("sha256"),
+            TableHashing::Salted(_) => String::from("salted-sha256"),
+            TableHashing::Hmac(algorithm) => format!("hmac-{}", algorithm),
+            TableHashing::Pipeline(_, _) => String::from("expression"),
+            TableHashing::Lm => String::from("lm"),
+        }
+    }
+
+    pub fn params(&self) -> Vec<(String, String)> {
+        // Whatever besides the name is needed to hash a word the same way again
+        let salt_param = |salt: &str| (String::from("salt"), String::from(salt));
+        match self {
+            TableHashing::Salted(salt) => {
+                let position = match salt.position {
+                    SaltPosition::Prefix => "prefix",
+                    SaltPosition::Suffix => "suffix",
+                };
+                vec![
+                    salt_param(&salt.value),
+                    (String::from("salt_position"), String::from(position)),
+                ]
+            }
+            TableHashing::Pipeline(pipeline, salt) => {
+                let mut params = vec![(String::from("expression"), pipeline.to_string())];
+                params.extend(salt.as_deref().map(salt_param));
+                params
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn from_fields(name: &str, params: &[(String, String)]) -> Result<TableHashing, String> {
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(param_key, _)| param_key == key)
+                .map(|(_, value)| value.clone())
+        };
+        let missing = |key: &str| format!("Table header for {} is missing its {}", name, key);
+        match name {
+            "sha256" => Ok(TableHashing::Plain),
+            "salted-sha256" => Ok(TableHashing::Salted(Salt {
+                value: param("salt").ok_or_else(|| missing("salt"))?,
+                position: param("salt_position")
+                    .ok_or_else(|| missing("salt_position"))?
+                    .parse::<SaltPosition>()?,
+            })),
+            "expression" => Ok(TableHashing::Pipeline(
+                Pipeline::compile(&param("expression").ok_or_else(|| missing("expression"))?)?,
+                param("salt"),
+            )),
+            "lm" => Ok(TableHashing::Lm),
+            _ => match name.strip_prefix("hmac-") {
+                Some(algorithm) => Ok(TableHashing::Hmac(algorithm.parse::<Algorithm>()?)),
+                None => Err(format!("Unknown table algorithm, got: {}", name)),
+            },
+        }
+    }
+
+    pub fn hash(&self, word: &str) -> Option<String> {
+        // None when the table does not hold everything needed to hash the word
+        match self {
+            TableHashing::Plain => Some(hasher::hash_word(word)),
+            TableHashing::Salted(salt) => Some(hasher::hash_salted_word(word, salt)),
+            TableHashing::Hmac(_) => None,
+            TableHashing::Pipeline(pipeline, salt) => Some(pipeline.hash(word, salt.as_deref())),
+            TableHashing::Lm => Some(lm::lm_half_hash(word)),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TableHeader {
+    pub version: u32,
+    pub hashing: TableHashing,
+    pub count: usize,
+    // Whether the entries are in ascending hash order
+    pub sorted: bool,
+    // SHA-256 of the entries as word:hash lines, the same whichever way they are stored
+    pub checksum: String,
+}
+
+pub fn is_sorted(word_hashes: &[WordHash]) -> bool {
+    word_hashes
+        .windows(2)
+        .all(|pair| pair[0].hash <= pair[1].hash)
+}
+
+pub fn checksum<'a>(word_hashes: impl IntoIterator<Item = &'a WordHash>) -> String {
+    let mut checksum = Sha256::new();
+    for word_hash in word_hashes {
+        checksum.update(&word_hash.word);
+        checksum.update(hasher::HASH_DELIMITER);
+        checksum.update(&word_hash.hash);
+        checksum.update("\n");
+    }
+    hasher::encode_hex(&checksum.finalize())
+}
+
+// Builds a header one entry at a time, for tables that are never all in memory
+pub struct HeaderBuilder {
+    hashing: TableHashing,
+    count: usize,
+    sorted: bool,
+    last_hash: Option<String>,
+    checksum: Sha256,
+}
+
+impl HeaderBuilder {
+    pub fn new(hashing: TableHashing) -> HeaderBuilder {
+        HeaderBuilder {
+            hashing,
+            count: 0,
+            sorted: true,
+            last_hash: None,
+            checksum: Sha256::new(),
+        }
+    }
+
+    pub fn add(&mut self, word_hash: &WordHash) {
+        self.count += 1;
+        if self
+            .last_hash
+            .as_ref()
+            .is_some_and(|last_hash| *last_hash > word_hash.hash)
+        {
+            self.sorted = false;
+        }
+        self.last_hash = Some(word_hash.hash.clone());
+        self.checksum.update(&word_hash.word);
+        self.checksum.update(hasher::HASH_DELIMITER);
+        self.checksum.update(&word_hash.hash);
+        self.checksum.update("\n");
+    }
+
+    pub fn finish(self) -> TableHeader {
+        TableHeader {
+            version: HEADER_VERSION,
+            hashing: self.hashing,
+            count: self.count,
+            sorted: self.sorted,
+            checksum: hasher::encode_hex(&self.checksum.finalize()),
+        }
+    }
+}
+
+impl TableHeader {
+    pub fn new(hashing: TableHashing, word_hashes: &[WordHash]) -> TableHeader {
+        TableHeader {
+            version: HEADER_VERSION,
+            hashing,
+            count: word_hashes.len(),
+            sorted: is_sorted(word_hashes),
+            checksum: checksum(word_hashes),
+        }
+    }
+
+    pub fn to_fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![
+            (String::from("version"), self.version.to_string()),
+            (String::from("algorithm"), self.hashing.name()),
+            (String::from("count"), self.count.to_string()),
+            (String::from("sorted"), self.sorted.to_string()),
+            (String::from("checksum"), self.checksum.clone()),
+        ];
+        fields.extend(self.hashing.params());
+        fields
+    }
+
+    pub fn from_fields(fields: &[(String, String)]) -> Result<TableHeader, String> {
+        let field = |key: &str| {
+            fields
+                .iter()
+                .find(|(field_key, _)| field_key == key)
+                .map(|(_, value)| value.as_str())
+                .ok_or_else(|| format!("Table header is missing its {}", key))
+        };
+        let invalid =
+            |key: &str, value: &str| format!("Invalid table header {}, got: {}", key, value);
+        let version = field("version")?;
+        let version = version
+            .parse::<u32>()
+            .map_err(|_| invalid("version", version))?;
+        if version != HEADER_VERSION {
+            return Err(format!(
+                "Unsupported table version {}, expected {}",
+                version, HEADER_VERSION
+            ));
+        }
+        let count = field("count")?;
+        let sorted = field("sorted")?;
+        Ok(TableHeader {
+            version,
+            hashing: TableHashing::from_fields(field("algorithm")?, fields)?,
+            count: count
+                .parse::<usize>()
+                .map_err(|_| invalid("count", count))?,
+            sorted: sorted
+                .parse::<bool>()
+                .map_err(|_| invalid("sorted", sorted))?,
+            checksum: String::from(field("checksum")?),
+        })
+    }
+
+    pub fn serialize(&self) -> Result<String, String> {
+        let mut line = String::from(HEADER_PREFIX);
+        for (key, value) in self.to_fields() {
+            if value.contains([FIELD_DELIMITER, '\n', '\r']) {
+                return Err(format!(
+                    "Table header {} cannot contain tabs or line breaks, got: {:?}",
+                    key, value
+                ));
+            }
+            line.push(FIELD_DELIMITER);
+            line.push_str(&format!("{}{}{}", key, KEY_VALUE_DELIMITER, value));
+        }
+        Ok(line)
+    }
+
+    pub fn deserialize(line: &str) -> Result<TableHeader, String> {
+        let mut fields = line.split(FIELD_DELIMITER);
+        if fields.next() != Some(HEADER_PREFIX) {
+            return Err(format!("Invalid table header, got: {}", line));
+        }
+        let fields = fields
+            .map(|field| match field.split_once(KEY_VALUE_DELIMITER) {
+                Some((key, value)) => Ok((String::from(key), String::from(value))),
+                None => Err(format!("Invalid table header field, got: {}", field)),
+            })
+            .collect::<Result<Vec<(String, String)>, String>>()?;
+        TableHeader::from_fields(&fields)
+    }
+}
+
+pub fn is_header_line(line: &str) -> bool {
+    line.starts_with(HEADER_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn test_serialize_header() {
+        let salted = TableHashing::Salted(Salt {
+            value: "pepper=1".to_string(),
+            position: SaltPosition::Suffix,
+        });
+        let pipeline = TableHashing::Pipeline(
+            Pipeline::compile("md5(md5(p).salt)").unwrap(),
+            Some("NaCl".to_string()),
+        );
+        let hashings = [
+            TableHashing::Plain,
+            salted,
+            TableHashing::Hmac(Algorithm::Sha1),
+            pipeline,
+            TableHashing::Lm,
+        ];
+        let word_hashes = test_utils::word_hashes(&TableHashing::Plain, &["potato", "rice"]);
+        for hashing in hashings {
+            let header = TableHeader::new(hashing, &word_hashes);
+            let line = header.serialize().unwrap();
+            assert!(is_header_line(&line));
+            assert_eq!(Ok(header), TableHeader::deserialize(&line));
+        }
+    }
+
+    #[test]
+    fn test_header_fields() {
+        let rice_potato = test_utils::word_hashes(&TableHashing::Plain, &["rice", "potato"]);
+        let potato_rice = test_utils::word_hashes(&TableHashing::Plain, &["potato", "rice"]);
+        let header = TableHeader::new(TableHashing::Plain, &rice_potato);
+        assert_eq!(2, header.count);
+        // 209f... sorts before e91c...
+        assert!(header.sorted);
+        assert_eq!(checksum(&rice_potato), header.checksum);
+        assert_ne!(checksum(&potato_rice), header.checksum);
+        assert!(!TableHeader::new(TableHashing::Plain, &potato_rice).sorted);
+    }
+
+    #[test]
+    fn test_header_builder() {
+        for words in [vec!["rice", "potato"], vec!["potato", "rice"], vec![]] {
+            let word_hashes = test_utils::word_hashes(&TableHashing::Plain, &words);
+            let mut builder = HeaderBuilder::new(TableHashing::Plain);
+            for word_hash in &word_hashes {
+                builder.add(word_hash);
+            }
+            assert_eq!(
+                TableHeader::new(TableHashing::Plain, &word_hashes),
+                builder.finish()
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_invalid_header() {
+        let line = TableHeader::new(TableHashing::Plain, &[])
+            .serialize()
+            .unwrap();
+        assert!(TableHeader::deserialize(&line.replace("version=1", "version=9")).is_err());
+        assert!(TableHeader::deserialize(&line.replace("sha256", "sha0")).is_err());
+        assert!(TableHeader::deserialize(&line.replace("\tcount=0", "")).is_err());
+        assert!(TableHeader::deserialize("potato:e91c").is_err());
+        let salted = TableHashing::Salted(Salt {
+            value: "tab\tsalt".to_string(),
+            position: SaltPosition::Prefix,
+        });
+        assert!(TableHeader::new(salted, &[]).serialize().is_err());
+    }
+
+    #[test]
+    fn test_table_hashing_hash() {
+        assert_eq!(
+            Some(hasher::hash_word("potato")),
+            TableHashing::Plain.hash("potato")
+        );
+        assert_eq!(
+            Some(lm::lm_half_hash("POTATO")),
+            TableHashing::Lm.hash("POTATO")
+        );
+        assert_eq!(None, TableHashing::Hmac(Algorithm::Md5).hash("potato"));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::header::TableHeader;
+use crate::shard::{self, ShardManifest};
+use crate::storage::{self, StorageFormat};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path;
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct CharsetStats {
+    // Each count is of the words holding at least one such character
+    pub lowercase: usize,
+    pub uppercase: usize,
+    pub digits: usize,
+    pub symbols: usize,
+    pub non_ascii: usize,
+    pub distinct_characters: usize,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum StatValue {
+    Count(u64),
+    Text(String),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TableInfo {
+    pub storage_format: StorageFormat,
+    // The header of the table, or of its first shard. None for tables from before headers
+    pub header: Option<TableHeader>,
+    pub shard_count: Option<usize>,
+    pub entry_count: usize,
+    // Lines or rows that could not be parsed, see `verify-table` for where they are
+    pub invalid_count: usize,
+    // In bytes, of every file that makes up the table
+    pub file_size: u64,
+    // Whether the entries actually are in ascending hash order, within each shard
+    pub sorted: bool,
+    // Word length in characters, and how many words have it
+    pub word_lengths: BTreeMap<usize, usize>,
+    pub charsets: CharsetStats,
+}
+
+fn file_size(file_path: &str) -> Result<u64, String> {
+    fs::metadata(file_path)
+        .map(|metadata| metadata.len())
+        .map_err(|e| format!("Unable to read the size of {}: {}", file_path, e))
+}
+
+impl TableInfo {
+    fn empty() -> TableInfo {
+        TableInfo {
+            storage_format: StorageFormat::Text,
+            header: None,
+            shard_count: None,
+            entry_count: 0,
+            invalid_count: 0,
+            file_size: 0,
+            sorted: true,
+            word_lengths: BTreeMap::new(),
+            charsets: CharsetStats::default(),
+        }
+    }
+
+    fn add_file(
+        &mut self,
+        table_path: &str,
+        characters: &mut BTreeSet<char>,
+    ) -> Result<(), String> {
+        let table = storage::open_table(table_path)?;
+        self.storage_format = storage::detect_format(table_path)?;
+        if self.header.is_none() {
+            self.header = table.read_header()?;
+        }
+        self.file_size += file_size(table_path)?;
+        let mut previous_hash: Option<String> = None;
+        for entry in table.read_entries()? {
+            let word_hash = match entry.word_hash {
+                Ok(word_hash) => word_hash,
+                Err(_) => {
+                    self.invalid_count += 1;
+                    continue;
+                }
+            };
+            self.entry_count += 1;
+            let word = &word_hash.word;
+            *self.word_lengths.entry(word.chars().count()).or_insert(0) += 1;
+            let has = |predicate: fn(&char) -> bool| word.chars().any(|c| predicate(&c)) as usize;
+            self.charsets.lowercase += has(char::is_ascii_lowercase);
+            self.charsets.uppercase += has(char::is_ascii_uppercase);
+            self.charsets.digits += has(char::is_ascii_digit);
+            self.charsets.symbols += has(|c| c.is_ascii() && !c.is_ascii_alphanumeric());
+            self.charsets.non_ascii += has(|c| !c.is_ascii());
+            characters.extend(word.chars());
+            if previous_hash
+                .as_ref()
+                .is_some_and(|hash| *hash > word_hash.hash)
+            {
+                self.sorted = false;
+            }
+            previous_hash = Some(word_hash.hash);
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Vec<(String, StatValue)> {
+        /*
+            Flatten the info into named values, in the order they are printed.
+            Grouped values share a prefix, e.g. `word_length.8`
+        */
+        let text = |value: &str| StatValue::Text(String::from(value));
+        let count = |value: usize| StatValue::Count(value as u64);
+        let mut stats = vec![(
+            String::from("format"),
+            text(&self.storage_format.to_string()),
+        )];
+        if let Some(shard_count) = self.shard_count {
+            stats.push((String::from("shards"), count(shard_count)));
+        }
+        match &self.header {
+            Some(header) => {
+                stats.push((String::from("version"), count(header.version as usize)));
+                stats.push((String::from("algorithm"), text(&header.hashing.name())));
+                for (key, value) in header.hashing.params() {
+                    stats.push((format!("param.{}", key), StatValue::Text(value)));
+                }
+            }
+            None => {
+                stats.push((String::from("version"), text("none")));
+                stats.push((String::from("algorithm"), text("unknown")));
+            }
+        }
+        stats.push((String::from("entries"), count(self.entry_count)));
+        if self.invalid_count > 0 {
+            stats.push((String::from("invalid_entries"), count(self.invalid_count)));
+        }
+        stats.push((String::from("file_size"), StatValue::Count(self.file_size)));
+        stats.push((String::from("sorted"), text(&self.sorted.to_string())));
+        for (length, words) in &self.word_lengths {
+            stats.push((format!("word_length.{}", length), count(*words)));
+        }
+        let charsets = [
+            ("lowercase", self.charsets.lowercase),
+            ("uppercase", self.charsets.uppercase),
+            ("digits", self.charsets.digits),
+            ("symbols", self.charsets.symbols),
+            ("non_ascii", self.charsets.non_ascii),
+            ("distinct_characters", self.charsets.distinct_characters),
+        ];
+        for (charset, words) in charsets {
+            stats.push((format!("charset.{}", charset), count(words)));
+        }
+        stats
+    }
+}
+
+pub fn table_info(rainbow_table_path: &str) -> Result<TableInfo, String> {
+    /*
+        Gather what is in a table, or in every generated shard of a sharded one.
+        Entries that cannot be parsed are counted, not reported
+    */
+    let mut info = TableInfo::empty();
+    let mut characters: BTreeSet<char> = BTreeSet::new();
+    if shard::is_sharded_table(rainbow_table_path) {
+        let manifest = ShardManifest::open(rainbow_table_path)?;
+        info.shard_count = Some(manifest.shard_count);
+        info.file_size += file_size(
+            &path::Path::new(rainbow_table_path)
+                .join(shard::MANIFEST_FILE_NAME)
+                .to_string_lossy(),
+        )?;
+        // Shards are only sorted within themselves, which is all lookups need
+        for shard_index in 0..manifest.shard_count {
+            let shard_path = manifest.shard_path(rainbow_table_path, shard_index);
+            if path::Path::new(&shard_path).exists() {
+                info.add_file(&shard_path, &mut characters)?;
+            }
+        }
+    } else {
+        info.add_file(rainbow_table_path, &mut characters)?;
+    }
+    info.charsets.distinct_characters = characters.len();
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn stat<'a>(stats: &'a [(String, StatValue)], name: &str) -> Option<&'a StatValue> {
+        stats
+            .iter()
+            .find(|(stat_name, _)| stat_name == name)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn test_table_info() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        test_utils::write_table(
+            path,
+            StorageFormat::Text,
+            &["potato", "Rice", "p4ssw0rd!", "crème"],
+        );
+
+        let info = table_info(path).unwrap();
+        assert_eq!(4, info.entry_count);
+        assert_eq!(fs::metadata(path).unwrap().len(), info.file_size);
+        assert!(!info.sorted);
+        let expected_lengths: BTreeMap<usize, usize> = [(4, 1), (5, 1), (6, 1), (9, 1)].into();
+        assert_eq!(expected_lengths, info.word_lengths);
+        assert_eq!(
+            CharsetStats {
+                lowercase: 4,
+                uppercase: 1,
+                digits: 1,
+                symbols: 1,
+                non_ascii: 1,
+                distinct_characters: 17,
+            },
+            info.charsets
+        );
+
+        let stats = info.stats();
+        assert_eq!(
+            Some(&StatValue::Text("sha256".to_string())),
+            stat(&stats, "algorithm")
+        );
+        assert_eq!(Some(&StatValue::Count(1)), stat(&stats, "version"));
+        assert_eq!(Some(&StatValue::Count(1)), stat(&stats, "word_length.9"));
+        assert_eq!(None, stat(&stats, "invalid_entries"));
+    }
+
+    #[test]
+    fn test_headerless_table_info() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        fs::write(path, "rice:209f\nnot a table\nsalad:c6c3\n").unwrap();
+        let info = table_info(path).unwrap();
+        assert_eq!(2, info.entry_count);
+        assert_eq!(1, info.invalid_count);
+        assert!(info.sorted);
+        let stats = info.stats();
+        assert_eq!(
+            Some(&StatValue::Text("none".to_string())),
+            stat(&stats, "version")
+        );
+        assert_eq!(Some(&StatValue::Count(1)), stat(&stats, "invalid_entries"));
+    }
+
+    #[test]
+    fn test_sharded_table_info() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let table_dir = format!("{}.shards", temp_file_handler.temp_file_path);
+        let manifest = ShardManifest::new(2).unwrap();
+        manifest.write(&table_dir).unwrap();
+        // Each shard is sorted on its own: 209f... then 838f..., and c6c3... then e91c...
+        test_utils::write_table(
+            &manifest.shard_path(&table_dir, 0),
+            StorageFormat::Text,
+            &["rice", "noodles"],
+        );
+        test_utils::write_table(
+            &manifest.shard_path(&table_dir, 1),
+            StorageFormat::Text,
+            &["salad", "potato"],
+        );
+
+        let info = table_info(&table_dir).unwrap();
+        assert_eq!(Some(2), info.shard_count);
+        assert_eq!(4, info.entry_count);
+        assert!(info.sorted);
+        assert!(info.header.is_some());
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+}
@@ -1,9 +1,28 @@
+pub mod benchmark;
+pub mod bloom;
+pub mod candidates;
+pub mod chains;
+pub mod checkpoint;
+pub mod external_sort;
 pub mod hasher;
+pub mod header;
+pub mod info;
 pub mod operations;
+pub mod output;
+pub mod plan;
+pub mod potfile;
+pub mod progress;
+pub mod rainbowcrack;
 pub mod reader;
+pub mod shard;
+pub mod storage;
+pub mod verify;
 
 #[cfg(test)]
 mod test_utils {
+    use crate::hasher::WordHash;
+    use crate::header::{TableHashing, TableHeader};
+    use crate::storage::{self, StorageFormat};
     use rand::{self, distributions::Alphanumeric, Rng};
     use std::fs;
 
@@ -76,4 +95,24 @@ mod test_utils {
             };
         }
     }
+
+    pub fn word_hashes(hashing: &TableHashing, words: &[&str]) -> Vec<WordHash> {
+        words
+            .iter()
+            .map(|word| WordHash {
+                word: word.to_string(),
+                hash: hashing.hash(word).unwrap(),
+            })
+            .collect()
+    }
+
+    pub fn write_table(path: &str, format: StorageFormat, words: &[&str]) -> Vec<WordHash> {
+        // A sha256 table of `words` in the given order, with its header
+        let word_hashes = word_hashes(&TableHashing::Plain, words);
+        let header = TableHeader::new(TableHashing::Plain, &word_hashes);
+        storage::create_table(path, format)
+            .write(&header, &word_hashes)
+            .unwrap();
+        word_hashes
+    }
 }
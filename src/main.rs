@@ -1,11 +1,172 @@
-use rust_rainbowtable::operations::{HashCracker, Operator, RainbowTableGenerator};
+use rust_rainbowtable::benchmark::BenchmarkHasher;
+use rust_rainbowtable::bloom::parse_false_positive_rate;
+use rust_rainbowtable::candidates::CandidateSource;
+use rust_rainbowtable::chains::{
+    self, parse_distinguished_mask, parse_max_chain_length, ChainMode,
+};
+use rust_rainbowtable::checkpoint::parse_checkpoint_interval;
+use rust_rainbowtable::external_sort::parse_memory_limit;
+use rust_rainbowtable::hasher::accounts::AccountFormat;
+use rust_rainbowtable::hasher::expression::Pipeline;
+use rust_rainbowtable::hasher::{Algorithm, HmacKey, Salt, SaltPosition};
+use rust_rainbowtable::operations::{
+    Attacker, Benchmarker, ChainCracker, ChainTableGenerator, HashCracker, HashIdentifier,
+    LmCracker, Operator, PotfileViewer, RainbowTableGenerator, SaltedHashCracker, TableConverter,
+    TableInspector, TablePlanner, TableVerifier,
+};
+use rust_rainbowtable::output::{OutputFormat, Reporter};
+use rust_rainbowtable::plan::parse_success_probability;
+use rust_rainbowtable::potfile::DEFAULT_POTFILE_PATH;
+use rust_rainbowtable::reader;
+use rust_rainbowtable::shard::parse_shard_count;
+use rust_rainbowtable::storage::{ConvertFormat, StorageFormat};
+use rust_rainbowtable::verify::Sample;
 use std::process::exit;
+use std::rc::Rc;
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 
 const RAINBOW_TABLE_ARG_HELP: &str = "Path to the rainbow table file";
 const WORD_FILE_ARG_HELP: &str = "Path to the word file";
 const HASH_ARG_HELP: &str = "Hash to crack";
+const SALTED_HASH_ARG_HELP: &str = "Salted hash to crack, in the hash:salt format. Can be repeated";
+const TARGET_HASH_ARG_HELP: &str =
+    "Hash to crack, either bare or in the hash:salt format (hash:username for PostgreSQL md5). Can be repeated";
+const HASH_FILE_ARG_HELP: &str = "Path to a file of hashes to crack, one per line";
+const SHADOW_FILE_ARG_HELP: &str =
+    "Path to an /etc/shadow style file of accounts to recover, same as --account-format shadow --account-file";
+const DATABASE_DUMP_ARG_HELP: &str =
+    "Path to a database user dump of account:hash lines (MySQL, PostgreSQL md5, MSSQL 2012, Oracle 11g)";
+const ACCOUNT_FILE_ARG_HELP: &str =
+    "Path to a list of accounts and their hashes, in the --account-format format";
+const ACCOUNT_FORMAT_ARG_HELP: &str =
+    "Format of the account file: user-hash (account:hash), shadow, htpasswd or pwdump";
+const TIME_BUDGET_ARG_HELP: &str =
+    "Maximum number of seconds to spend on each slow hash (crypt, bcrypt, scrypt, PBKDF2, Argon2)";
+const HMAC_KEY_ARG_HELP: &str = "HMAC key as a hex string";
+const HMAC_KEY_FILE_ARG_HELP: &str = "Path to a file containing the raw HMAC key";
+const HMAC_ALGORITHM_ARG_HELP: &str =
+    "Digest used for HMAC, e.g. md5, sha1, sha256, sha512, sha3_256, blake2b, blake2s_128 or blake3";
+const EXPRESSION_ARG_HELP: &str =
+    "Composed hash expression, e.g. sha1(md5(p)) or md5(md5(p).salt). `_raw` suffixes give raw intermediate digests";
+const LM_TABLE_ARG_HELP: &str =
+    "Generate an LM table of uppercased 7 character password halves instead of whole words";
+const LM_HASH_ARG_HELP: &str = "LM hash to crack, 32 hex characters";
+const NTLM_HASH_ARG_HELP: &str =
+    "NTLM hash of the same account, used to recover the password's case";
+const BLOOM_FILTER_ARG_HELP: &str =
+    "Also write a Bloom filter of the table's hashes to <table>.bloom, letting crack-hash skip the table for hashes it does not hold";
+const BLOOM_FP_RATE_ARG_HELP: &str = "False positive rate the Bloom filter is sized for";
+const SHARDS_ARG_HELP: &str =
+    "Split the table by the leading bits of each digest into this many shard files (a power of two) under the -r directory, next to a manifest";
+const SHARD_ARG_HELP: &str =
+    "Only generate the shard with this index, so shards can be generated independently, e.g. on different machines";
+const STORAGE_ARG_HELP: &str =
+    "How the table is stored: text (word:hash lines), sqlite (a database indexed on the hash) or binary (fixed size records of raw digests). crack-hash detects it on its own";
+const SORT_ARG_HELP: &str =
+    "Write the table in hash order, sorting in runs on disk and merging them when it does not fit in --memory-limit";
+const MEMORY_LIMIT_ARG_HELP: &str =
+    "Roughly how much of the table to hold in memory while sorting with --sort or --checkpoint-interval, in bytes or with a K, M or G suffix";
+const CHECKPOINT_INTERVAL_ARG_HELP: &str =
+    "Checkpoint the run every N entries, so that an interrupted run can be continued with --resume. Implies --sort";
+const RESUME_ARG_HELP: &str =
+    "Continue an interrupted checkpointed run from its last checkpoint, with the same settings and word file. Implies --sort";
+const TABLE_PATH_ARG_HELP: &str =
+    "Path to the rainbow table file, or the directory of a sharded table";
+const SAMPLE_ARG_HELP: &str =
+    "How many randomly picked words to hash again and check against their digests, or full for every word";
+const CONVERT_INPUT_ARG_HELP: &str =
+    "Path to the table to convert: a text, sqlite or binary table, or a RainbowCrack .rt file";
+const CONVERT_OUTPUT_ARG_HELP: &str =
+    "Path to write the converted table to. For rt, the directory the RainbowCrack named .rt file is written to";
+const CONVERT_FORMAT_ARG_HELP: &str =
+    "Format to convert to: text, sqlite, binary or rt (RainbowCrack). For .rtc, convert to rt and run rt2rtc";
+const CHARSET_ARG_HELP: &str =
+    "RainbowCrack charset to export with, e.g. loweralpha-numeric. Defaults to the smallest one holding every word";
+const THREADS_ARG_HELP: &str =
+    "Threads for the multi-threaded measurements. Defaults to every available core";
+const DURATION_MS_ARG_HELP: &str =
+    "How long each measurement runs for, in milliseconds (500 by default)";
+const TABLE_ENTRIES_ARG_HELP: &str =
+    "Entries in the tables that lookups are timed against (100000 by default)";
+const PLAN_CHARSET_ARG_HELP: &str = "RainbowCrack charset of the keyspace, e.g. loweralpha-numeric";
+const MIN_LENGTH_ARG_HELP: &str = "Shortest plaintext length in the keyspace";
+const MAX_LENGTH_ARG_HELP: &str = "Longest plaintext length in the keyspace";
+const PLAN_ALGORITHM_ARG_HELP: &str =
+    "Algorithm the tables are for: any supported algorithm, lm or ntlm";
+const SUCCESS_PROBABILITY_ARG_HELP: &str =
+    "Chance of a hash being found that the tables should reach, e.g. 0.999 or 99.9%";
+const CHAIN_LENGTH_ARG_HELP: &str = "Length of each chain (3800 by default)";
+const TABLE_COUNT_ARG_HELP: &str =
+    "Number of tables. Defaults to as few as can reach the success probability";
+const HASH_RATE_ARG_HELP: &str =
+    "Hashes per second to estimate times with, instead of measuring it on every core";
+const CHAIN_OUTPUT_DIR_ARG_HELP: &str =
+    "Directory to write the chain table to, named after how it was generated";
+const CHAIN_ALGORITHM_ARG_HELP: &str = "Algorithm the chains hash with: lm, md5, sha1 or sha256";
+const CHAIN_COUNT_ARG_HELP: &str = "Number of chains to generate";
+const TABLE_INDEX_ARG_HELP: &str =
+    "Index of the table in a set, which each use their own reduction function";
+const DP_MASK_ARG_HELP: &str =
+    "End chains at distinguished points: hashes whose first 8 bytes, read little-endian, have every bit of this mask clear, e.g. 0xfff";
+const MAX_CHAIN_LENGTH_ARG_HELP: &str =
+    "Hashes after which a distinguished point chain is dropped (20 times the expected length by default)";
+const CHAIN_TABLE_ARG_HELP: &str = "Path to a .rt or distinguished point chain table";
+const IDENTIFY_HASH_ARG_HELP: &str = "Hash to identify. Can be repeated";
+const POTFILE_ARG_HELP: &str = "Path to the potfile of previously cracked hash:plaintext pairs";
+const NO_POTFILE_ARG_HELP: &str = "Neither read nor write the potfile";
+const SHOW_HASH_FILE_ARG_HELP: &str =
+    "Path to a file of hashes, one per line, to print the cracked plaintexts of";
+const RULE_FILE_ARG_HELP: &str = "Path to a file of rules to apply to every word";
+const MASK_ARG_HELP: &str = "Mask to generate candidates from, e.g. ?u?l?l?d?d";
+const SALT_ARG_HELP: &str = "Global salt to hash every word with";
+const SALT_POSITION_ARG_HELP: &str =
+    "Where the salt is placed relative to the word: prefix or suffix";
+const OUTPUT_FORMAT_ARG_HELP: &str =
+    "Format of results, stats and errors on stdout: text, json, jsonl or csv";
+const QUIET_ARG_HELP: &str =
+    "Leave out progress and informational messages, printing only results, warnings and errors";
+
+#[derive(Args)]
+pub struct HmacArgs {
+    #[clap(long = "hmac-key", conflicts_with = "hmac-key-file", help = HMAC_KEY_ARG_HELP)]
+    hmac_key: Option<String>,
+    #[clap(long = "hmac-key-file", help = HMAC_KEY_FILE_ARG_HELP)]
+    hmac_key_file: Option<String>,
+    #[clap(long = "hmac-algorithm", default_value = "sha256", help = HMAC_ALGORITHM_ARG_HELP)]
+    hmac_algorithm: Algorithm,
+}
+
+impl HmacArgs {
+    fn hmac_key(&self) -> Result<Option<HmacKey>, String> {
+        match (&self.hmac_key, &self.hmac_key_file) {
+            (Some(hex_key), _) => Ok(Some(HmacKey::from_hex(self.hmac_algorithm, hex_key)?)),
+            (None, Some(key_file)) => Ok(Some(HmacKey::new(
+                self.hmac_algorithm,
+                reader::read_key_file(key_file)?,
+            )?)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct PotfileArgs {
+    #[clap(long = "potfile", default_value = DEFAULT_POTFILE_PATH, help = POTFILE_ARG_HELP)]
+    potfile_path: String,
+    #[clap(long = "no-potfile", help = NO_POTFILE_ARG_HELP)]
+    no_potfile: bool,
+}
+
+impl PotfileArgs {
+    fn potfile_path(self) -> Option<String> {
+        match self.no_potfile {
+            true => None,
+            false => Some(self.potfile_path),
+        }
+    }
+}
 
 #[derive(Subcommand)]
 pub enum Commands {
@@ -14,12 +175,176 @@ pub enum Commands {
         rainbow_table_file_path: String,
         #[clap(short = 'w', long = "word-file", help = WORD_FILE_ARG_HELP)]
         word_file_path: String,
+        #[clap(short = 's', long = "salt", conflicts_with_all = &["hmac-key", "hmac-key-file"], help = SALT_ARG_HELP)]
+        salt: Option<String>,
+        #[clap(short = 'p', long = "salt-position", default_value = "prefix", help = SALT_POSITION_ARG_HELP)]
+        salt_position: SaltPosition,
+        #[clap(flatten)]
+        hmac_args: HmacArgs,
+        #[clap(short = 'e', long = "expression", conflicts_with_all = &["hmac-key", "hmac-key-file"], help = EXPRESSION_ARG_HELP)]
+        pipeline: Option<Pipeline>,
+        #[clap(long = "lm", conflicts_with_all = &["salt", "hmac-key", "hmac-key-file", "pipeline"], help = LM_TABLE_ARG_HELP)]
+        lm: bool,
+        #[clap(long = "bloom-filter", help = BLOOM_FILTER_ARG_HELP)]
+        bloom_filter: bool,
+        #[clap(long = "bloom-fp-rate", requires = "bloom-filter", default_value = "0.01", parse(try_from_str = parse_false_positive_rate), help = BLOOM_FP_RATE_ARG_HELP)]
+        bloom_false_positive_rate: f64,
+        #[clap(long = "shards", conflicts_with = "lm", parse(try_from_str = parse_shard_count), help = SHARDS_ARG_HELP)]
+        shard_count: Option<usize>,
+        #[clap(long = "shard", requires = "shard-count", help = SHARD_ARG_HELP)]
+        shard_index: Option<usize>,
+        #[clap(long = "storage", default_value = "text", help = STORAGE_ARG_HELP)]
+        storage_format: StorageFormat,
+        #[clap(long = "sort", help = SORT_ARG_HELP)]
+        sort: bool,
+        #[clap(long = "memory-limit", default_value = "1G", parse(try_from_str = parse_memory_limit), help = MEMORY_LIMIT_ARG_HELP)]
+        memory_limit: usize,
+        #[clap(long = "checkpoint-interval", parse(try_from_str = parse_checkpoint_interval), help = CHECKPOINT_INTERVAL_ARG_HELP)]
+        checkpoint_interval: Option<usize>,
+        #[clap(long = "resume", help = RESUME_ARG_HELP)]
+        resume: bool,
     },
     CrackHash {
         #[clap(short = 'r', long = "rainbow-table-file", help = RAINBOW_TABLE_ARG_HELP)]
         rainbow_table_file_path: String,
         #[clap(short = 'H', long = "hash", help = HASH_ARG_HELP)]
         hash: String,
+        #[clap(flatten)]
+        potfile_args: PotfileArgs,
+    },
+    Identify {
+        #[clap(required = true, help = IDENTIFY_HASH_ARG_HELP)]
+        hashes: Vec<String>,
+    },
+    CrackLm {
+        #[clap(short = 'r', long = "rainbow-table-file", help = RAINBOW_TABLE_ARG_HELP)]
+        rainbow_table_file_path: String,
+        #[clap(short = 'H', long = "hash", help = LM_HASH_ARG_HELP)]
+        lm_hash: String,
+        #[clap(long = "ntlm", help = NTLM_HASH_ARG_HELP)]
+        ntlm_hash: Option<String>,
+        #[clap(flatten)]
+        potfile_args: PotfileArgs,
+    },
+    CrackSaltedHash {
+        #[clap(short = 'w', long = "word-file", help = WORD_FILE_ARG_HELP)]
+        word_file_path: String,
+        #[clap(short = 'H', long = "hash", required = true, help = SALTED_HASH_ARG_HELP)]
+        salted_hashes: Vec<String>,
+        #[clap(short = 'p', long = "salt-position", default_value = "prefix", help = SALT_POSITION_ARG_HELP)]
+        salt_position: SaltPosition,
+        #[clap(flatten)]
+        potfile_args: PotfileArgs,
+    },
+    Attack {
+        #[clap(short = 'H', long = "hash", required_unless_present_any = &["hash-file-path", "shadow-file-path", "database-dump-file-path", "account-file-path"], help = TARGET_HASH_ARG_HELP)]
+        hashes: Vec<String>,
+        #[clap(long = "hash-file", help = HASH_FILE_ARG_HELP)]
+        hash_file_path: Option<String>,
+        #[clap(long = "shadow-file", conflicts_with_all = &["account-file-path", "account-format"], help = SHADOW_FILE_ARG_HELP)]
+        shadow_file_path: Option<String>,
+        #[clap(long = "db-dump", help = DATABASE_DUMP_ARG_HELP)]
+        database_dump_file_path: Option<String>,
+        #[clap(long = "account-file", help = ACCOUNT_FILE_ARG_HELP)]
+        account_file_path: Option<String>,
+        #[clap(long = "account-format", default_value = "user-hash", help = ACCOUNT_FORMAT_ARG_HELP)]
+        account_format: AccountFormat,
+        #[clap(short = 'w', long = "word-file", required_unless_present = "mask", conflicts_with = "mask", help = WORD_FILE_ARG_HELP)]
+        word_file_path: Option<String>,
+        #[clap(long = "rules", conflicts_with = "mask", help = RULE_FILE_ARG_HELP)]
+        rule_file_path: Option<String>,
+        #[clap(short = 'm', long = "mask", help = MASK_ARG_HELP)]
+        mask: Option<String>,
+        #[clap(short = 'p', long = "salt-position", default_value = "prefix", help = SALT_POSITION_ARG_HELP)]
+        salt_position: SaltPosition,
+        #[clap(long = "time-budget", help = TIME_BUDGET_ARG_HELP)]
+        time_budget: Option<u64>,
+        #[clap(flatten)]
+        hmac_args: HmacArgs,
+        #[clap(short = 'e', long = "expression", conflicts_with_all = &["hmac-key", "hmac-key-file"], help = EXPRESSION_ARG_HELP)]
+        pipeline: Option<Pipeline>,
+        #[clap(flatten)]
+        potfile_args: PotfileArgs,
+    },
+    Show {
+        #[clap(long = "hash-file", help = SHOW_HASH_FILE_ARG_HELP)]
+        hash_file_path: String,
+        #[clap(long = "potfile", default_value = DEFAULT_POTFILE_PATH, help = POTFILE_ARG_HELP)]
+        potfile_path: String,
+    },
+    TableInfo {
+        #[clap(short = 'r', long = "rainbow-table-file", help = TABLE_PATH_ARG_HELP)]
+        rainbow_table_file_path: String,
+    },
+    VerifyTable {
+        #[clap(short = 'r', long = "rainbow-table-file", help = TABLE_PATH_ARG_HELP)]
+        rainbow_table_file_path: String,
+        #[clap(long = "sample", default_value = "full", help = SAMPLE_ARG_HELP)]
+        sample: Sample,
+    },
+    ConvertTable {
+        #[clap(short = 'i', long = "input", help = CONVERT_INPUT_ARG_HELP)]
+        input_path: String,
+        #[clap(short = 'o', long = "output", help = CONVERT_OUTPUT_ARG_HELP)]
+        output_path: String,
+        #[clap(long = "to", help = CONVERT_FORMAT_ARG_HELP)]
+        format: ConvertFormat,
+        #[clap(long = "charset", help = CHARSET_ARG_HELP)]
+        charset: Option<String>,
+    },
+    Plan {
+        #[clap(long = "charset", help = PLAN_CHARSET_ARG_HELP)]
+        charset: String,
+        #[clap(long = "min-length", default_value = "1", help = MIN_LENGTH_ARG_HELP)]
+        min_length: usize,
+        #[clap(long = "max-length", help = MAX_LENGTH_ARG_HELP)]
+        max_length: usize,
+        #[clap(short = 'a', long = "algorithm", default_value = "sha256", help = PLAN_ALGORITHM_ARG_HELP)]
+        hasher: BenchmarkHasher,
+        #[clap(short = 'p', long = "success-probability", parse(try_from_str = parse_success_probability), help = SUCCESS_PROBABILITY_ARG_HELP)]
+        success_probability: f64,
+        #[clap(long = "chain-length", help = CHAIN_LENGTH_ARG_HELP)]
+        chain_length: Option<u64>,
+        #[clap(long = "tables", help = TABLE_COUNT_ARG_HELP)]
+        table_count: Option<u64>,
+        #[clap(long = "hash-rate", help = HASH_RATE_ARG_HELP)]
+        hash_rate: Option<u64>,
+    },
+    GenerateChains {
+        #[clap(short = 'o', long = "output-dir", help = CHAIN_OUTPUT_DIR_ARG_HELP)]
+        output_dir: String,
+        #[clap(short = 'a', long = "algorithm", default_value = "sha256", help = CHAIN_ALGORITHM_ARG_HELP)]
+        algorithm: String,
+        #[clap(long = "charset", help = PLAN_CHARSET_ARG_HELP)]
+        charset: String,
+        #[clap(long = "min-length", default_value = "1", help = MIN_LENGTH_ARG_HELP)]
+        min_length: usize,
+        #[clap(long = "max-length", help = MAX_LENGTH_ARG_HELP)]
+        max_length: usize,
+        #[clap(long = "chain-count", help = CHAIN_COUNT_ARG_HELP)]
+        chain_count: u64,
+        #[clap(long = "table-index", default_value = "0", help = TABLE_INDEX_ARG_HELP)]
+        table_index: u64,
+        #[clap(long = "chain-length", conflicts_with = "dp-mask", help = CHAIN_LENGTH_ARG_HELP)]
+        chain_length: Option<u64>,
+        #[clap(long = "dp-mask", parse(try_from_str = parse_distinguished_mask), help = DP_MASK_ARG_HELP)]
+        dp_mask: Option<u64>,
+        #[clap(long = "max-chain-length", requires = "dp-mask", parse(try_from_str = parse_max_chain_length), help = MAX_CHAIN_LENGTH_ARG_HELP)]
+        max_chain_length: Option<u64>,
+    },
+    CrackChains {
+        #[clap(short = 'r', long = "rainbow-table-file", help = CHAIN_TABLE_ARG_HELP)]
+        rainbow_table_file_path: String,
+        #[clap(short = 'H', long = "hash", help = HASH_ARG_HELP)]
+        hash: String,
+    },
+    Benchmark {
+        #[clap(long = "threads", help = THREADS_ARG_HELP)]
+        threads: Option<usize>,
+        #[clap(long = "duration-ms", help = DURATION_MS_ARG_HELP)]
+        duration_ms: Option<u64>,
+        #[clap(long = "table-entries", help = TABLE_ENTRIES_ARG_HELP)]
+        table_entries: Option<usize>,
     },
 }
 
@@ -27,23 +352,261 @@ pub enum Commands {
 struct Cli {
     #[clap(subcommand)]
     pub command: Commands,
+    #[clap(long = "output-format", global = true, default_value = "text", help = OUTPUT_FORMAT_ARG_HELP)]
+    pub output_format: OutputFormat,
+    #[clap(short = 'q', long = "quiet", global = true, help = QUIET_ARG_HELP)]
+    pub quiet: bool,
+}
+
+fn hmac_key_or_exit(hmac_args: &HmacArgs, reporter: &Reporter) -> Option<HmacKey> {
+    match hmac_args.hmac_key() {
+        Ok(hmac_key) => hmac_key,
+        Err(e) => {
+            reporter.error(&e);
+            reporter.finish();
+            exit(reader::FILE_OPERATION_ERROR);
+        }
+    }
 }
 
 fn main() {
     let args = Cli::parse();
+    let mut reporter = Reporter::new(args.output_format);
+    reporter.quiet = args.quiet;
+    let reporter = Rc::new(reporter);
     let operator: Box<dyn Operator> = match args.command {
         Commands::CrackHash {
             rainbow_table_file_path,
             hash,
-        } => Box::new(HashCracker::new(rainbow_table_file_path, hash)),
+            potfile_args,
+        } => {
+            let mut cracker = HashCracker::new(rainbow_table_file_path, hash);
+            cracker.potfile_path = potfile_args.potfile_path();
+            cracker.reporter = Rc::clone(&reporter);
+            Box::new(cracker)
+        }
         Commands::GenerateTable {
             rainbow_table_file_path,
             word_file_path,
-        } => Box::new(RainbowTableGenerator::new(
+            salt,
+            salt_position,
+            hmac_args,
+            pipeline,
+            lm,
+            bloom_filter,
+            bloom_false_positive_rate,
+            shard_count,
+            shard_index,
+            storage_format,
+            sort,
+            memory_limit,
+            checkpoint_interval,
+            resume,
+        } => {
+            let mut generator = RainbowTableGenerator::new(word_file_path, rainbow_table_file_path);
+            generator.salt = salt.map(|value| Salt {
+                value,
+                position: salt_position,
+            });
+            generator.hmac_key = hmac_key_or_exit(&hmac_args, &reporter);
+            generator.pipeline = pipeline;
+            generator.lm = lm;
+            generator.bloom_false_positive_rate = bloom_filter.then_some(bloom_false_positive_rate);
+            generator.shard_count = shard_count;
+            generator.shard_index = shard_index;
+            generator.storage_format = storage_format;
+            generator.sort = sort;
+            generator.memory_limit = memory_limit;
+            generator.checkpoint_interval = checkpoint_interval;
+            generator.resume = resume;
+            generator.reporter = Rc::clone(&reporter);
+            Box::new(generator)
+        }
+        Commands::Identify { hashes } => {
+            let mut identifier = HashIdentifier::new(hashes);
+            identifier.reporter = Rc::clone(&reporter);
+            Box::new(identifier)
+        }
+        Commands::CrackLm {
+            rainbow_table_file_path,
+            lm_hash,
+            ntlm_hash,
+            potfile_args,
+        } => {
+            let mut cracker = LmCracker::new(rainbow_table_file_path, lm_hash);
+            cracker.ntlm_hash = ntlm_hash;
+            cracker.potfile_path = potfile_args.potfile_path();
+            cracker.reporter = Rc::clone(&reporter);
+            Box::new(cracker)
+        }
+        Commands::CrackSaltedHash {
             word_file_path,
+            salted_hashes,
+            salt_position,
+            potfile_args,
+        } => {
+            let mut cracker = SaltedHashCracker::new(word_file_path, salted_hashes, salt_position);
+            cracker.potfile_path = potfile_args.potfile_path();
+            cracker.reporter = Rc::clone(&reporter);
+            Box::new(cracker)
+        }
+        Commands::Attack {
+            hashes,
+            hash_file_path,
+            shadow_file_path,
+            database_dump_file_path,
+            account_file_path,
+            account_format,
+            word_file_path,
+            rule_file_path,
+            mask,
+            salt_position,
+            time_budget,
+            hmac_args,
+            pipeline,
+            potfile_args,
+        } => {
+            let candidate_source = match (word_file_path, mask) {
+                (Some(word_file_path), _) => CandidateSource::Wordlist {
+                    word_file_path,
+                    rule_file_path,
+                },
+                (None, Some(mask)) => CandidateSource::Mask(mask),
+                (None, None) => unreachable!("clap requires either a word file or a mask"),
+            };
+            let mut attacker =
+                Attacker::new(candidate_source, hashes, hash_file_path, salt_position);
+            attacker.database_dump_file_path = database_dump_file_path;
+            // --shadow-file is only a shorthand for a shadow format account file
+            (attacker.account_file_path, attacker.account_format) = match shadow_file_path {
+                Some(shadow_file_path) => (Some(shadow_file_path), AccountFormat::Shadow),
+                None => (account_file_path, account_format),
+            };
+            attacker.time_budget = time_budget.map(Duration::from_secs);
+            attacker.hmac_key = hmac_key_or_exit(&hmac_args, &reporter);
+            attacker.pipeline = pipeline;
+            attacker.potfile_path = potfile_args.potfile_path();
+            attacker.reporter = Rc::clone(&reporter);
+            Box::new(attacker)
+        }
+        Commands::Show {
+            hash_file_path,
+            potfile_path,
+        } => {
+            let mut viewer = PotfileViewer::new(hash_file_path, potfile_path);
+            viewer.reporter = Rc::clone(&reporter);
+            Box::new(viewer)
+        }
+        Commands::TableInfo {
+            rainbow_table_file_path,
+        } => {
+            let mut inspector = TableInspector::new(rainbow_table_file_path);
+            inspector.reporter = Rc::clone(&reporter);
+            Box::new(inspector)
+        }
+        Commands::VerifyTable {
             rainbow_table_file_path,
-        )),
+            sample,
+        } => {
+            let mut verifier = TableVerifier::new(rainbow_table_file_path);
+            verifier.sample = sample;
+            verifier.reporter = Rc::clone(&reporter);
+            Box::new(verifier)
+        }
+        Commands::ConvertTable {
+            input_path,
+            output_path,
+            format,
+            charset,
+        } => {
+            let mut converter = TableConverter::new(input_path, output_path, format);
+            converter.charset = charset;
+            converter.reporter = Rc::clone(&reporter);
+            Box::new(converter)
+        }
+        Commands::Plan {
+            charset,
+            min_length,
+            max_length,
+            hasher,
+            success_probability,
+            chain_length,
+            table_count,
+            hash_rate,
+        } => {
+            let mut planner =
+                TablePlanner::new(charset, min_length, max_length, success_probability);
+            planner.hasher = hasher;
+            if let Some(chain_length) = chain_length {
+                planner.chain_length = chain_length;
+            }
+            planner.table_count = table_count;
+            planner.hash_rate = hash_rate;
+            planner.reporter = Rc::clone(&reporter);
+            Box::new(planner)
+        }
+        Commands::GenerateChains {
+            output_dir,
+            algorithm,
+            charset,
+            min_length,
+            max_length,
+            chain_count,
+            table_index,
+            chain_length,
+            dp_mask,
+            max_chain_length,
+        } => {
+            let mut generator = ChainTableGenerator::new(
+                output_dir,
+                algorithm,
+                charset,
+                min_length,
+                max_length,
+                chain_count,
+            );
+            generator.table_index = table_index;
+            if let Some(mask) = dp_mask {
+                generator.mode = ChainMode::DistinguishedPoint {
+                    mask,
+                    max_chain_length: max_chain_length
+                        .unwrap_or_else(|| chains::default_max_chain_length(mask)),
+                };
+            } else if let Some(chain_length) = chain_length {
+                generator.mode = ChainMode::Rainbow { chain_length };
+            }
+            generator.reporter = Rc::clone(&reporter);
+            Box::new(generator)
+        }
+        Commands::CrackChains {
+            rainbow_table_file_path,
+            hash,
+        } => {
+            let mut cracker = ChainCracker::new(rainbow_table_file_path, hash);
+            cracker.reporter = Rc::clone(&reporter);
+            Box::new(cracker)
+        }
+        Commands::Benchmark {
+            threads,
+            duration_ms,
+            table_entries,
+        } => {
+            let mut benchmarker = Benchmarker::default();
+            if let Some(threads) = threads {
+                benchmarker.threads = threads;
+            }
+            if let Some(duration_ms) = duration_ms {
+                benchmarker.duration = Duration::from_millis(duration_ms);
+            }
+            if let Some(table_entries) = table_entries {
+                benchmarker.table_entries = table_entries;
+            }
+            benchmarker.reporter = Rc::clone(&reporter);
+            Box::new(benchmarker)
+        }
     };
     let exit_code = operator.run();
+    // `exit` skips destructors, so buffered JSON output has to be flushed first
+    reporter.finish();
     exit(exit_code);
 }
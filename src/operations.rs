@@ -1,17 +1,104 @@
+use crate::benchmark::{self, BenchmarkHasher};
+use crate::bloom::{self, BloomFilter};
+use crate::candidates::CandidateSource;
+use crate::chains::{ChainMode, ChainSpace, ChainTable};
+use crate::checkpoint::{self, Checkpoint};
+use crate::external_sort::{self, ExternalSorter};
+use crate::hasher::accounts::{self, AccountFormat};
+use crate::hasher::expression::Pipeline;
+use crate::hasher::{crypt, database, htpasswd, identify, lm, slow};
+use crate::header::{HeaderBuilder, TableHashing, TableHeader};
+use crate::info::{self, StatValue};
+use crate::output::{Event, Record, Reporter};
+use crate::plan::{self, Plan};
+use crate::potfile::Potfile;
+use crate::progress::{self, Progress};
+use crate::rainbowcrack;
+use crate::shard::{self, ShardManifest};
+use crate::storage::{self, ConvertFormat, StorageFormat, TableStorage};
+use crate::verify::{self, Sample};
 use crate::{hasher, reader};
-use std::io::{stdin, BufRead, Write};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{stdin, BufRead, Seek, SeekFrom};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use std::{fs, path};
 
 const CRACK_HASH_RUNTIME_ERROR_EXIT_CODE: i32 = 3;
 const INPUT_READ_ERROR: i32 = 4;
+const TABLE_VERIFICATION_FAILED_EXIT_CODE: i32 = 5;
+// Words hashed at a time when generating a table, between progress updates
+const HASH_CHUNK_WORDS: usize = 4096;
 
 pub trait Operator {
     fn run(&self) -> i32;
 }
 
+fn open_potfile(
+    reporter: &Reporter,
+    potfile_path: &Option<String>,
+) -> Result<Option<Potfile>, String> {
+    let potfile = potfile_path.as_deref().map(Potfile::open).transpose()?;
+    if let Some(potfile) = &potfile {
+        warn_invalid_lines(reporter, potfile);
+    }
+    Ok(potfile)
+}
+
+fn warn_invalid_lines(reporter: &Reporter, potfile: &Potfile) {
+    for invalid_line in &potfile.invalid_lines {
+        reporter.warning(invalid_line);
+    }
+}
+
+fn record_cracked(reporter: &Reporter, potfile: &mut Option<Potfile>, hash: &str, word: &str) {
+    // A potfile that cannot be written to should not hide the result itself
+    if let Some(potfile) = potfile {
+        if let Err(e) = potfile.append(hash, word) {
+            reporter.error(&e);
+        }
+    }
+}
+
+fn confirm_overwrite<R: BufRead>(reader: &mut R, prompt: &str) -> Result<bool, String> {
+    // Ask on stderr, so the answer is not mixed into the output. Enter alone means yes
+    eprintln!("{} Overwrite? (Y/n)", prompt);
+    let mut buf = String::new();
+    if reader.read_line(&mut buf).is_err() {
+        return Err(String::from("Error while reading input!"));
+    }
+    Ok(matches!(buf.chars().next(), Some('y' | 'Y' | '\n')))
+}
+
 pub struct RainbowTableGenerator {
     pub word_file_path: String,
     pub rainbow_table_file_path: String,
+    // Global salt applied to every word in the table
+    pub salt: Option<hasher::Salt>,
+    // Generate HMAC(key, word) instead of a plain digest
+    pub hmac_key: Option<hasher::HmacKey>,
+    // Composed hash expression. The global salt, if any, fills in its `salt` variable
+    pub pipeline: Option<Pipeline>,
+    // Write LM password halves instead of whole words
+    pub lm: bool,
+    // Also write a Bloom filter of the table's hashes, sized for this false positive rate
+    pub bloom_false_positive_rate: Option<f64>,
+    // Split the table by digest prefix into this many files, under `rainbow_table_file_path`
+    pub shard_count: Option<usize>,
+    // Only generate this one shard, so shards can be generated independently
+    pub shard_index: Option<usize>,
+    // What the table, or each of its shards, is stored as
+    pub storage_format: StorageFormat,
+    // Write the entries in hash order, sorting in runs on disk when they exceed `memory_limit`
+    pub sort: bool,
+    // In bytes, roughly how much of the table is held in memory while sorting
+    pub memory_limit: usize,
+    // Checkpoint the run after this many entries, so that it can be resumed. Only sorted runs
+    // are checkpointed, so this sorts the table as `sort` does
+    pub checkpoint_interval: Option<usize>,
+    // Continue a checkpointed run from its last checkpoint instead of starting over
+    pub resume: bool,
+    pub reporter: Rc<Reporter>,
 }
 
 impl RainbowTableGenerator {
@@ -19,7 +106,133 @@ impl RainbowTableGenerator {
         RainbowTableGenerator {
             word_file_path,
             rainbow_table_file_path,
+            salt: None,
+            hmac_key: None,
+            pipeline: None,
+            lm: false,
+            bloom_false_positive_rate: None,
+            shard_count: None,
+            shard_index: None,
+            storage_format: StorageFormat::Text,
+            sort: false,
+            memory_limit: external_sort::DEFAULT_MEMORY_LIMIT,
+            checkpoint_interval: None,
+            resume: false,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn serialize_hashes(&self, words: Vec<String>) -> Result<Vec<String>, String> {
+        if self.lm {
+            if self.pipeline.is_some() || self.salt.is_some() || self.hmac_key.is_some() {
+                return Err(String::from(
+                    "LM tables cannot be combined with a salt, HMAC key or hash expression",
+                ));
+            }
+            return Ok(hasher::serialize_lm_hashes(words));
+        }
+        if let Some(pipeline) = &self.pipeline {
+            if self.hmac_key.is_some() {
+                return Err(String::from(
+                    "A hash expression cannot be combined with an HMAC key",
+                ));
+            }
+            let salt = self.salt.as_ref().map(|salt| salt.value.as_str());
+            if pipeline.uses_salt() && salt.is_none() {
+                return Err(format!("Hash expression {} requires a salt", pipeline));
+            }
+            return Ok(hasher::serialize_pipeline_hashes(words, pipeline, salt));
+        }
+        match (&self.salt, &self.hmac_key) {
+            (Some(_), Some(_)) => Err(String::from("A salt cannot be combined with an HMAC key")),
+            (Some(salt), None) => Ok(hasher::serialize_salted_hashes(words, salt)),
+            (None, Some(hmac_key)) => Ok(hasher::serialize_hmac_hashes(words, hmac_key)),
+            (None, None) => Ok(hasher::serialize_hashes(words)),
+        }
+    }
+
+    fn hash_words(&self, words: Vec<String>) -> Result<Vec<String>, String> {
+        // Hash a chunk at a time, showing progress along the way
+        self.serialize_hashes(Vec::new())?;
+        let mut progress = self
+            .reporter
+            .progress("Generating", "words", Some(words.len() as u64));
+        let mut serialized_hashes: Vec<String> = Vec::with_capacity(words.len());
+        for chunk in words.chunks(HASH_CHUNK_WORDS) {
+            serialized_hashes.extend(self.serialize_hashes(chunk.to_vec())?);
+            progress.add(chunk.len() as u64);
+        }
+        progress.finish();
+        if self.lm {
+            // Each distinct half is written once, also when it turns up in another chunk
+            let mut seen: HashSet<String> = HashSet::new();
+            serialized_hashes.retain(|serialized_hash| seen.insert(serialized_hash.clone()));
+        }
+        Ok(serialized_hashes)
+    }
+
+    fn table_hashing(&self) -> TableHashing {
+        // What goes into the header, mirroring the choice made in `serialize_hashes`
+        if self.lm {
+            return TableHashing::Lm;
+        }
+        if let Some(pipeline) = &self.pipeline {
+            let salt = self.salt.as_ref().map(|salt| salt.value.clone());
+            return TableHashing::Pipeline(pipeline.clone(), salt);
+        }
+        match (&self.salt, &self.hmac_key) {
+            (_, Some(hmac_key)) => TableHashing::Hmac(hmac_key.algorithm),
+            (Some(salt), None) => TableHashing::Salted(salt.clone()),
+            (None, None) => TableHashing::Plain,
+        }
+    }
+
+    fn partition_tables(
+        &self,
+        serialized_hashes: Vec<String>,
+    ) -> Result<Vec<(String, Vec<hasher::WordHash>)>, String> {
+        /*
+            Split the table into the files it is written to: the table file itself,
+            or one file per shard. When only a single shard is generated, the rest
+            of the entries are dropped
+        */
+        let word_hashes = serialized_hashes
+            .iter()
+            .filter_map(|serialized_hash| hasher::split_serialized_hash(serialized_hash))
+            .collect::<Vec<hasher::WordHash>>();
+        let shard_count = match self.shard_count {
+            Some(shard_count) => shard_count,
+            None => return Ok(vec![(self.rainbow_table_file_path.clone(), word_hashes)]),
+        };
+        let manifest = ShardManifest::new(shard_count)?;
+        if let Some(shard_index) = self.shard_index {
+            if shard_index >= shard_count {
+                return Err(format!(
+                    "Shard {} does not exist in a table of {} shards",
+                    shard_index, shard_count
+                ));
+            }
+        }
+        let mut shards: Vec<Vec<hasher::WordHash>> = vec![Vec::new(); shard_count];
+        for word_hash in word_hashes {
+            let shard_index = manifest.shard_index(&word_hash.hash)?;
+            if self.shard_index.is_none() || self.shard_index == Some(shard_index) {
+                shards[shard_index].push(word_hash);
+            }
         }
+        Ok(shards
+            .into_iter()
+            .enumerate()
+            .filter(|(shard_index, _)| {
+                self.shard_index.is_none() || self.shard_index == Some(*shard_index)
+            })
+            .map(|(shard_index, shard)| {
+                (
+                    manifest.shard_path(&self.rainbow_table_file_path, shard_index),
+                    shard,
+                )
+            })
+            .collect())
     }
 
     fn write_hashes_to_file<R: BufRead>(
@@ -27,298 +240,3458 @@ impl RainbowTableGenerator {
         mut reader: R,
         serialized_hashes: Vec<String>,
     ) -> i32 {
-        // Check if file exists, and if it does, prompt to overwrite
-        let path_exists = path::Path::new(&self.rainbow_table_file_path).exists();
-        if path_exists {
-            eprintln!(
-                "{} already exists. Overwrite? (Y/n)",
-                &self.rainbow_table_file_path
-            );
-            let mut buf = String::new();
-            if reader.read_line(&mut buf).is_err() {
-                eprintln!("Error while reading input!");
+        let tables = match self.partition_tables(serialized_hashes) {
+            Ok(tables) => tables,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+
+        let table_paths: Vec<String> = tables
+            .iter()
+            .map(|(table_path, _)| table_path.clone())
+            .collect();
+        match self.confirm_overwrite_tables(&mut reader, &table_paths) {
+            Ok(true) => (),
+            Ok(false) => return 0,
+            Err(e) => {
+                self.reporter.error(&e);
                 return INPUT_READ_ERROR;
             }
-            let first_char: char = buf.as_bytes()[0] as char;
-            if first_char != 'y' && first_char != 'Y' && first_char != '\n' {
-                return 0;
+        }
+
+        if let Err(e) = self.write_manifest() {
+            self.reporter.error(&e);
+            return reader::FILE_OPERATION_ERROR;
+        }
+        for (table_path, word_hashes) in tables {
+            let exit_code = self.write_table_file(&table_path, &word_hashes);
+            if exit_code != 0 {
+                return exit_code;
+            }
+        }
+        0
+    }
+
+    fn confirm_overwrite_tables<R: BufRead>(
+        &self,
+        reader: &mut R,
+        table_paths: &[String],
+    ) -> Result<bool, String> {
+        // Check if any file exists, and if it does, prompt to overwrite
+        let existing_count = table_paths
+            .iter()
+            .filter(|table_path| path::Path::new(table_path).exists())
+            .count();
+        if existing_count == 0 {
+            return Ok(true);
+        }
+        let prompt = match self.shard_count {
+            Some(_) => format!(
+                "{} of the shards in {} already exist.",
+                existing_count, &self.rainbow_table_file_path
+            ),
+            None => format!("{} already exists.", &self.rainbow_table_file_path),
+        };
+        confirm_overwrite(reader, &prompt)
+    }
+
+    fn write_manifest(&self) -> Result<(), String> {
+        match self.shard_count {
+            Some(shard_count) => {
+                ShardManifest::new(shard_count)?.write(&self.rainbow_table_file_path)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn write_table_file(&self, table_path: &str, word_hashes: &[hasher::WordHash]) -> i32 {
+        let header = TableHeader::new(self.table_hashing(), word_hashes);
+        let bloom_filter = self
+            .new_bloom_filter(word_hashes.len())
+            .map(|mut bloom_filter| {
+                for word_hash in word_hashes {
+                    bloom_filter.insert(&word_hash.hash);
+                }
+                bloom_filter
+            });
+        let written = storage::create_table(table_path, self.storage_format)
+            .write(&header, word_hashes)
+            .and_then(|_| self.write_bloom_filter(table_path, bloom_filter));
+        match written {
+            Ok(_) => 0,
+            Err(e) => {
+                self.reporter.error(&e);
+                reader::FILE_OPERATION_ERROR
+            }
+        }
+    }
+
+    fn new_bloom_filter(&self, entry_count: usize) -> Option<BloomFilter> {
+        self.bloom_false_positive_rate
+            .map(|false_positive_rate| BloomFilter::new(entry_count, false_positive_rate))
+    }
+
+    fn write_bloom_filter(
+        &self,
+        table_path: &str,
+        bloom_filter: Option<BloomFilter>,
+    ) -> Result<(), String> {
+        /*
+            Write the Bloom filter sidecar next to the table. A sidecar left over
+            from an earlier table is removed, since it would hide the new hashes
+        */
+        let sidecar_path = bloom::sidecar_path(table_path);
+        match bloom_filter {
+            Some(bloom_filter) => bloom_filter.write(&sidecar_path),
+            None if path::Path::new(&sidecar_path).exists() => fs::remove_file(&sidecar_path)
+                .map_err(|e| {
+                    format!(
+                        "Unable to remove stale Bloom filter {}: {}",
+                        sidecar_path, e
+                    )
+                }),
+            None => Ok(()),
+        }
+    }
+
+    fn sorted_table_position(
+        &self,
+        manifest: &Option<ShardManifest>,
+        hash: &str,
+    ) -> Result<usize, String> {
+        // Which of the tables being written an entry goes to
+        match (manifest, self.shard_index) {
+            (Some(manifest), None) => manifest.shard_index(hash),
+            _ => Ok(0),
+        }
+    }
+
+    fn settings_fingerprint(&self) -> String {
+        // Everything that decides which entries end up in the sorted runs
+        let hashing = self.table_hashing();
+        let mut settings = vec![hashing.name()];
+        settings.extend(
+            hashing
+                .params()
+                .into_iter()
+                .map(|(key, value)| format!("{}={}", key, value)),
+        );
+        settings.push(format!(
+            "hmac_key={:?}",
+            self.hmac_key
+                .as_ref()
+                .map(|hmac_key| hasher::encode_hex(&hmac_key.key))
+        ));
+        settings.push(format!("shard_count={:?}", self.shard_count));
+        settings.push(format!("shard_index={:?}", self.shard_index));
+        hasher::hash_word(&settings.join("\n"))
+    }
+
+    fn open_checkpoint(&self, checkpoint_dir: &str) -> Result<Option<Checkpoint>, String> {
+        /*
+            The checkpoint to keep up to date while generating, if checkpointing.
+            Resuming picks up the last one written, as long as it was written with
+            the same settings and word file
+        */
+        if !self.resume && self.checkpoint_interval.is_none() {
+            return Ok(None);
+        }
+        let word_file_size = fs::metadata(&self.word_file_path)
+            .map_err(|e| format!("Error opening word file for reading: {}", e))?
+            .len();
+        let settings = self.settings_fingerprint();
+        match (self.resume, Checkpoint::open(checkpoint_dir)?) {
+            (true, None) => Err(format!("No checkpoint to resume from in {}", checkpoint_dir)),
+            (true, Some(checkpoint)) if checkpoint.settings != settings => Err(format!(
+                "Checkpoint in {} was written with different settings, resume with the same ones",
+                checkpoint_dir
+            )),
+            (true, Some(checkpoint)) if checkpoint.word_file_size != word_file_size => {
+                Err(format!(
+                    "Word file {} has changed since the checkpoint in {} was written",
+                    self.word_file_path, checkpoint_dir
+                ))
+            }
+            (true, Some(checkpoint)) => Ok(Some(checkpoint)),
+            (false, Some(_)) => Err(format!(
+                "Checkpoint of an earlier run found in {}, use --resume to continue it or remove it to start over",
+                checkpoint_dir
+            )),
+            (false, None) => Ok(Some(Checkpoint::new(settings, word_file_size))),
+        }
+    }
+
+    fn fill_sorter(
+        &self,
+        sorter: &mut ExternalSorter,
+        manifest: &Option<ShardManifest>,
+        mut checkpoint: Option<&mut Checkpoint>,
+        progress: &mut Progress,
+    ) -> Result<(), String> {
+        /*
+            Hash the word file a chunk at a time into the sorter, which spills
+            sorted runs to disk whenever its memory limit is reached. With a
+            checkpoint, reading starts where it left off, and every checkpoint
+            interval the sorter is flushed and the checkpoint moved up to the
+            end of the last chunk
+        */
+        let push_chunk =
+            |sorter: &mut ExternalSorter, words: Vec<String>| -> Result<usize, String> {
+                let mut pushed = 0;
+                for serialized_hash in self.serialize_hashes(words)? {
+                    let word_hash = match hasher::split_serialized_hash(&serialized_hash) {
+                        Some(word_hash) => word_hash,
+                        None => continue,
+                    };
+                    if let (Some(manifest), Some(shard_index)) = (manifest, self.shard_index) {
+                        if manifest.shard_index(&word_hash.hash)? != shard_index {
+                            continue;
+                        }
+                    }
+                    sorter.push(word_hash)?;
+                    pushed += 1;
+                }
+                Ok(pushed)
+            };
+        let read_error = |e: std::io::Error| format!("Error while reading from file: {}", e);
+        let checkpoint_dir = checkpoint::checkpoint_dir(&self.rainbow_table_file_path);
+        let checkpoint_interval = self
+            .checkpoint_interval
+            .unwrap_or(checkpoint::DEFAULT_CHECKPOINT_INTERVAL);
+
+        let mut word_file = reader::open_word_file(&self.word_file_path)?;
+        let mut input_offset = checkpoint
+            .as_ref()
+            .map_or(0, |checkpoint| checkpoint.input_offset);
+        word_file
+            .seek(SeekFrom::Start(input_offset))
+            .map_err(read_error)?;
+        let mut chunk: Vec<String> = Vec::with_capacity(HASH_CHUNK_WORDS);
+        let mut since_checkpoint = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let line_length = word_file.read_line(&mut line).map_err(read_error)?;
+            input_offset += line_length as u64;
+            if line_length > 0 {
+                let word = line.strip_suffix('\n').unwrap_or(&line);
+                chunk.push(String::from(word.strip_suffix('\r').unwrap_or(word)));
+            }
+            if chunk.len() == HASH_CHUNK_WORDS || line_length == 0 {
+                progress.advance(chunk.len() as u64, input_offset);
+                let pushed = push_chunk(sorter, std::mem::take(&mut chunk))?;
+                if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                    checkpoint.entry_count += pushed;
+                    since_checkpoint += pushed;
+                    if since_checkpoint >= checkpoint_interval || line_length == 0 {
+                        sorter.flush()?;
+                        checkpoint.input_offset = input_offset;
+                        checkpoint.run_count = sorter.run_count();
+                        checkpoint.write(&checkpoint_dir)?;
+                        since_checkpoint = 0;
+                    }
+                }
+            }
+            if line_length == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn write_sorted_tables(
+        &self,
+        sorter: &mut ExternalSorter,
+        manifest: &Option<ShardManifest>,
+        table_paths: &[String],
+    ) -> Result<(), String> {
+        /*
+            Go over the sorted entries twice: once for the header of every table,
+            which has to come first, and once to write them. Shards are picked by the
+            leading bits of the hash, so each shard's entries follow one another
+        */
+        let mut header_builders: Vec<HeaderBuilder> = table_paths
+            .iter()
+            .map(|_| HeaderBuilder::new(self.table_hashing()))
+            .collect();
+        for word_hash in sorter.sorted()? {
+            let word_hash = word_hash?;
+            header_builders[self.sorted_table_position(manifest, &word_hash.hash)?].add(&word_hash);
+        }
+        let headers: Vec<TableHeader> = header_builders
+            .into_iter()
+            .map(HeaderBuilder::finish)
+            .collect();
+        self.reporter.record(Record {
+            count: Some(headers.iter().map(|header| header.count).sum()),
+            path: Some(self.rainbow_table_file_path.clone()),
+            ..Record::new(Event::Generated)
+        });
+
+        self.write_manifest()?;
+        let mut entries = sorter.sorted()?.peekable();
+        for (position, (table_path, header)) in table_paths.iter().zip(&headers).enumerate() {
+            let mut bloom_filter = self.new_bloom_filter(header.count);
+            {
+                let mut table_entries = std::iter::from_fn(|| match entries.peek() {
+                    Some(Ok(word_hash))
+                        if self.sorted_table_position(manifest, &word_hash.hash)
+                            != Ok(position) =>
+                    {
+                        None
+                    }
+                    _ => entries.next(),
+                })
+                .inspect(|entry| {
+                    if let (Some(bloom_filter), Ok(word_hash)) = (&mut bloom_filter, entry) {
+                        bloom_filter.insert(&word_hash.hash);
+                    }
+                });
+                storage::create_table(table_path, self.storage_format)
+                    .write_stream(header, &mut table_entries)?;
             }
+            self.write_bloom_filter(table_path, bloom_filter)?;
         }
+        Ok(())
+    }
 
-        // Create a new file, and write to it
-        let mut file = match fs::File::create(&self.rainbow_table_file_path) {
-            Ok(f) => f,
+    fn generate_sorted<R: BufRead>(&self, mut reader: R) -> i32 {
+        /*
+            Generate the table in ascending hash order, so that it can be binary
+            searched. Tables larger than the memory limit are sorted in runs on disk
+            and merged, and the runs are removed whether or not this succeeds.
+            When checkpointing, the runs are kept after a failure instead, for
+            `resume` to continue from
+        */
+        // Settings that cannot be combined are rejected before any file is touched
+        if let Err(e) = self.serialize_hashes(Vec::new()) {
+            self.reporter.error(&e);
+            return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+        }
+        let manifest = match self.shard_count.map(ShardManifest::new).transpose() {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        let table_paths: Vec<String> = match (&manifest, self.shard_index) {
+            (None, _) => vec![self.rainbow_table_file_path.clone()],
+            (Some(manifest), Some(shard_index)) if shard_index >= manifest.shard_count => {
+                self.reporter.error(&format!(
+                    "Shard {} does not exist in a table of {} shards",
+                    shard_index, manifest.shard_count
+                ));
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+            (Some(manifest), Some(shard_index)) => {
+                vec![manifest.shard_path(&self.rainbow_table_file_path, shard_index)]
+            }
+            (Some(manifest), None) => (0..manifest.shard_count)
+                .map(|shard_index| manifest.shard_path(&self.rainbow_table_file_path, shard_index))
+                .collect(),
+        };
+        let checkpoint_dir = checkpoint::checkpoint_dir(&self.rainbow_table_file_path);
+        let mut checkpoint = match self.open_checkpoint(&checkpoint_dir) {
+            Ok(checkpoint) => checkpoint,
             Err(e) => {
-                eprintln!("Unable to open file for writing: {}", e);
+                self.reporter.error(&e);
                 return reader::FILE_OPERATION_ERROR;
             }
         };
+        match self.confirm_overwrite_tables(&mut reader, &table_paths) {
+            Ok(true) => (),
+            Ok(false) => return 0,
+            Err(e) => {
+                self.reporter.error(&e);
+                return INPUT_READ_ERROR;
+            }
+        }
 
-        let mut content = String::new();
-        for hash in serialized_hashes {
-            content.push_str(&format!("{}\n", &hash));
+        let sorter = match &checkpoint {
+            Some(checkpoint) if self.resume => {
+                self.reporter.info(&format!(
+                    "Resuming from checkpoint: {} entries from the first {} bytes of {}",
+                    checkpoint.entry_count, checkpoint.input_offset, self.word_file_path
+                ));
+                ExternalSorter::checkpointed(
+                    &checkpoint_dir,
+                    self.memory_limit,
+                    Some(checkpoint.run_count),
+                )
+            }
+            Some(_) => ExternalSorter::checkpointed(&checkpoint_dir, self.memory_limit, None),
+            None => ExternalSorter::new(&self.rainbow_table_file_path, self.memory_limit),
+        };
+        self.reporter.info("Generating words...");
+        // Bytes of the word file read is what is known up front, not how many words it holds
+        let word_file_size = fs::metadata(&self.word_file_path)
+            .ok()
+            .map(|metadata| metadata.len());
+        let mut progress = self
+            .reporter
+            .progress("Generating", "words", word_file_size);
+        if let Some(checkpoint) = checkpoint.as_ref().filter(|_| self.resume) {
+            progress.resume_from(checkpoint.input_offset);
         }
-        match file.write_all(content.as_bytes()) {
+        let sorted = sorter.and_then(|mut sorter| {
+            self.fill_sorter(&mut sorter, &manifest, checkpoint.as_mut(), &mut progress)?;
+            progress.finish();
+            if sorter.run_count() > 0 {
+                self.reporter.info(&format!(
+                    "Merging {} sorted runs into {}",
+                    sorter.run_count(),
+                    &self.rainbow_table_file_path
+                ));
+            } else {
+                self.reporter.info(&format!(
+                    "Writing generated words to {}",
+                    &self.rainbow_table_file_path
+                ));
+            }
+            self.write_sorted_tables(&mut sorter, &manifest, &table_paths)?;
+            // The checkpoint is of no more use once the tables are written
+            sorter.set_keep_on_drop(false);
+            Ok(())
+        });
+        match sorted {
+            Ok(_) => {
+                self.reporter.info("Write complete!");
+                0
+            }
             Err(e) => {
-                eprintln!("Error while writing hashes to file: {}", e);
+                self.reporter.error(&e);
+                if checkpoint.is_some() && path::Path::new(&checkpoint_dir).exists() {
+                    self.reporter.info(&format!(
+                        "Progress is kept in {}, run again with --resume to continue",
+                        checkpoint_dir
+                    ));
+                }
                 reader::FILE_OPERATION_ERROR
             }
-            Ok(_) => 0,
         }
     }
 }
 
 impl Operator for RainbowTableGenerator {
     fn run(&self) -> i32 {
+        // Checkpoints are sorted runs on disk, so checkpointed runs take the sorted path
+        if self.sort || self.checkpoint_interval.is_some() || self.resume {
+            let stdin = stdin();
+            return self.generate_sorted(stdin.lock());
+        }
         let words = match reader::read_words(&self.word_file_path) {
             Ok(result) => result,
             Err(e) => {
-                eprintln!("{}", e);
+                self.reporter.error(&e);
                 return reader::FILE_OPERATION_ERROR;
             }
         };
 
-        println!("Generating words...");
-        let serialized_hashes = hasher::serialize_hashes(words);
-        println!("Generated {} words", serialized_hashes.len());
-        println!(
+        self.reporter.info("Generating words...");
+        let serialized_hashes = match self.hash_words(words) {
+            Ok(serialized_hashes) => serialized_hashes,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        self.reporter.record(Record {
+            count: Some(serialized_hashes.len()),
+            path: Some(self.rainbow_table_file_path.clone()),
+            ..Record::new(Event::Generated)
+        });
+        self.reporter.info(&format!(
             "Writing generated words to {}",
             &self.rainbow_table_file_path
-        );
+        ));
         let stdin = stdin();
-        self.write_hashes_to_file(stdin.lock(), serialized_hashes);
-        println!("Write complete!");
-        0
+        let exit_code = self.write_hashes_to_file(stdin.lock(), serialized_hashes);
+        if exit_code == 0 {
+            self.reporter.info("Write complete!");
+        }
+        exit_code
     }
 }
 
 pub struct HashCracker {
     rainbow_table_file_path: String,
     hash: String,
+    // Cracked hashes are looked up here first, and newly cracked ones appended
+    pub potfile_path: Option<String>,
+    pub reporter: Rc<Reporter>,
 }
 
 impl HashCracker {
     pub fn new(rainbow_table_file_path: String, hash: String) -> HashCracker {
         HashCracker {
             rainbow_table_file_path,
-            hash,
+            // Once here, so the potfile, Bloom filter and every storage format see the same hash
+            hash: identify::normalize_hash(&hash),
+            potfile_path: None,
+            reporter: Rc::new(Reporter::default()),
         }
     }
 
-    fn crack_hash(&self, rainbow_table: Vec<hasher::WordHash>) -> Result<String, ()> {
-        for wordhash in rainbow_table {
-            if &wordhash.hash == &self.hash {
-                return Ok(wordhash.word);
-            }
-        }
-        Err(())
+    fn crack_hash(&self, rainbow_table: &dyn TableStorage) -> Result<Result<String, ()>, String> {
+        Ok(rainbow_table.lookup(&self.hash)?.ok_or(()))
     }
 }
 
 impl Operator for HashCracker {
     fn run(&self) -> i32 {
-        // Read words from file
-        let read_words = match reader::read_words(&self.rainbow_table_file_path) {
-            Ok(result) => result,
+        let mut potfile = match open_potfile(&self.reporter, &self.potfile_path) {
+            Ok(potfile) => potfile,
             Err(e) => {
-                eprintln!("{}", e);
+                self.reporter.error(&e);
                 return reader::FILE_OPERATION_ERROR;
             }
         };
-        let rainbow_table = match hasher::deserialize_hashes(read_words) {
-            Ok(hashes) => hashes,
+        if let Some(word) = potfile
+            .as_ref()
+            .and_then(|potfile| potfile.lookup(&self.hash))
+        {
+            self.reporter
+                .single_crack_result(&self.hash, &Ok(String::from(word)));
+            return 0;
+        }
+
+        // Sharded tables only need the one shard the hash would be in
+        let table_path = match shard::resolve_table_path(&self.rainbow_table_file_path, &self.hash)
+        {
+            Ok(table_path) => table_path,
             Err(e) => {
-                eprintln!("{}", e);
-                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+
+        // A negative from the Bloom filter is certain, so the table does not need to be read
+        match BloomFilter::open(&bloom::sidecar_path(&table_path)) {
+            Ok(Some(bloom_filter)) if !bloom_filter.contains(&self.hash) => {
+                self.reporter.single_crack_result(&self.hash, &Err(()));
+                return 0;
+            }
+            Ok(_) => (),
+            Err(e) => self.reporter.warning(&e),
+        }
+
+        // Text and SQLite tables are told apart by their contents
+        let rainbow_table = match storage::open_table(&table_path) {
+            Ok(rainbow_table) => rainbow_table,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
             }
         };
-        match &self.crack_hash(rainbow_table) {
-            Ok(cracked_word) => println!("Hash Cracked! The word is: {}", cracked_word),
-            Err(_) => println!("Sorry, hash not found in the rainbow table!"),
+        // Tables have a single algorithm, so any entry tells us what the rest look like
+        match rainbow_table.sample_hash() {
+            Ok(Some(sample_hash)) => {
+                if let Some(mismatch) = identify::hash_length_mismatch(&self.hash, &sample_hash) {
+                    self.reporter.warning(&mismatch);
+                }
+            }
+            Ok(None) => (),
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        }
+        let crack_result = match self.crack_hash(rainbow_table.as_ref()) {
+            Ok(crack_result) => crack_result,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
         };
+        if let Ok(word) = &crack_result {
+            record_cracked(&self.reporter, &mut potfile, &self.hash, word);
+        }
+        self.reporter.single_crack_result(&self.hash, &crack_result);
         0
     }
 }
 
-#[cfg(test)]
-mod rainbow_table_generator_tests {
-    use super::*;
-    use crate::hasher::{serialize_hashes, HASH_DELIMITER};
-    use crate::test_utils;
-    use std::io::{BufReader, BufWriter, Read};
-
-    #[test]
-    fn test_rainbow_table_write_hashes_to_file() {
-        // Create a temp file for the test to write to
-        let temp_file_handler = test_utils::TempFileHandler::new();
+pub struct HashIdentifier {
+    hashes: Vec<String>,
+    pub reporter: Rc<Reporter>,
+}
 
-        // Create serialized hashes vec for testing
-        let words = vec![
-            "potato".to_string(),
-            "rice".to_string(),
-            "noodles".to_string(),
-            "salad".to_string(),
-        ];
-        let serialized_hashes = serialize_hashes(words);
-        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
-        let operator = RainbowTableGenerator::new("".to_string(), temp_file_path);
-        let input = b"y\n";
-        // https://stackoverflow.com/questions/28370126/how-can-i-test-stdin-and-stdout
-        operator.write_hashes_to_file(&input[..], serialized_hashes);
+impl HashIdentifier {
+    pub fn new(hashes: Vec<String>) -> HashIdentifier {
+        HashIdentifier {
+            hashes,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
 
-        // Verify that the expected things were written to the file
-        let wordfile = temp_file_handler.get_file_object(test_utils::FileMode::Read);
-        let reader = BufReader::new(wordfile);
-        let expected_lines = vec![
-            format!(
-                "potato{}e91c254ad58860a02c788dfb5c1a65d6a8846ab1dc649631c7db16fef4af2dec",
-                HASH_DELIMITER
-            ),
-            format!(
-                "rice{}209f76418ece7c936b65ff4777a578d860f762c37ad6c7f08f5826242199ef51",
-                HASH_DELIMITER
-            ),
-            format!(
-                "noodles{}838f8d9acc45bd36e3213c47c3222e644f44c959fa370bbfa6df46b171c02f0c",
-                HASH_DELIMITER
-            ),
-            format!(
-                "salad{}c6c3fa689e291bba6f7436ee76dc542ec4678a410a2adbb26bbedfd1e6a8aa85",
-                HASH_DELIMITER
-            ),
-        ];
-        for line in reader.lines() {
-            match line {
-                Ok(line) => assert!(
-                    expected_lines.contains(&line),
-                    "Expected line not found in written wordfile: {}",
-                    line
-                ),
-                Err(e) => panic!("{}", e),
-            };
+impl Operator for HashIdentifier {
+    fn run(&self) -> i32 {
+        for hash in &self.hashes {
+            let candidates = identify::identify_hash(hash);
+            if candidates.is_empty() {
+                self.reporter.record(Record {
+                    hash: Some(hash.clone()),
+                    ..Record::new(Event::Unidentified)
+                });
+                continue;
+            }
+            self.reporter.info(&format!(
+                "Possible algorithms for {}, most likely first:",
+                hash
+            ));
+            for candidate in candidates {
+                self.reporter.record(Record {
+                    hash: Some(hash.clone()),
+                    algorithm: Some(candidate.algorithm),
+                    detail: Some(candidate.likelihood.to_string()),
+                    ..Record::new(Event::Identified)
+                });
+            }
         }
+        0
     }
+}
 
-    #[test]
-    fn test_write_hashes_to_file_not_overwrite() {
-        // Create a temp file with sample text in it to check if it got overwritten
-        let sample_text = "The quick brown fox jumps over the lazy dog.";
-        let temp_file_handler = test_utils::TempFileHandler::new();
-        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
-        // Write sample text into file
-        let mut writer = BufWriter::new(&file);
-        match writer.write_all(sample_text.as_bytes()) {
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
-        };
-        // Drop file pointer and writer to "close" the file, prevent any conflicts later on
-        std::mem::drop(writer);
-        std::mem::drop(file);
+pub struct LmCracker {
+    rainbow_table_file_path: String,
+    lm_hash: String,
+    // NTLM hash of the same account, used to recover the case LM throws away
+    pub ntlm_hash: Option<String>,
+    pub potfile_path: Option<String>,
+    pub reporter: Rc<Reporter>,
+}
 
-        let words = vec!["potato".to_string()];
-        let serialized_hashes = serialize_hashes(words);
-        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
-        let operator = RainbowTableGenerator::new("".to_string(), temp_file_path);
-        let input = b"n\n";
-        operator.write_hashes_to_file(&input[..], serialized_hashes);
-        // File should not be overwritten
-        let file = temp_file_handler.get_file_object(test_utils::FileMode::Read);
-        let mut reader = BufReader::new(&file);
-        let mut read_text = String::new();
-        // Read all text to a string buffer, to make sure that nothing else was appended to the file
-        match reader.read_to_string(&mut read_text) {
-            // If Ok, read until EOF was successful
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
-        };
-        assert_eq!(sample_text, read_text);
-        println!("Test complete!")
+impl LmCracker {
+    pub fn new(rainbow_table_file_path: String, lm_hash: String) -> LmCracker {
+        LmCracker {
+            rainbow_table_file_path,
+            lm_hash,
+            ntlm_hash: None,
+            potfile_path: None,
+            reporter: Rc::new(Reporter::default()),
+        }
     }
-}
 
-#[cfg(test)]
+    fn crack_half(
+        &self,
+        half_hash: &str,
+        rainbow_table: &dyn TableStorage,
+    ) -> Result<Option<String>, String> {
+        // Empty halves are never in the table, since every password has them
+        if half_hash == lm::EMPTY_LM_HALF_HASH {
+            return Ok(Some(String::new()));
+        }
+        rainbow_table.lookup(half_hash)
+    }
+
+    fn crack_lm_hash(
+        &self,
+        rainbow_table: &dyn TableStorage,
+    ) -> Result<Result<String, String>, String> {
+        /*
+            Crack both halves independently, then join them into the uppercased
+            password. With an NTLM hash, the original case is recovered from it.
+            The inner error says why the hash was not cracked
+        */
+        let lm_hash = lm::parse_lm_hash(&self.lm_hash)?;
+        let first_half = self.crack_half(&lm_hash.first_half, rainbow_table)?;
+        let second_half = self.crack_half(&lm_hash.second_half, rainbow_table)?;
+        let uppercase_word = match (first_half, second_half) {
+            (Some(first_half), Some(second_half)) => format!("{}{}", first_half, second_half),
+            (Some(first_half), None) => {
+                return Ok(Err(format!(
+                    "Only the first half was cracked: {}",
+                    first_half
+                )))
+            }
+            (None, Some(second_half)) => {
+                return Ok(Err(format!(
+                    "Only the second half was cracked: {}",
+                    second_half
+                )))
+            }
+            (None, None) => return Ok(Err(String::from("Neither half was cracked"))),
+        };
+        Ok(match &self.ntlm_hash {
+            Some(ntlm_hash) => lm::recover_case(&uppercase_word, ntlm_hash).ok_or_else(|| {
+                format!(
+                    "The uppercased word is {}, but no case variant of it matches NTLM hash {}",
+                    uppercase_word, ntlm_hash
+                )
+            }),
+            None => Ok(uppercase_word),
+        })
+    }
+}
+
+impl Operator for LmCracker {
+    fn run(&self) -> i32 {
+        if let Err(e) = lm::parse_lm_hash(&self.lm_hash) {
+            self.reporter.error(&e);
+            return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+        }
+        let mut potfile = match open_potfile(&self.reporter, &self.potfile_path) {
+            Ok(potfile) => potfile,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        // Only an NTLM entry is sure to have the right case
+        let potfile_hash = self.ntlm_hash.as_ref().unwrap_or(&self.lm_hash);
+        if let Some(word) = potfile
+            .as_ref()
+            .and_then(|potfile| potfile.lookup(potfile_hash))
+        {
+            self.reporter
+                .single_crack_result(&self.lm_hash, &Ok(String::from(word)));
+            return 0;
+        }
+        let rainbow_table = match storage::open_table(&self.rainbow_table_file_path) {
+            Ok(rainbow_table) => rainbow_table,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        let crack_result = match self.crack_lm_hash(rainbow_table.as_ref()) {
+            Ok(crack_result) => crack_result,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        match crack_result {
+            Ok(word) => {
+                record_cracked(&self.reporter, &mut potfile, &self.lm_hash, &word);
+                if let Some(ntlm_hash) = &self.ntlm_hash {
+                    record_cracked(&self.reporter, &mut potfile, ntlm_hash, &word);
+                }
+                self.reporter.single_crack_result(&self.lm_hash, &Ok(word));
+                if self.ntlm_hash.is_none() {
+                    self.reporter
+                        .info("LM is case insensitive, pass the NTLM hash to recover the case");
+                }
+            }
+            Err(e) => {
+                self.reporter.record(Record {
+                    hash: Some(self.lm_hash.clone()),
+                    detail: Some(e),
+                    single_hash: true,
+                    ..Record::new(Event::NotCracked)
+                });
+            }
+        }
+        0
+    }
+}
+
+pub struct SaltedHashCracker {
+    word_file_path: String,
+    // Hashes in the `hash:salt` format
+    salted_hashes: Vec<String>,
+    salt_position: hasher::SaltPosition,
+    pub potfile_path: Option<String>,
+    pub reporter: Rc<Reporter>,
+}
+
+impl SaltedHashCracker {
+    pub fn new(
+        word_file_path: String,
+        salted_hashes: Vec<String>,
+        salt_position: hasher::SaltPosition,
+    ) -> SaltedHashCracker {
+        SaltedHashCracker {
+            word_file_path,
+            salted_hashes,
+            salt_position,
+            potfile_path: None,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn crack_salted_hash(
+        &self,
+        words: &[String],
+        salted_hash: &hasher::SaltedHash,
+    ) -> Result<String, ()> {
+        /*
+            Precomputed tables are useless against per-entry salts, so run a
+            dictionary attack against each salt individually
+        */
+        let salt = hasher::Salt {
+            value: salted_hash.salt.clone(),
+            position: self.salt_position,
+        };
+        for word in words {
+            if hasher::hash_salted_word(word, &salt) == salted_hash.hash {
+                return Ok(word.clone());
+            }
+        }
+        Err(())
+    }
+}
+
+impl Operator for SaltedHashCracker {
+    fn run(&self) -> i32 {
+        let mut salted_hashes: Vec<hasher::SaltedHash> = Vec::new();
+        for salted_hash in &self.salted_hashes {
+            match hasher::parse_salted_hash(salted_hash) {
+                Ok(salted_hash) => salted_hashes.push(salted_hash),
+                Err(e) => {
+                    self.reporter.error(&e);
+                    return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+                }
+            };
+        }
+
+        let words = match reader::read_words(&self.word_file_path) {
+            Ok(result) => result,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+
+        let mut potfile = match open_potfile(&self.reporter, &self.potfile_path) {
+            Ok(potfile) => potfile,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        for salted_hash in salted_hashes {
+            let potfile_word = potfile
+                .as_ref()
+                .and_then(|potfile| potfile.lookup(&salted_hash.hash))
+                .map(String::from);
+            let crack_result = match potfile_word {
+                Some(word) => Ok(word),
+                None => self.crack_salted_hash(&words, &salted_hash),
+            };
+            if let Ok(word) = &crack_result {
+                record_cracked(&self.reporter, &mut potfile, &salted_hash.hash, word);
+            }
+            self.reporter
+                .crack_result(&salted_hash.hash, None, &crack_result);
+        }
+        0
+    }
+}
+
+pub struct Attacker {
+    candidate_source: CandidateSource,
+    // Hashes to crack, either bare hashes or in the `hash:salt` format
+    hashes: Vec<String>,
+    hash_file_path: Option<String>,
+    salt_position: hasher::SaltPosition,
+    // Database user table dump, one `account:hash` line per account
+    pub database_dump_file_path: Option<String>,
+    // List of accounts and their hashes, in `account_format`
+    pub account_file_path: Option<String>,
+    pub account_format: AccountFormat,
+    // Maximum time spent verifying candidates against any single slow hash
+    pub time_budget: Option<Duration>,
+    // Bare targets are HMAC(key, word) instead of a plain digest
+    pub hmac_key: Option<hasher::HmacKey>,
+    // Composed hash expression, with the salt of `hash:salt` targets filling in `salt`
+    pub pipeline: Option<Pipeline>,
+    pub potfile_path: Option<String>,
+    pub reporter: Rc<Reporter>,
+}
+
+// Hashes that carry their own salt and cost parameters, so each one is verified on its own
+enum VerifiableHash {
+    Crypt(crypt::CryptHash),
+    Slow(slow::SlowHash),
+    Database(database::DatabaseHash),
+    Htpasswd(htpasswd::HtpasswdHash),
+}
+
+impl VerifiableHash {
+    fn parse(hash: &str) -> Result<VerifiableHash, String> {
+        if htpasswd::is_htpasswd_hash(hash) {
+            return Ok(VerifiableHash::Htpasswd(htpasswd::parse_htpasswd_hash(
+                hash,
+            )?));
+        }
+        if let Ok(crypt_hash) = crypt::parse_crypt_hash(hash) {
+            return Ok(VerifiableHash::Crypt(crypt_hash));
+        }
+        if database::is_database_hash(hash) {
+            return Ok(VerifiableHash::Database(database::parse_database_target(
+                hash,
+            )?));
+        }
+        Ok(VerifiableHash::Slow(slow::parse_slow_hash(hash)?))
+    }
+
+    fn verify(&self, word: &str) -> bool {
+        match self {
+            VerifiableHash::Crypt(crypt_hash) => crypt_hash.verify(word),
+            VerifiableHash::Slow(slow_hash) => slow_hash.verify(word),
+            VerifiableHash::Database(database_hash) => database_hash.verify(word),
+            VerifiableHash::Htpasswd(htpasswd_hash) => htpasswd_hash.verify(word),
+        }
+    }
+
+    fn raw(&self) -> &str {
+        match self {
+            VerifiableHash::Crypt(crypt_hash) => &crypt_hash.raw,
+            VerifiableHash::Slow(slow_hash) => &slow_hash.raw,
+            VerifiableHash::Database(database_hash) => &database_hash.raw,
+            VerifiableHash::Htpasswd(htpasswd_hash) => &htpasswd_hash.raw,
+        }
+    }
+
+    fn describe_cost(&self) -> String {
+        match self {
+            VerifiableHash::Crypt(crypt_hash) => format!(
+                "{} rounds={}",
+                crypt_hash.scheme,
+                crypt_hash.effective_rounds()
+            ),
+            VerifiableHash::Slow(slow_hash) => format!("{} {}", slow_hash.scheme, slow_hash.cost),
+            VerifiableHash::Database(database_hash) => database_hash.scheme.to_string(),
+            VerifiableHash::Htpasswd(htpasswd_hash) => htpasswd_hash.scheme.to_string(),
+        }
+    }
+}
+
+struct VerifyTarget {
+    hash: VerifiableHash,
+    // Time spent verifying candidates against this hash so far
+    elapsed: Duration,
+}
+
+#[derive(Default)]
+struct AttackTargets {
+    unsalted: HashSet<String>,
+    // Salt -> hashes using that salt, so each candidate is hashed once per distinct salt
+    salted: HashMap<String, HashSet<String>>,
+    // NTLM hashes from pwdump files, which are unsalted but never in the table's algorithm
+    ntlm: HashSet<String>,
+    verifiable: Vec<VerifyTarget>,
+    // Verifiable hashes that were given up on after exceeding the time budget
+    exhausted: Vec<String>,
+    // Hash -> accounts it belongs to, for targets read from account lists
+    accounts: HashMap<String, Vec<String>>,
+}
+
+impl AttackTargets {
+    fn insert_verifiable(&mut self, hash: VerifiableHash) -> String {
+        // Returns the hash the target will be reported as. Duplicates are only verified once
+        let reported_hash = String::from(hash.raw());
+        if !self
+            .verifiable
+            .iter()
+            .any(|target| target.hash.raw() == reported_hash)
+        {
+            self.verifiable.push(VerifyTarget {
+                hash,
+                elapsed: Duration::ZERO,
+            });
+        }
+        reported_hash
+    }
+
+    fn insert(&mut self, target: &str) -> Result<String, String> {
+        // Returns the hash the target will be reported as once cracked
+        if target.starts_with('$')
+            || database::is_database_hash(target)
+            || htpasswd::is_htpasswd_hash(target)
+        {
+            return Ok(self.insert_verifiable(VerifiableHash::parse(target)?));
+        }
+        // Candidates are hashed to lowercase hex, so hex targets are lowercased to match
+        if !target.contains(hasher::HASH_DELIMITER) {
+            let hash = identify::normalize_hash(target);
+            self.unsalted.insert(hash.clone());
+            return Ok(hash);
+        }
+        let salted_hash = hasher::parse_salted_hash(target)?;
+        let hash = identify::normalize_hash(&salted_hash.hash);
+        self.salted
+            .entry(salted_hash.salt)
+            .or_default()
+            .insert(hash.clone());
+        Ok(hash)
+    }
+
+    fn insert_account(
+        &mut self,
+        account_hash: accounts::AccountHash,
+        format: AccountFormat,
+    ) -> Result<(), String> {
+        let with_account = |e: String| format!("Account {}: {}", account_hash.account, e);
+        let reported_hash = match format {
+            AccountFormat::UserHash => self.insert(&account_hash.hash).map_err(with_account)?,
+            AccountFormat::Pwdump => {
+                self.ntlm.insert(account_hash.hash.clone());
+                account_hash.hash.clone()
+            }
+            AccountFormat::Shadow | AccountFormat::Htpasswd => {
+                // DES crypt has no prefix, so htpasswd hashes are tried before anything else
+                let hash = match htpasswd::parse_htpasswd_hash(&account_hash.hash) {
+                    Ok(htpasswd_hash) if format == AccountFormat::Htpasswd => {
+                        VerifiableHash::Htpasswd(htpasswd_hash)
+                    }
+                    _ => VerifiableHash::parse(&account_hash.hash).map_err(with_account)?,
+                };
+                self.insert_verifiable(hash)
+            }
+        };
+        self.accounts
+            .entry(reported_hash)
+            .or_default()
+            .push(account_hash.account);
+        Ok(())
+    }
+
+    fn insert_database_entry(&mut self, database_entry: database::DatabaseEntry) {
+        let reported_hash = self.insert_verifiable(VerifiableHash::Database(database_entry.hash));
+        self.accounts
+            .entry(reported_hash)
+            .or_default()
+            .push(database_entry.account);
+    }
+
+    fn take_cracked(&mut self, potfile: &Potfile) -> Vec<hasher::WordHash> {
+        // Remove targets that an earlier run already cracked, returning them with their words
+        let mut cracked: Vec<hasher::WordHash> = Vec::new();
+        let mut take = |hash: &str| match potfile.lookup(hash) {
+            Some(word) => {
+                cracked.push(hasher::WordHash {
+                    word: String::from(word),
+                    hash: String::from(hash),
+                });
+                false
+            }
+            None => true,
+        };
+        self.unsalted.retain(|hash| take(hash));
+        self.ntlm.retain(|hash| take(hash));
+        self.salted.retain(|_, hashes| {
+            hashes.retain(|hash| take(hash));
+            !hashes.is_empty()
+        });
+        self.verifiable.retain(|target| take(target.hash.raw()));
+        cracked
+    }
+
+    fn is_empty(&self) -> bool {
+        self.unsalted.is_empty()
+            && self.salted.is_empty()
+            && self.ntlm.is_empty()
+            && self.verifiable.is_empty()
+    }
+
+    fn remaining(&self) -> Vec<String> {
+        let mut remaining: Vec<String> = self.unsalted.iter().cloned().collect();
+        remaining.extend(self.ntlm.iter().cloned());
+        for hashes in self.salted.values() {
+            remaining.extend(hashes.iter().cloned());
+        }
+        remaining.extend(
+            self.verifiable
+                .iter()
+                .map(|target| String::from(target.hash.raw())),
+        );
+        remaining.sort();
+        remaining
+    }
+
+    fn accounts_of(&self, hash: &str) -> Vec<Option<&str>> {
+        // Hashes given without an account are reported once, on their own
+        match self.accounts.get(hash) {
+            Some(accounts) => accounts
+                .iter()
+                .map(|account| Some(account.as_str()))
+                .collect(),
+            None => vec![None],
+        }
+    }
+
+    fn report(&self, reporter: &Reporter, hash: &str, crack_result: &Result<String, ()>) {
+        for account in self.accounts_of(hash) {
+            reporter.crack_result(hash, account, crack_result);
+        }
+    }
+
+    fn report_exhausted(&self, reporter: &Reporter, hash: &str, time_budget: &Duration) {
+        for account in self.accounts_of(hash) {
+            reporter.record(Record {
+                hash: Some(String::from(hash)),
+                account: account.map(String::from),
+                detail: Some(format!("{:?}", time_budget)),
+                ..Record::new(Event::Exhausted)
+            });
+        }
+    }
+
+    fn shared_passwords(&self, cracked: &[hasher::WordHash]) -> BTreeMap<String, Vec<String>> {
+        // Password -> accounts using it, for passwords used by more than one account
+        let mut shared: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for word_hash in cracked {
+            if let Some(accounts) = self.accounts.get(&word_hash.hash) {
+                shared
+                    .entry(word_hash.word.clone())
+                    .or_default()
+                    .extend(accounts.iter().cloned());
+            }
+        }
+        shared.retain(|_, accounts| {
+            accounts.sort();
+            accounts.dedup();
+            accounts.len() > 1
+        });
+        shared
+    }
+}
+
+impl Attacker {
+    pub fn new(
+        candidate_source: CandidateSource,
+        hashes: Vec<String>,
+        hash_file_path: Option<String>,
+        salt_position: hasher::SaltPosition,
+    ) -> Attacker {
+        Attacker {
+            candidate_source,
+            hashes,
+            hash_file_path,
+            salt_position,
+            database_dump_file_path: None,
+            account_file_path: None,
+            account_format: AccountFormat::UserHash,
+            time_budget: None,
+            hmac_key: None,
+            pipeline: None,
+            potfile_path: None,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn read_targets(&self) -> Result<AttackTargets, String> {
+        let mut targets = AttackTargets::default();
+        let mut hashes = self.hashes.clone();
+        if let Some(hash_file_path) = &self.hash_file_path {
+            hashes.extend(reader::read_words(hash_file_path)?);
+        }
+        for hash in hashes.iter().filter(|hash| !hash.is_empty()) {
+            targets.insert(hash)?;
+        }
+        if let Some(account_file_path) = &self.account_file_path {
+            for line in reader::read_words(account_file_path)? {
+                if let Some(account_hash) =
+                    accounts::parse_account_line(&line, self.account_format)?
+                {
+                    // Unsupported schemes should not stop the rest of the accounts from being audited
+                    if let Err(e) = targets.insert_account(account_hash, self.account_format) {
+                        self.reporter.warning(&format!("Skipping {}", e));
+                    }
+                }
+            }
+        }
+        if let Some(database_dump_file_path) = &self.database_dump_file_path {
+            for line in reader::read_words(database_dump_file_path)? {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match database::parse_dump_line(&line) {
+                    Ok(database_entry) => targets.insert_database_entry(database_entry),
+                    Err(e) => self.reporter.warning(&format!("Skipping {}", e)),
+                }
+            }
+        }
+        // Only once every target is in, account lists included
+        match (&self.pipeline, &self.hmac_key) {
+            (Some(_), Some(_)) => {
+                return Err(String::from(
+                    "A hash expression cannot be combined with an HMAC key",
+                ))
+            }
+            (Some(pipeline), None) if pipeline.uses_salt() && !targets.unsalted.is_empty() => {
+                return Err(format!(
+                    "Hash expression {} requires every hash to be in the hash{}salt format",
+                    pipeline,
+                    hasher::HASH_DELIMITER
+                ))
+            }
+            (Some(pipeline), None) if !pipeline.uses_salt() && !targets.salted.is_empty() => {
+                return Err(format!(
+                    "Hash expression {} does not use the salt of salted hashes",
+                    pipeline
+                ))
+            }
+            (None, Some(_)) if !targets.salted.is_empty() => {
+                return Err(String::from(
+                    "Salted hashes cannot be combined with an HMAC key",
+                ))
+            }
+            _ => (),
+        };
+        Ok(targets)
+    }
+
+    fn hash_candidate(&self, candidate: &str, salt: Option<&str>) -> String {
+        match (&self.pipeline, &self.hmac_key, salt) {
+            (Some(pipeline), _, salt) => pipeline.hash(candidate, salt),
+            (None, Some(hmac_key), _) => hmac_key.hash(candidate),
+            (None, None, Some(salt)) => hasher::hash_salted_word(
+                candidate,
+                &hasher::Salt {
+                    value: String::from(salt),
+                    position: self.salt_position,
+                },
+            ),
+            (None, None, None) => hasher::hash_word(candidate),
+        }
+    }
+
+    fn attack<I: Iterator<Item = String>>(
+        &self,
+        candidates: I,
+        targets: &mut AttackTargets,
+    ) -> Vec<hasher::WordHash> {
+        /*
+            Hash every candidate and compare against the remaining targets. Cracked
+            targets are removed, and the attack stops as soon as none are left
+        */
+        let total = match candidates.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(lower as u64),
+            _ => None,
+        };
+        let mut progress = self.reporter.progress("Attacking", "candidates", total);
+        let mut cracked: Vec<hasher::WordHash> = Vec::new();
+        for candidate in candidates {
+            progress.add(1);
+            if !targets.unsalted.is_empty() {
+                let hash = self.hash_candidate(&candidate, None);
+                if targets.unsalted.remove(&hash) {
+                    cracked.push(hasher::WordHash {
+                        word: candidate.clone(),
+                        hash,
+                    });
+                }
+            }
+            if !targets.ntlm.is_empty() {
+                let hash = lm::ntlm_hash(&candidate);
+                if targets.ntlm.remove(&hash) {
+                    cracked.push(hasher::WordHash {
+                        word: candidate.clone(),
+                        hash,
+                    });
+                }
+            }
+            targets.salted.retain(|salt, hashes| {
+                let hash = self.hash_candidate(&candidate, Some(salt));
+                if hashes.remove(&hash) {
+                    cracked.push(hasher::WordHash {
+                        word: candidate.clone(),
+                        hash,
+                    });
+                }
+                !hashes.is_empty()
+            });
+            let exhausted = &mut targets.exhausted;
+            targets.verifiable.retain_mut(|target| {
+                let start = Instant::now();
+                let verified = target.hash.verify(&candidate);
+                target.elapsed += start.elapsed();
+                if verified {
+                    cracked.push(hasher::WordHash {
+                        word: candidate.clone(),
+                        hash: String::from(target.hash.raw()),
+                    });
+                    return false;
+                }
+                match self.time_budget {
+                    Some(time_budget) if target.elapsed >= time_budget => {
+                        exhausted.push(String::from(target.hash.raw()));
+                        false
+                    }
+                    _ => true,
+                }
+            });
+            if targets.is_empty() {
+                break;
+            }
+        }
+        progress.finish();
+        cracked
+    }
+}
+
+impl Operator for Attacker {
+    fn run(&self) -> i32 {
+        let mut targets = match self.read_targets() {
+            Ok(targets) => targets,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        let mut potfile = match open_potfile(&self.reporter, &self.potfile_path) {
+            Ok(potfile) => potfile,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        let mut cracked: Vec<hasher::WordHash> = Vec::new();
+        if let Some(potfile) = &potfile {
+            for word_hash in targets.take_cracked(potfile) {
+                targets.report(&self.reporter, &word_hash.hash, &Ok(word_hash.word.clone()));
+                cracked.push(word_hash);
+            }
+        }
+        let candidates = match self.candidate_source.candidates() {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+
+        for target in &targets.verifiable {
+            self.reporter.info(&format!(
+                "Verifying {} as {}",
+                target.hash.raw(),
+                target.hash.describe_cost()
+            ));
+        }
+
+        for word_hash in self.attack(candidates, &mut targets) {
+            record_cracked(
+                &self.reporter,
+                &mut potfile,
+                &word_hash.hash,
+                &word_hash.word,
+            );
+            targets.report(&self.reporter, &word_hash.hash, &Ok(word_hash.word.clone()));
+            cracked.push(word_hash);
+        }
+        if let Some(time_budget) = &self.time_budget {
+            for hash in &targets.exhausted {
+                targets.report_exhausted(&self.reporter, hash, time_budget);
+            }
+        }
+        for hash in targets.remaining() {
+            targets.report(&self.reporter, &hash, &Err(()));
+        }
+        for (word, accounts) in targets.shared_passwords(&cracked) {
+            self.reporter.record(Record {
+                plaintext: Some(word),
+                count: Some(accounts.len()),
+                detail: Some(accounts.join(", ")),
+                ..Record::new(Event::SharedPassword)
+            });
+        }
+        0
+    }
+}
+
+pub struct PotfileViewer {
+    hash_file_path: String,
+    potfile_path: String,
+    pub reporter: Rc<Reporter>,
+}
+
+impl PotfileViewer {
+    pub fn new(hash_file_path: String, potfile_path: String) -> PotfileViewer {
+        PotfileViewer {
+            hash_file_path,
+            potfile_path,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn cracked_entries(&self, potfile: &Potfile, lines: &[String]) -> Vec<hasher::WordHash> {
+        /*
+            Lines are matched as a whole first, then by the part before the first
+            delimiter, so `hash:salt` lines match the hash they were cracked as
+        */
+        lines
+            .iter()
+            .filter_map(|line| {
+                let hash = line.split(hasher::HASH_DELIMITER).next()?;
+                let word = potfile.lookup(line).or_else(|| potfile.lookup(hash))?;
+                Some(hasher::WordHash {
+                    word: String::from(word),
+                    hash: line.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Operator for PotfileViewer {
+    fn run(&self) -> i32 {
+        let potfile = match Potfile::open(&self.potfile_path) {
+            Ok(potfile) => potfile,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        warn_invalid_lines(&self.reporter, &potfile);
+        let lines = match reader::read_words(&self.hash_file_path) {
+            Ok(lines) => lines,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        for word_hash in self.cracked_entries(&potfile, &lines) {
+            self.reporter.record(Record {
+                hash: Some(word_hash.hash),
+                plaintext: Some(word_hash.word),
+                ..Record::new(Event::Shown)
+            });
+        }
+        0
+    }
+}
+
+pub struct TableVerifier {
+    rainbow_table_file_path: String,
+    // How many words to hash again, to check them against their digests
+    pub sample: Sample,
+    pub reporter: Rc<Reporter>,
+}
+
+impl TableVerifier {
+    pub fn new(rainbow_table_file_path: String) -> TableVerifier {
+        TableVerifier {
+            rainbow_table_file_path,
+            sample: Sample::Full,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
+
+impl Operator for TableVerifier {
+    fn run(&self) -> i32 {
+        let report = match verify::verify_table(&self.rainbow_table_file_path, self.sample) {
+            Ok(report) => report,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        for note in &report.notes {
+            self.reporter.warning(note);
+        }
+        for problem in &report.problems {
+            self.reporter.record(Record {
+                path: Some(problem.path.clone()),
+                position: problem.position.clone(),
+                detail: Some(problem.detail.clone()),
+                ..Record::new(Event::TableProblem)
+            });
+        }
+        let problems = match report.problems.len() {
+            0 => String::from("no problems found"),
+            1 => String::from("1 problem found"),
+            count => format!("{} problems found", count),
+        };
+        let sorted = if report.sorted {
+            "sorted"
+        } else {
+            "not sorted"
+        };
+        self.reporter.record(Record {
+            count: Some(report.entry_count),
+            path: Some(self.rainbow_table_file_path.clone()),
+            detail: Some(format!(
+                "{}, {}, {} digests re-hashed",
+                problems, sorted, report.rehashed_count
+            )),
+            ..Record::new(Event::Verified)
+        });
+        match report.problems.is_empty() {
+            true => 0,
+            false => TABLE_VERIFICATION_FAILED_EXIT_CODE,
+        }
+    }
+}
+
+pub struct TableInspector {
+    rainbow_table_file_path: String,
+    pub reporter: Rc<Reporter>,
+}
+
+impl TableInspector {
+    pub fn new(rainbow_table_file_path: String) -> TableInspector {
+        TableInspector {
+            rainbow_table_file_path,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
+
+impl Operator for TableInspector {
+    fn run(&self) -> i32 {
+        let table_info = match info::table_info(&self.rainbow_table_file_path) {
+            Ok(table_info) => table_info,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        for (stat, value) in table_info.stats() {
+            let (count, detail) = match value {
+                StatValue::Count(count) => (Some(count as usize), None),
+                StatValue::Text(text) => (None, Some(text)),
+            };
+            self.reporter.record(Record {
+                path: Some(self.rainbow_table_file_path.clone()),
+                stat: Some(stat),
+                count,
+                detail,
+                ..Record::new(Event::TableStat)
+            });
+        }
+        if table_info.invalid_count > 0 {
+            self.reporter.warning(&format!(
+                "{} entries could not be parsed, run verify-table to find them",
+                table_info.invalid_count
+            ));
+        }
+        0
+    }
+}
+
+pub struct TableConverter {
+    input_path: String,
+    // A file, or for RainbowCrack tables the directory the named .rt file is written to
+    output_path: String,
+    format: ConvertFormat,
+    // RainbowCrack charset to export with, instead of the smallest one that fits every word
+    pub charset: Option<String>,
+    pub reporter: Rc<Reporter>,
+}
+
+impl TableConverter {
+    pub fn new(input_path: String, output_path: String, format: ConvertFormat) -> TableConverter {
+        TableConverter {
+            input_path,
+            output_path,
+            format,
+            charset: None,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn read_table(&self) -> Result<(TableHeader, Vec<hasher::WordHash>), String> {
+        if shard::is_sharded_table(&self.input_path) {
+            return Err(format!(
+                "{} is a sharded table, convert each of its shards on their own",
+                self.input_path
+            ));
+        }
+        if rainbowcrack::is_rainbowcrack_path(&self.input_path) {
+            return rainbowcrack::import_table(&self.input_path);
+        }
+        let table = storage::open_table(&self.input_path)?;
+        let word_hashes = table.read_all()?;
+        let header = match table.read_header()? {
+            Some(header) => header,
+            // Tables from before headers were always sha256(p)
+            None => {
+                self.reporter.warning(&format!(
+                    "{} has no header, assuming it holds sha256 hashes",
+                    self.input_path
+                ));
+                TableHeader::new(TableHashing::Plain, &word_hashes)
+            }
+        };
+        Ok((header, word_hashes))
+    }
+
+    fn convert<R: BufRead>(&self, mut reader: R) -> i32 {
+        let (header, word_hashes) = match self.read_table() {
+            Ok(table) => table,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+
+        let written = match self.format {
+            ConvertFormat::Storage(storage_format) => {
+                if path::Path::new(&self.output_path).exists() {
+                    let prompt = format!("{} already exists.", &self.output_path);
+                    match confirm_overwrite(&mut reader, &prompt) {
+                        Ok(true) => (),
+                        Ok(false) => return 0,
+                        Err(e) => {
+                            self.reporter.error(&e);
+                            return INPUT_READ_ERROR;
+                        }
+                    }
+                }
+                if self.charset.is_some() {
+                    self.reporter
+                        .warning("--charset only applies when converting to rt, ignoring it");
+                }
+                storage::create_table(&self.output_path, storage_format)
+                    .write(&header, &word_hashes)
+                    .map(|_| self.output_path.clone())
+            }
+            ConvertFormat::RainbowCrack => rainbowcrack::export_table(
+                &header,
+                &word_hashes,
+                self.charset.as_deref(),
+                &self.output_path,
+            ),
+        };
+        match written {
+            Ok(written_path) => {
+                self.reporter.record(Record {
+                    count: Some(word_hashes.len()),
+                    path: Some(written_path),
+                    detail: Some(self.format.to_string()),
+                    ..Record::new(Event::Converted)
+                });
+                0
+            }
+            Err(e) => {
+                self.reporter.error(&e);
+                reader::FILE_OPERATION_ERROR
+            }
+        }
+    }
+}
+
+impl Operator for TableConverter {
+    fn run(&self) -> i32 {
+        self.reporter.info(&format!(
+            "Converting {} to {}",
+            &self.input_path, self.format
+        ));
+        let stdin = stdin();
+        self.convert(stdin.lock())
+    }
+}
+
+pub struct Benchmarker {
+    // Every digest and scheme by default
+    pub hashers: Vec<BenchmarkHasher>,
+    // Threads to hash on besides a single one, all available by default
+    pub threads: usize,
+    // How long each measurement runs for
+    pub duration: Duration,
+    // Entries in each of the tables lookups are timed against
+    pub table_entries: usize,
+    pub reporter: Rc<Reporter>,
+}
+
+impl Default for Benchmarker {
+    fn default() -> Benchmarker {
+        Benchmarker {
+            hashers: benchmark::hashers(),
+            threads: benchmark::available_threads(),
+            duration: Duration::from_millis(benchmark::DEFAULT_DURATION_MS),
+            table_entries: benchmark::DEFAULT_TABLE_ENTRIES,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
+
+fn describe_threads(threads: usize) -> String {
+    match threads {
+        1 => String::from("1 thread"),
+        threads => format!("{} threads", threads),
+    }
+}
+
+impl Operator for Benchmarker {
+    fn run(&self) -> i32 {
+        let mut thread_counts = vec![1, self.threads.max(1)];
+        thread_counts.dedup();
+        self.reporter.info(&format!(
+            "Hashing with {} algorithms for {} ms each on {}",
+            self.hashers.len(),
+            self.duration.as_millis(),
+            thread_counts
+                .iter()
+                .map(|&threads| describe_threads(threads))
+                .collect::<Vec<String>>()
+                .join(" and ")
+        ));
+        for &hasher in &self.hashers {
+            for &threads in &thread_counts {
+                let hash_rate = benchmark::hashes_per_second(hasher, threads, self.duration);
+                self.reporter.record(Record {
+                    algorithm: Some(hasher.name()),
+                    count: Some(hash_rate as usize),
+                    detail: Some(describe_threads(threads)),
+                    ..Record::new(Event::HashRate)
+                });
+            }
+        }
+
+        self.reporter.info(&format!(
+            "Looking up hashes in tables of {} entries",
+            self.table_entries
+        ));
+        for format in [
+            StorageFormat::Text,
+            StorageFormat::Sqlite,
+            StorageFormat::Binary,
+        ] {
+            match benchmark::lookup_latency(format, self.table_entries, self.duration) {
+                Ok(latency) => self.reporter.record(Record {
+                    count: Some(latency.as_nanos() as usize),
+                    detail: Some(format.to_string()),
+                    ..Record::new(Event::LookupLatency)
+                }),
+                Err(e) => {
+                    self.reporter.error(&e);
+                    return reader::FILE_OPERATION_ERROR;
+                }
+            }
+        }
+        0
+    }
+}
+
+pub struct TablePlanner {
+    // RainbowCrack charset and plaintext lengths that make up the keyspace
+    charset: String,
+    min_length: usize,
+    max_length: usize,
+    // Chance of a hash from the keyspace being found in one of the tables
+    success_probability: f64,
+    pub hasher: BenchmarkHasher,
+    pub chain_length: u64,
+    // Chosen to be as few as can reach the success probability when not given
+    pub table_count: Option<u64>,
+    // Hashes per second to estimate times with. Measured on every core when not given
+    pub hash_rate: Option<u64>,
+    pub reporter: Rc<Reporter>,
+}
+
+impl TablePlanner {
+    pub fn new(
+        charset: String,
+        min_length: usize,
+        max_length: usize,
+        success_probability: f64,
+    ) -> TablePlanner {
+        TablePlanner {
+            charset,
+            min_length,
+            max_length,
+            success_probability,
+            hasher: BenchmarkHasher::Digest(hasher::Algorithm::Sha256),
+            chain_length: plan::DEFAULT_CHAIN_LENGTH,
+            table_count: None,
+            hash_rate: None,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+
+    fn measure_hash_rate(&self) -> u64 {
+        let threads = benchmark::available_threads();
+        self.reporter.info(&format!(
+            "Measuring the {} hash rate on {}",
+            self.hasher.name(),
+            describe_threads(threads)
+        ));
+        benchmark::hashes_per_second(
+            self.hasher,
+            threads,
+            Duration::from_millis(benchmark::DEFAULT_DURATION_MS),
+        )
+    }
+
+    fn stats(&self, plan: &Plan, hash_rate: u64) -> Vec<(String, StatValue)> {
+        let percentage = |probability: f64| StatValue::Text(format!("{:.2}%", probability * 100.0));
+        // Saturates for estimates too long to hold, which are far beyond any useful plan anyway
+        let time = |hashes: u64| {
+            let seconds = hashes as f64 / hash_rate.max(1) as f64;
+            StatValue::Text(progress::format_duration(
+                Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX),
+            ))
+        };
+        vec![
+            (String::from("keyspace"), StatValue::Count(plan.keyspace)),
+            (
+                String::from("algorithm"),
+                StatValue::Text(self.hasher.name()),
+            ),
+            (
+                String::from("chain_length"),
+                StatValue::Count(plan.parameters.chain_length),
+            ),
+            (
+                String::from("chain_count"),
+                StatValue::Count(plan.parameters.chain_count),
+            ),
+            (
+                String::from("table_count"),
+                StatValue::Count(plan.parameters.table_count),
+            ),
+            (
+                String::from("success_probability"),
+                percentage(plan.success_probability),
+            ),
+            (
+                String::from("table_success_probability"),
+                percentage(plan.table_success_probability),
+            ),
+            (
+                String::from("distinct_chains"),
+                StatValue::Count(plan.distinct_chains),
+            ),
+            (String::from("disk_size"), StatValue::Count(plan.disk_size)),
+            (String::from("hash_rate"), StatValue::Count(hash_rate)),
+            (
+                String::from("precomputation_hashes"),
+                StatValue::Count(plan.precomputation_hashes),
+            ),
+            (
+                String::from("precomputation_time"),
+                time(plan.precomputation_hashes),
+            ),
+            (
+                String::from("lookup_hashes"),
+                StatValue::Count(plan.lookup_hashes),
+            ),
+            (String::from("lookup_time"), time(plan.lookup_hashes)),
+            (
+                String::from("table_searches"),
+                StatValue::Count(plan.table_searches),
+            ),
+        ]
+    }
+}
+
+impl Operator for TablePlanner {
+    fn run(&self) -> i32 {
+        let parameters = rainbowcrack::keyspace(&self.charset, self.min_length, self.max_length)
+            .and_then(|keyspace| {
+                let parameters = plan::choose_parameters(
+                    keyspace,
+                    self.success_probability,
+                    self.chain_length,
+                    self.table_count,
+                )?;
+                Ok((keyspace, parameters))
+            });
+        let plan = match parameters {
+            Ok((keyspace, parameters)) => Plan::new(keyspace, parameters),
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        let hash_rate = self.hash_rate.unwrap_or_else(|| self.measure_hash_rate());
+        for (stat, value) in self.stats(&plan, hash_rate) {
+            let (count, detail) = match value {
+                StatValue::Count(count) => (Some(count as usize), None),
+                StatValue::Text(text) => (None, Some(text)),
+            };
+            self.reporter.record(Record {
+                stat: Some(stat),
+                count,
+                detail,
+                ..Record::new(Event::PlanStat)
+            });
+        }
+        0
+    }
+}
+
+pub struct ChainTableGenerator {
+    // Directory the table is written to, under a name that says how it was generated
+    output_dir: String,
+    algorithm: String,
+    // RainbowCrack charset and plaintext lengths the chains cover
+    charset: String,
+    min_length: usize,
+    max_length: usize,
+    chain_count: u64,
+    pub table_index: u64,
+    pub mode: ChainMode,
+    pub reporter: Rc<Reporter>,
+}
+
+impl ChainTableGenerator {
+    pub fn new(
+        output_dir: String,
+        algorithm: String,
+        charset: String,
+        min_length: usize,
+        max_length: usize,
+        chain_count: u64,
+    ) -> ChainTableGenerator {
+        ChainTableGenerator {
+            output_dir,
+            algorithm,
+            charset,
+            min_length,
+            max_length,
+            chain_count,
+            table_index: 0,
+            mode: ChainMode::Rainbow {
+                chain_length: plan::DEFAULT_CHAIN_LENGTH,
+            },
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
+
+impl ChainTableGenerator {
+    fn generate<R: BufRead>(&self, mut reader: R) -> i32 {
+        let space = match ChainSpace::new(
+            &self.algorithm,
+            &self.charset,
+            self.min_length,
+            self.max_length,
+            self.table_index,
+        ) {
+            Ok(space) => space,
+            Err(e) => {
+                self.reporter.error(&e);
+                return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+            }
+        };
+        let mut progress =
+            self.reporter
+                .progress("Generating chains", "hashes", Some(self.chain_count));
+        let generated =
+            match ChainTable::generate(space, self.mode, self.chain_count, &mut progress) {
+                Ok(generated) => generated,
+                Err(e) => {
+                    self.reporter.error(&e);
+                    return CRACK_HASH_RUNTIME_ERROR_EXIT_CODE;
+                }
+            };
+        drop(progress);
+        if let ChainMode::DistinguishedPoint {
+            max_chain_length, ..
+        } = self.mode
+        {
+            if generated.dropped > 0 {
+                self.reporter.warning(&format!(
+                    "Dropped {} of {} chains that reached no distinguished point within {} hashes, \
+                    a smaller mask or a larger --max-chain-length keeps more of them",
+                    generated.dropped, self.chain_count, max_chain_length
+                ));
+            }
+            if generated.merged > 0 {
+                self.reporter.info(&format!(
+                    "Merged {} chains into longer ones ending at the same distinguished point",
+                    generated.merged
+                ));
+            }
+        }
+        // Only asked now, as the name holds the number of chains that were kept
+        let table_path = generated.table.path(&self.output_dir);
+        if path::Path::new(&table_path).exists() {
+            let prompt = format!("{} already exists.", &table_path);
+            match confirm_overwrite(&mut reader, &prompt) {
+                Ok(true) => (),
+                Ok(false) => return 0,
+                Err(e) => {
+                    self.reporter.error(&e);
+                    return INPUT_READ_ERROR;
+                }
+            }
+        }
+        match generated.table.write(&self.output_dir) {
+            Ok(table_path) => {
+                self.reporter.record(Record {
+                    count: Some(generated.table.chains.len()),
+                    path: Some(table_path),
+                    detail: Some(String::from("chains")),
+                    ..Record::new(Event::Generated)
+                });
+                0
+            }
+            Err(e) => {
+                self.reporter.error(&e);
+                reader::FILE_OPERATION_ERROR
+            }
+        }
+    }
+}
+
+impl Operator for ChainTableGenerator {
+    fn run(&self) -> i32 {
+        let stdin = stdin();
+        self.generate(stdin.lock())
+    }
+}
+
+pub struct ChainCracker {
+    table_path: String,
+    hash: String,
+    pub reporter: Rc<Reporter>,
+}
+
+impl ChainCracker {
+    pub fn new(table_path: String, hash: String) -> ChainCracker {
+        ChainCracker {
+            table_path,
+            hash,
+            reporter: Rc::new(Reporter::default()),
+        }
+    }
+}
+
+impl Operator for ChainCracker {
+    fn run(&self) -> i32 {
+        let table = match ChainTable::open(&self.table_path) {
+            Ok(table) => table,
+            Err(e) => {
+                self.reporter.error(&e);
+                return reader::FILE_OPERATION_ERROR;
+            }
+        };
+        match table.lookup(&self.hash) {
+            Ok(word) => {
+                self.reporter
+                    .single_crack_result(&self.hash, &word.ok_or(()));
+                0
+            }
+            Err(e) => {
+                self.reporter.error(&e);
+                CRACK_HASH_RUNTIME_ERROR_EXIT_CODE
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod potfile_viewer_tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn test_cracked_entries() {
+        let potfile_handler = test_utils::TempFileHandler::new();
+        let mut potfile = Potfile::open(&potfile_handler.temp_file_path).unwrap();
+        potfile.append("aaa", "first").unwrap();
+        potfile.append("S:bbb", "second").unwrap();
+
+        let lines = vec![
+            "aaa".to_string(),
+            "aaa:salt".to_string(),
+            "S:bbb".to_string(),
+            "ccc".to_string(),
+        ];
+        let viewer = PotfileViewer::new("".to_string(), potfile_handler.temp_file_path.clone());
+        let entries: Vec<String> = viewer
+            .cracked_entries(&potfile, &lines)
+            .into_iter()
+            .map(|word_hash| format!("{}:{}", word_hash.hash, word_hash.word))
+            .collect();
+        assert_eq!(vec!["aaa:first", "aaa:salt:first", "S:bbb:second"], entries);
+    }
+}
+
+#[cfg(test)]
+mod rainbow_table_generator_tests {
+    use super::*;
+    use crate::hasher::{serialize_hashes, HASH_DELIMITER};
+    use crate::test_utils;
+    use std::io::{BufReader, BufWriter, Read, Write};
+
+    #[test]
+    fn test_rainbow_table_write_hashes_to_file() {
+        // Create a temp file for the test to write to
+        let temp_file_handler = test_utils::TempFileHandler::new();
+
+        // Create serialized hashes vec for testing
+        let words = vec![
+            "potato".to_string(),
+            "rice".to_string(),
+            "noodles".to_string(),
+            "salad".to_string(),
+        ];
+        let serialized_hashes = serialize_hashes(words);
+        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
+        let operator = RainbowTableGenerator::new("".to_string(), temp_file_path);
+        let input = b"y\n";
+        // https://stackoverflow.com/questions/28370126/how-can-i-test-stdin-and-stdout
+        operator.write_hashes_to_file(&input[..], serialized_hashes);
+
+        // Verify that the expected things were written to the file
+        let wordfile = temp_file_handler.get_file_object(test_utils::FileMode::Read);
+        let reader = BufReader::new(wordfile);
+        let expected_lines = [
+            format!(
+                "potato{}e91c254ad58860a02c788dfb5c1a65d6a8846ab1dc649631c7db16fef4af2dec",
+                HASH_DELIMITER
+            ),
+            format!(
+                "rice{}209f76418ece7c936b65ff4777a578d860f762c37ad6c7f08f5826242199ef51",
+                HASH_DELIMITER
+            ),
+            format!(
+                "noodles{}838f8d9acc45bd36e3213c47c3222e644f44c959fa370bbfa6df46b171c02f0c",
+                HASH_DELIMITER
+            ),
+            format!(
+                "salad{}c6c3fa689e291bba6f7436ee76dc542ec4678a410a2adbb26bbedfd1e6a8aa85",
+                HASH_DELIMITER
+            ),
+        ];
+        let mut lines = reader.lines();
+        let header = lines.next().unwrap().unwrap();
+        assert_eq!(
+            Ok(4),
+            TableHeader::deserialize(&header).map(|header| header.count)
+        );
+        for line in lines {
+            match line {
+                Ok(line) => assert!(
+                    expected_lines.contains(&line),
+                    "Expected line not found in written wordfile: {}",
+                    line
+                ),
+                Err(e) => panic!("{}", e),
+            };
+        }
+    }
+
+    #[test]
+    fn test_write_hashes_to_file_not_overwrite() {
+        // Create a temp file with sample text in it to check if it got overwritten
+        let sample_text = "The quick brown fox jumps over the lazy dog.";
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        // Write sample text into file
+        let mut writer = BufWriter::new(&file);
+        match writer.write_all(sample_text.as_bytes()) {
+            Ok(_) => (),
+            Err(e) => panic!("{}", e),
+        };
+        // Drop file pointer and writer to "close" the file, prevent any conflicts later on
+        std::mem::drop(writer);
+        std::mem::drop(file);
+
+        let words = vec!["potato".to_string()];
+        let serialized_hashes = serialize_hashes(words);
+        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
+        let operator = RainbowTableGenerator::new("".to_string(), temp_file_path);
+        let input = b"n\n";
+        operator.write_hashes_to_file(&input[..], serialized_hashes);
+        // File should not be overwritten
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Read);
+        let mut reader = BufReader::new(&file);
+        let mut read_text = String::new();
+        // Read all text to a string buffer, to make sure that nothing else was appended to the file
+        match reader.read_to_string(&mut read_text) {
+            // If Ok, read until EOF was successful
+            Ok(_) => (),
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(sample_text, read_text);
+        println!("Test complete!")
+    }
+
+    #[test]
+    fn test_write_hashes_to_file_sharded() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let table_dir = format!("{}.shards", temp_file_handler.temp_file_path);
+        // The hashes of these start with e9, 20, 83 and c6
+        let words = vec![
+            "potato".to_string(),
+            "rice".to_string(),
+            "noodles".to_string(),
+            "salad".to_string(),
+        ];
+        let mut operator = RainbowTableGenerator::new("".to_string(), table_dir.clone());
+        operator.shard_count = Some(4);
+        operator.shard_index = Some(3);
+        assert_eq!(
+            0,
+            operator.write_hashes_to_file(&b"y\n"[..], serialize_hashes(words.clone()))
+        );
+        let manifest = ShardManifest::open(&table_dir).unwrap();
+        assert_eq!(4, manifest.shard_count);
+        // Only shard 3 was generated, holding the hashes starting with the bits 11
+        assert!(!path::Path::new(&manifest.shard_path(&table_dir, 0)).exists());
+        let shard = storage::open_table(&manifest.shard_path(&table_dir, 3))
+            .and_then(|shard| shard.read_all())
+            .unwrap();
+        assert_eq!(2, shard.len());
+        assert!(shard[0].word == "potato" && shard[1].word == "salad");
+
+        // The rest can be generated separately, into the same directory
+        operator.shard_index = None;
+        assert_eq!(
+            0,
+            operator.write_hashes_to_file(&b"y\n"[..], serialize_hashes(words.clone()))
+        );
+        let shard_sizes: Vec<usize> = (0..4)
+            .map(|shard_index| {
+                storage::open_table(&manifest.shard_path(&table_dir, shard_index))
+                    .and_then(|shard| shard.read_all())
+                    .unwrap()
+                    .len()
+            })
+            .collect();
+        assert_eq!(vec![1, 0, 1, 2], shard_sizes);
+
+        // A different layout in the same directory is refused
+        operator.shard_count = Some(8);
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            operator.write_hashes_to_file(&b"y\n"[..], serialize_hashes(words))
+        );
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_hashes_to_file_bloom_filter() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
+        let sidecar_path = bloom::sidecar_path(&temp_file_path);
+        let serialized_hashes = serialize_hashes(vec!["potato".to_string(), "rice".to_string()]);
+
+        let mut operator = RainbowTableGenerator::new("".to_string(), temp_file_path);
+        operator.bloom_false_positive_rate = Some(0.01);
+        assert_eq!(
+            0,
+            operator.write_hashes_to_file(&b"y\n"[..], serialized_hashes.clone())
+        );
+        let bloom_filter = BloomFilter::open(&sidecar_path).unwrap().unwrap();
+        assert_eq!(2, bloom_filter.item_count());
+        assert!(bloom_filter
+            .contains("e91c254ad58860a02c788dfb5c1a65d6a8846ab1dc649631c7db16fef4af2dec"));
+        assert!(!bloom_filter.contains("potato"));
+
+        // Regenerating the table without a filter removes the now stale one
+        operator.bloom_false_positive_rate = None;
+        assert_eq!(
+            0,
+            operator.write_hashes_to_file(&b"y\n"[..], serialized_hashes)
+        );
+        assert!(!path::Path::new(&sidecar_path).exists());
+    }
+
+    #[test]
+    fn test_write_hashes_to_file_sqlite() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
+        let serialized_hashes = serialize_hashes(vec!["potato".to_string(), "rice".to_string()]);
+
+        let mut operator = RainbowTableGenerator::new("".to_string(), temp_file_path.clone());
+        operator.storage_format = StorageFormat::Sqlite;
+        assert_eq!(
+            0,
+            operator.write_hashes_to_file(&b"y\n"[..], serialized_hashes.clone())
+        );
+        assert_eq!(
+            Ok(StorageFormat::Sqlite),
+            storage::detect_format(&temp_file_path)
+        );
+        let rainbow_table = storage::open_table(&temp_file_path).unwrap();
+        assert_eq!(
+            Ok(hasher::deserialize_hashes(serialized_hashes).unwrap()),
+            rainbow_table.read_all()
+        );
+    }
+
+    fn sort_leftovers(temp_file_path: &str) -> usize {
+        // Sorted runs are spilled next to the table, in a directory of their own
+        fs::read_dir(path::Path::new(temp_file_path).parent().unwrap())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .contains(".sort-")
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_generate_sorted() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let word_file_path = format!("{}.words", temp_file_handler.temp_file_path);
+        let table_path = format!("{}.table", temp_file_handler.temp_file_path);
+        let words: Vec<String> = (0..2000).map(|i| format!("word{}", i)).collect();
+        // Duplicate words only make it into the table once
+        fs::write(
+            &word_file_path,
+            format!("{}\n{}\n", words.join("\n"), words[0]),
+        )
+        .unwrap();
+
+        let mut operator = RainbowTableGenerator::new(word_file_path, table_path.clone());
+        operator.sort = true;
+        // Far less than the words take up, so they are sorted in runs and merged
+        operator.memory_limit = 8192;
+        operator.storage_format = StorageFormat::Binary;
+        assert_eq!(0, operator.generate_sorted(&b""[..]));
+        assert_eq!(0, sort_leftovers(&temp_file_handler.temp_file_path));
+
+        let table = storage::open_table(&table_path).unwrap();
+        let header = table.read_header().unwrap().unwrap();
+        let mut expected = hasher::deserialize_hashes(serialize_hashes(words)).unwrap();
+        expected.sort_by(|a, b| a.hash.cmp(&b.hash));
+        assert!(header.sorted);
+        assert_eq!(TableHeader::new(TableHashing::Plain, &expected), header);
+        assert_eq!(Ok(expected), table.read_all());
+    }
+
+    #[test]
+    fn test_generate_sorted_sharded() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let word_file_path = format!("{}.words", temp_file_handler.temp_file_path);
+        let table_dir = format!("{}.shards", temp_file_handler.temp_file_path);
+        fs::write(&word_file_path, "potato\nrice\nnoodles\nsalad\n").unwrap();
+
+        let mut operator = RainbowTableGenerator::new(word_file_path, table_dir.clone());
+        operator.sort = true;
+        operator.shard_count = Some(4);
+        operator.bloom_false_positive_rate = Some(0.01);
+        assert_eq!(0, operator.generate_sorted(&b""[..]));
+        let manifest = ShardManifest::open(&table_dir).unwrap();
+        let shards: Vec<Vec<String>> = (0..4)
+            .map(|shard_index| {
+                let shard_path = manifest.shard_path(&table_dir, shard_index);
+                let shard = storage::open_table(&shard_path).unwrap();
+                assert!(shard.read_header().unwrap().unwrap().sorted);
+                let bloom_filter = BloomFilter::open(&bloom::sidecar_path(&shard_path));
+                let words: Vec<String> = shard
+                    .read_all()
+                    .unwrap()
+                    .into_iter()
+                    .map(|word_hash| word_hash.word)
+                    .collect();
+                assert_eq!(
+                    words.len() as u64,
+                    bloom_filter.unwrap().unwrap().item_count()
+                );
+                words
+            })
+            .collect();
+        // c6... sorts before e9...
+        let expected: Vec<Vec<String>> = vec![
+            vec!["rice".to_string()],
+            vec![],
+            vec!["noodles".to_string()],
+            vec!["salad".to_string(), "potato".to_string()],
+        ];
+        assert_eq!(expected, shards);
+
+        // A shard that does not exist fails before anything is written
+        operator.shard_index = Some(4);
+        assert_eq!(
+            CRACK_HASH_RUNTIME_ERROR_EXIT_CODE,
+            operator.generate_sorted(&b""[..])
+        );
+        // A missing word file leaves no sorted runs behind
+        operator.shard_index = None;
+        operator.word_file_path = String::from("/abc/defghi/jkl.qwerty");
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            operator.generate_sorted(&b"y\n"[..])
+        );
+        assert_eq!(0, sort_leftovers(&temp_file_handler.temp_file_path));
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_sorted_resume() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let word_file_path = format!("{}.words", temp_file_handler.temp_file_path);
+        let table_path = format!("{}.table", temp_file_handler.temp_file_path);
+        let checkpoint_dir = checkpoint::checkpoint_dir(&table_path);
+        let words: Vec<String> = (0..2000).map(|i| format!("word{}", i)).collect();
+
+        let mut operator = RainbowTableGenerator::new(word_file_path.clone(), table_path.clone());
+        operator.sort = true;
+        operator.memory_limit = 8192;
+        operator.checkpoint_interval = Some(500);
+        // Interrupted once the first half of the words had been checkpointed
+        fs::write(&word_file_path, format!("{}\n", words[..1000].join("\n"))).unwrap();
+        {
+            let mut sorter = ExternalSorter::checkpointed(&checkpoint_dir, 8192, None).unwrap();
+            let mut checkpoint = Checkpoint::new(operator.settings_fingerprint(), 0);
+            let mut progress = operator.reporter.progress("Generating", "words", None);
+            operator
+                .fill_sorter(&mut sorter, &None, Some(&mut checkpoint), &mut progress)
+                .unwrap();
+        }
+        fs::write(
+            &word_file_path,
+            format!(
+                "{}\n{}\n",
+                words[..1000].join("\n"),
+                words[1000..].join("\n")
+            ),
+        )
+        .unwrap();
+        let mut checkpoint = Checkpoint::open(&checkpoint_dir).unwrap().unwrap();
+        assert_eq!(1000, checkpoint.entry_count);
+        checkpoint.word_file_size = fs::metadata(&word_file_path).unwrap().len();
+        checkpoint.write(&checkpoint_dir).unwrap();
+
+        // Neither a fresh run nor one with other settings throws the checkpoint away
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            operator.generate_sorted(&b""[..])
+        );
+        operator.resume = true;
+        operator.lm = true;
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            operator.generate_sorted(&b""[..])
+        );
+        operator.lm = false;
+        assert_eq!(0, operator.generate_sorted(&b""[..]));
+        assert!(!path::Path::new(&checkpoint_dir).exists());
+
+        let table = storage::open_table(&table_path).unwrap();
+        let mut expected = hasher::deserialize_hashes(serialize_hashes(words)).unwrap();
+        expected.sort_by(|a, b| a.hash.cmp(&b.hash));
+        assert_eq!(Ok(expected), table.read_all());
+        // Nothing is left to resume
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            operator.generate_sorted(&b"y\n"[..])
+        );
+    }
+
+    #[test]
+    fn test_run_checkpointed_without_sort() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let word_file_path = format!("{}.words", temp_file_handler.temp_file_path);
+        let table_path = format!("{}.table", temp_file_handler.temp_file_path);
+        let words: Vec<String> = (0..2000).map(|i| format!("word{}", i)).collect();
+        fs::write(&word_file_path, format!("{}\n", words.join("\n"))).unwrap();
+
+        // Checkpointing takes the sorted path even though sorting was not asked for
+        let mut operator = RainbowTableGenerator::new(word_file_path, table_path.clone());
+        operator.memory_limit = 8192;
+        operator.checkpoint_interval = Some(500);
+        assert_eq!(0, operator.run());
+        assert!(!path::Path::new(&checkpoint::checkpoint_dir(&table_path)).exists());
+
+        let table = storage::open_table(&table_path).unwrap();
+        let mut expected = hasher::deserialize_hashes(serialize_hashes(words)).unwrap();
+        expected.sort_by(|a, b| a.hash.cmp(&b.hash));
+        assert!(table.read_header().unwrap().unwrap().sorted);
+        assert_eq!(Ok(expected), table.read_all());
+        // Resuming without --sort finds nothing to resume instead of starting over
+        operator.checkpoint_interval = None;
+        operator.resume = true;
+        assert_eq!(reader::FILE_OPERATION_ERROR, operator.run());
+    }
+}
+
+#[cfg(test)]
 mod hash_cracker_tests {
     use super::*;
-    use crate::hasher::WordHash;
+    use crate::hasher::WordHash;
+    use crate::test_utils;
+    use hasher::HASH_DELIMITER;
+    use std::io::{BufWriter, Write};
+
+    #[test]
+    fn test_crack_uppercase_hash() {
+        // Uppercase hex is cracked the same whichever way the table is stored
+        let uppercase_hash = hasher::hash_word("potato").to_uppercase();
+        for storage_format in [
+            StorageFormat::Text,
+            StorageFormat::Sqlite,
+            StorageFormat::Binary,
+        ] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let path = &temp_file_handler.temp_file_path;
+            test_utils::write_table(path, storage_format, &["rice", "potato"]);
+            let cracker = HashCracker::new(path.clone(), uppercase_hash.clone());
+            let rainbow_table = storage::open_table(path).unwrap();
+            assert_eq!(
+                Ok(Ok(String::from("potato"))),
+                cracker.crack_hash(rainbow_table.as_ref())
+            );
+        }
+    }
+
+    #[test]
+    fn test_crack_hash() {
+        let expected_hash =
+            "c10c7396898976bb8c95966eef6b45c81f66be86cdea5c593ae5cba1026cbbb5".to_string();
+        let expected_word = "malenia".to_string();
+        let word_hashes = vec![
+            WordHash {
+                hash: "1b8c5c045da33a8545e741e5095d8b96296d84ce1ea18a5918518e2a9c8eca98"
+                    .to_string(),
+                word: "uchigatana".to_string(),
+            },
+            WordHash {
+                hash: expected_hash.clone(),
+                word: expected_word.clone(),
+            },
+        ];
+
+        // Cracking works the same whichever way the table is stored
+        for storage_format in [StorageFormat::Text, StorageFormat::Sqlite] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let rainbow_table =
+                storage::create_table(&temp_file_handler.temp_file_path, storage_format);
+            let header = TableHeader::new(TableHashing::Plain, &word_hashes);
+            rainbow_table.write(&header, &word_hashes).unwrap();
+
+            // Test that expected cracking happens
+            let cracker = HashCracker::new("".to_string(), expected_hash.clone());
+            match cracker.crack_hash(rainbow_table.as_ref()) {
+                Ok(Ok(word)) => assert_eq!(*word, expected_word),
+                _ => panic!("Failed to crack expected word {}", expected_word),
+            };
+
+            // Test that Err is returned when hash is not present in rainbow table
+            let absent_word_hash =
+                String::from("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+            let cracker = HashCracker::new("".to_string(), absent_word_hash);
+            if let Ok(Ok(word)) = cracker.crack_hash(rainbow_table.as_ref()) {
+                panic!(
+                    "Word was cracked even though hash was not in rainbow table. Got: {}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let expected_word = "gitlab";
+        let expected_hash = "9d96d9d5b1addd7e7e6119a23b1e5b5f68545312bfecb21d1cdc6af22b8628b8";
+        let wordlist_lines = vec![
+            format!(
+                "hashicorp{}fe64108583908cdaeacb766f4e1c26977727ece6c44dd901ab1f511c32e22dc0",
+                HASH_DELIMITER
+            ),
+            format!(
+                "pulumi{}fbe2a04069387628783a3f90b947236e6ff8b1c099e710871356a6381a4e20b2",
+                HASH_DELIMITER
+            ),
+            format!("{}{}{}", expected_word, HASH_DELIMITER, expected_hash),
+        ];
+
+        // Write wordlist to file
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        for line in wordlist_lines {
+            if let Err(e) = writer.write(line.as_bytes()) {
+                panic!("{}", e);
+            }
+        }
+
+        let temp_file_path = temp_file_handler.temp_file_path.clone();
+        let cracker = HashCracker::new(temp_file_path, expected_hash.to_string());
+
+        let return_code = cracker.run();
+        assert_eq!(return_code, 0);
+
+        // Test that return code 0 is returned even when hash is uncracked
+        // Word is "absent"
+        let absent_hash =
+            "5ad38304b535c2987dbd24657c1a11b884984ff600d9f389deb0d4e634fee792".to_string();
+        let temp_file_path = temp_file_handler.temp_file_path.clone();
+        let cracker = HashCracker::new(temp_file_path, absent_hash);
+        let return_code = cracker.run();
+        assert_eq!(return_code, 0);
+    }
+
+    #[test]
+    fn test_run_potfile() {
+        let hash = "9d96d9d5b1addd7e7e6119a23b1e5b5f68545312bfecb21d1cdc6af22b8628b8";
+        let table_file_handler = test_utils::TempFileHandler::new();
+        let file = table_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(format!("gitlab{}{}", HASH_DELIMITER, hash).as_bytes())
+            .unwrap();
+        std::mem::drop(writer);
+
+        // Cracked hashes are appended to the potfile
+        let potfile_handler = test_utils::TempFileHandler::new();
+        let mut cracker =
+            HashCracker::new(table_file_handler.temp_file_path.clone(), hash.to_string());
+        cracker.potfile_path = Some(potfile_handler.temp_file_path.clone());
+        assert_eq!(0, cracker.run());
+        let potfile = Potfile::open(&potfile_handler.temp_file_path).unwrap();
+        assert_eq!(Some("gitlab"), potfile.lookup(hash));
+
+        // And found there without the table being read at all
+        let mut cracker = HashCracker::new("/abc/defghi/jkl.qwerty".to_string(), hash.to_string());
+        cracker.potfile_path = Some(potfile_handler.temp_file_path.clone());
+        assert_eq!(0, cracker.run());
+
+        // A malformed line is skipped rather than failing the run
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&potfile_handler.temp_file_path)
+            .unwrap();
+        file.write_all(b"no delimiter\n").unwrap();
+        assert_eq!(0, cracker.run());
+    }
+
+    #[test]
+    fn test_run_sharded() {
+        let present_hash = "9d96d9d5b1addd7e7e6119a23b1e5b5f68545312bfecb21d1cdc6af22b8628b8";
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let table_dir = format!("{}.shards", temp_file_handler.temp_file_path);
+        let manifest = ShardManifest::new(16).unwrap();
+        manifest.write(&table_dir).unwrap();
+        fs::write(
+            manifest.shard_path(&table_dir, 9),
+            format!("gitlab{}{}\n", HASH_DELIMITER, present_hash),
+        )
+        .unwrap();
+
+        // Only shard 9 is read, every other shard is missing
+        let cracker = HashCracker::new(table_dir.clone(), present_hash.to_string());
+        assert_eq!(0, cracker.run());
+        let absent_hash = "5ad38304b535c2987dbd24657c1a11b884984ff600d9f389deb0d4e634fee792";
+        let cracker = HashCracker::new(table_dir.clone(), absent_hash.to_string());
+        assert_eq!(reader::FILE_OPERATION_ERROR, cracker.run());
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_bloom_filter() {
+        let present_hash = "9d96d9d5b1addd7e7e6119a23b1e5b5f68545312bfecb21d1cdc6af22b8628b8";
+        let absent_hash = "5ad38304b535c2987dbd24657c1a11b884984ff600d9f389deb0d4e634fee792";
+        // The table itself is unreadable, so any run that touches it fails
+        let table_file_handler = test_utils::TempFileHandler::new();
+        let file = table_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        writer.write_all(b"not:a:table").unwrap();
+        std::mem::drop(writer);
+        let mut bloom_filter = BloomFilter::new(1, 0.01);
+        bloom_filter.insert(present_hash);
+        bloom_filter
+            .write(&bloom::sidecar_path(&table_file_handler.temp_file_path))
+            .unwrap();
+
+        let cracker = HashCracker::new(
+            table_file_handler.temp_file_path.clone(),
+            absent_hash.to_string(),
+        );
+        assert_eq!(0, cracker.run());
+        let cracker = HashCracker::new(
+            table_file_handler.temp_file_path.clone(),
+            present_hash.to_string(),
+        );
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, cracker.run());
+    }
+}
+
+#[cfg(test)]
+mod hash_identifier_tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let hashes = vec![
+            "5f4dcc3b5aa765d61d8327deb882cf99".to_string(),
+            "not a hash".to_string(),
+        ];
+        assert_eq!(0, HashIdentifier::new(hashes).run());
+    }
+}
+
+#[cfg(test)]
+mod lm_cracker_tests {
+    use super::*;
+    use crate::test_utils;
+    use std::io::{BufWriter, Write};
+
+    // LM hash of "password", in any case
+    const LM_HASH: &str = "e52cac67419a9a224a3b108f3fa6cb6d";
+
+    fn lm_table(
+        temp_file_handler: &test_utils::TempFileHandler,
+        words: Vec<String>,
+    ) -> Box<dyn TableStorage> {
+        let path = &temp_file_handler.temp_file_path;
+        fs::write(path, hasher::serialize_lm_hashes(words).join("\n")).unwrap();
+        storage::open_table(path).unwrap()
+    }
+
+    #[test]
+    fn test_crack_lm_hash() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let rainbow_table = lm_table(
+            &temp_file_handler,
+            vec!["dragon".to_string(), "password".to_string()],
+        );
+        let rainbow_table = rainbow_table.as_ref();
+        let mut cracker = LmCracker::new("".to_string(), LM_HASH.to_string());
+        assert_eq!(
+            Ok(Ok("PASSWORD".to_string())),
+            cracker.crack_lm_hash(rainbow_table)
+        );
+        cracker.ntlm_hash = Some(lm::ntlm_hash("PassWord"));
+        assert_eq!(
+            Ok(Ok("PassWord".to_string())),
+            cracker.crack_lm_hash(rainbow_table)
+        );
+
+        // Passwords of 7 characters or less have an empty second half
+        let cracker = LmCracker::new("".to_string(), lm::lm_hash("Dragon"));
+        assert_eq!(
+            Ok(Ok("DRAGON".to_string())),
+            cracker.crack_lm_hash(rainbow_table)
+        );
+    }
+
+    #[test]
+    fn test_crack_lm_hash_partial() {
+        // The table only has the first half, PASSWOR
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let rainbow_table = lm_table(&temp_file_handler, vec!["passwor".to_string()]);
+        let cracker = LmCracker::new("".to_string(), LM_HASH.to_string());
+        assert_eq!(
+            Ok(Err("Only the first half was cracked: PASSWOR".to_string())),
+            cracker.crack_lm_hash(rainbow_table.as_ref())
+        );
+
+        let rainbow_table = lm_table(&temp_file_handler, vec!["password".to_string()]);
+        let mut cracker = LmCracker::new("".to_string(), LM_HASH.to_string());
+        cracker.ntlm_hash = Some(lm::ntlm_hash("dragon"));
+        assert!(cracker
+            .crack_lm_hash(rainbow_table.as_ref())
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        let table_lines = hasher::serialize_lm_hashes(vec!["password".to_string()]);
+        writer.write_all(table_lines.join("\n").as_bytes()).unwrap();
+        std::mem::drop(writer);
+
+        let temp_file_path = temp_file_handler.temp_file_path.clone();
+        let cracker = LmCracker::new(temp_file_path, LM_HASH.to_string());
+        assert_eq!(0, cracker.run());
+
+        let temp_file_path = temp_file_handler.temp_file_path.clone();
+        let cracker = LmCracker::new(temp_file_path, "abc".to_string());
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, cracker.run());
+    }
+}
+
+#[cfg(test)]
+mod salted_hash_cracker_tests {
+    use super::*;
+    use crate::hasher::{SaltPosition, SaltedHash};
+
+    #[test]
+    fn test_crack_salted_hash() {
+        let words = vec![
+            "radahn".to_string(),
+            "password".to_string(),
+            "pepper".to_string(),
+        ];
+
+        // sha256("abc" || "password")
+        let cracker = SaltedHashCracker::new("".to_string(), vec![], SaltPosition::Prefix);
+        let salted_hash = SaltedHash {
+            hash: "c5ae5f176fadad3c9fe337ac7d4846b2603faffc66dfa47295d638021671a547".to_string(),
+            salt: "abc".to_string(),
+        };
+        assert_eq!(
+            Ok("password".to_string()),
+            cracker.crack_salted_hash(&words, &salted_hash)
+        );
+
+        // Same hash should not be cracked when the salt is expected at the end
+        let cracker = SaltedHashCracker::new("".to_string(), vec![], SaltPosition::Suffix);
+        assert!(cracker.crack_salted_hash(&words, &salted_hash).is_err());
+
+        // sha256("password" || "abc")
+        let salted_hash = SaltedHash {
+            hash: "6733b7ffeace4887c3b31258079c780d8db3018db9cbc05c500df3521f968df8".to_string(),
+            salt: "abc".to_string(),
+        };
+        assert_eq!(
+            Ok("password".to_string()),
+            cracker.crack_salted_hash(&words, &salted_hash)
+        );
+    }
+
+    #[test]
+    fn test_run_invalid_salted_hash() {
+        let cracker = SaltedHashCracker::new(
+            "".to_string(),
+            vec!["nosalt".to_string()],
+            SaltPosition::Prefix,
+        );
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, cracker.run());
+    }
+}
+
+#[cfg(test)]
+mod attacker_tests {
+    use super::*;
+    use crate::hasher::SaltPosition;
     use crate::test_utils;
-    use hasher::HASH_DELIMITER;
     use std::io::{BufWriter, Write};
 
+    fn mask_attacker(mask: &str, hashes: Vec<String>) -> Attacker {
+        Attacker::new(
+            CandidateSource::Mask(mask.to_string()),
+            hashes,
+            None,
+            SaltPosition::Prefix,
+        )
+    }
+
     #[test]
-    fn test_crack_hash() {
-        let expected_hash =
-            "c10c7396898976bb8c95966eef6b45c81f66be86cdea5c593ae5cba1026cbbb5".to_string();
-        let expected_word = "malenia".to_string();
-        let rainbow_table = vec![
-            WordHash {
-                hash: "1b8c5c045da33a8545e741e5095d8b96296d84ce1ea18a5918518e2a9c8eca98"
+    fn test_attack() {
+        // sha256("ab1") and sha256("abc" || "password"), the latter only reachable by the word list
+        let hashes = vec![
+            "ca5ba87c93d42f8a45c1e0f569bba8bac92c80f4ce6c864bd44d136572411b7e".to_string(),
+            "c5ae5f176fadad3c9fe337ac7d4846b2603faffc66dfa47295d638021671a547:abc".to_string(),
+        ];
+        let attacker = mask_attacker("ab?d", hashes);
+        let mut targets = attacker.read_targets().unwrap();
+        let words = vec!["zombie".to_string(), "password".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(
+            vec![hasher::WordHash {
+                word: "password".to_string(),
+                hash: "c5ae5f176fadad3c9fe337ac7d4846b2603faffc66dfa47295d638021671a547"
                     .to_string(),
-                word: "uchigatana".to_string(),
-            },
-            WordHash {
-                hash: expected_hash.clone(),
-                word: expected_word.clone(),
-            },
+            }],
+            cracked
+        );
+        assert_eq!(
+            vec!["ca5ba87c93d42f8a45c1e0f569bba8bac92c80f4ce6c864bd44d136572411b7e".to_string()],
+            targets.remaining()
+        );
+
+        let candidates = attacker.candidate_source.candidates().unwrap();
+        let cracked = attacker.attack(candidates, &mut targets);
+        assert_eq!("ab1", cracked[0].word);
+        assert!(targets.is_empty());
+    }
+
+    #[test]
+    fn test_attack_stops_early() {
+        // sha256("a1")
+        let hashes =
+            vec!["f55ff16f66f43360266b95db6f8fec01d76031054306ae4a4b380598f6cfd114".to_string()];
+        let attacker = mask_attacker("a?d", hashes);
+        let mut targets = attacker.read_targets().unwrap();
+
+        let mut candidates = attacker.candidate_source.candidates().unwrap();
+        let cracked = attacker.attack(&mut candidates, &mut targets);
+        assert_eq!("a1", cracked[0].word);
+        assert!(targets.is_empty());
+        // Only "a0" and "a1" should have been consumed
+        assert_eq!(Some("a2".to_string()), candidates.next());
+    }
+
+    #[test]
+    fn test_attack_uppercase_hashes() {
+        // sha256("a1") and sha256("ABC" || "a2") as dumps sometimes print them, in uppercase
+        let hashes = vec![
+            "F55FF16F66F43360266B95DB6F8FEC01D76031054306AE4A4B380598F6CFD114".to_string(),
+            format!("{}:ABC", hasher::hash_word("ABCa2").to_uppercase()),
         ];
+        let attacker = mask_attacker("a?d", hashes);
+        let mut targets = attacker.read_targets().unwrap();
+        let candidates = attacker.candidate_source.candidates().unwrap();
+        let cracked = attacker.attack(candidates, &mut targets);
+        let words: Vec<&str> = cracked
+            .iter()
+            .map(|word_hash| word_hash.word.as_str())
+            .collect();
+        assert_eq!(vec!["a1", "a2"], words);
+        assert_eq!(
+            "f55ff16f66f43360266b95db6f8fec01d76031054306ae4a4b380598f6cfd114",
+            cracked[0].hash
+        );
+        assert!(targets.is_empty());
+    }
 
-        // Test that expected cracking happens
-        let cracker = HashCracker::new("".to_string(), expected_hash);
-        match cracker.crack_hash(rainbow_table.to_vec()) {
-            Ok(word) => assert_eq!(*word, expected_word),
-            Err(_) => panic!("Failed to crack expected word {}", expected_word),
-        };
-
-        // Test that Err is returned when hash is not present in rainbow table
-        let absent_word_hash =
-            String::from("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
-        let cracker = HashCracker::new("".to_string(), absent_word_hash);
-        if let Ok(word) = cracker.crack_hash(rainbow_table) {
-            panic!(
-                "Word was cracked even though hash was not in rainbow table. Got: {}",
-                word
-            );
-        }
+    #[test]
+    fn test_attack_shadow_file() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        let shadow_lines = [
+            "root:$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/:19000:0:99999:7:::",
+            "daemon:*:19000:0:99999:7:::",
+            "user:$6$rounds=1000$abc$cdoAGQGxUujH1PHBiAw35gXAeIHP01NR2wd/ZbzMqNc.Ga9Wv2rR7SJljziPkfho8k85LucFIl5hWKb5KJ7VP/:19000:0:99999:7:::",
+        ];
+        writer
+            .write_all(shadow_lines.join("\n").as_bytes())
+            .unwrap();
+        std::mem::drop(writer);
+
+        let mut attacker = mask_attacker("x", vec![]);
+        attacker.account_file_path = Some(temp_file_handler.temp_file_path.clone());
+        attacker.account_format = AccountFormat::Shadow;
+        let mut targets = attacker.read_targets().unwrap();
+        assert_eq!(2, targets.verifiable.len());
+        assert_eq!(
+            Some(&vec!["root".to_string()]),
+            targets.accounts.get("$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/")
+        );
+
+        let words = vec!["password".to_string(), "dragon".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(
+            vec!["password".to_string(), "dragon".to_string()],
+            cracked
+                .into_iter()
+                .map(|word_hash| word_hash.word)
+                .collect::<Vec<String>>()
+        );
+        assert!(targets.is_empty());
     }
 
     #[test]
-    fn test_run() {
-        let expected_word = "gitlab";
-        let expected_hash = "9d96d9d5b1addd7e7e6119a23b1e5b5f68545312bfecb21d1cdc6af22b8628b8";
-        let wordlist_lines = vec![
-            format!(
-                "hashicorp{}fe64108583908cdaeacb766f4e1c26977727ece6c44dd901ab1f511c32e22dc0",
-                HASH_DELIMITER
+    fn test_attack_account_file() {
+        let account_files = [
+            (
+                AccountFormat::UserHash,
+                vec![
+                    // sha256 of "password" and "dragon"
+                    "alice:5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+                    "bob:5e884898da28047151d0e56f8dc6292773603d0d6aabbdd62a11ef721d1542d8",
+                    "carol:$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/",
+                    "dave:a9c43be948c5cabd56ef2bacffb77cdaa5eec49dd5eb0cc4129cf3cdb91b6e72",
+                ],
             ),
-            format!(
-                "pulumi{}fbe2a04069387628783a3f90b947236e6ff8b1c099e710871356a6381a4e20b2",
-                HASH_DELIMITER
+            (
+                AccountFormat::Htpasswd,
+                vec![
+                    "alice:$apr1$r31abcde$ouL8QL9v/FwrkrtBccxbL.",
+                    "bob:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=",
+                    "carol:abJnggxhB/yWI",
+                    "dave:$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+                ],
+            ),
+            (
+                AccountFormat::Pwdump,
+                vec![
+                    "alice:500:E52CAC67419A9A224A3B108F3FA6CB6D:8846F7EAEE8FB117AD06BDD830B7586C:::",
+                    "bob:1001:aad3b435b51404eeaad3b435b51404ee:8846f7eaee8fb117ad06bdd830b7586c:::",
+                    "carol:1002:aad3b435b51404eeaad3b435b51404ee:8846f7eaee8fb117ad06bdd830b7586c:::",
+                    "dave:1003:aad3b435b51404eeaad3b435b51404ee:9a22cc73c1c81c8eee87e33d7b3f3b2c:::",
+                ],
             ),
-            format!("{}{}{}", expected_word, HASH_DELIMITER, expected_hash),
         ];
+        for (format, lines) in account_files {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+            let mut writer = BufWriter::new(file);
+            writer.write_all(lines.join("\n").as_bytes()).unwrap();
+            std::mem::drop(writer);
 
-        // Write wordlist to file
+            let mut attacker = mask_attacker("x", vec![]);
+            attacker.account_file_path = Some(temp_file_handler.temp_file_path.clone());
+            attacker.account_format = format;
+            let mut targets = attacker.read_targets().unwrap();
+            let words = vec!["password".to_string(), "U*U".to_string()];
+            let cracked = attacker.attack(words.into_iter(), &mut targets);
+
+            // Everyone but dave uses "password", so they are grouped together
+            let mut shared = BTreeMap::new();
+            shared.insert(
+                "password".to_string(),
+                vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+            );
+            if format == AccountFormat::Htpasswd {
+                // dave's bcrypt test vector is for "U*U", elsewhere dave is not cracked at all
+                assert_eq!(vec!["dave".to_string()], targets.accounts[&cracked[3].hash]);
+            } else {
+                assert_eq!(
+                    vec!["dave".to_string()],
+                    targets
+                        .remaining()
+                        .iter()
+                        .flat_map(|hash| targets.accounts[hash].clone())
+                        .collect::<Vec<String>>()
+                );
+            }
+            assert_eq!(shared, targets.shared_passwords(&cracked), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_attack_database_dump() {
         let temp_file_handler = test_utils::TempFileHandler::new();
         let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
         let mut writer = BufWriter::new(file);
-        for line in wordlist_lines {
-            if let Err(e) = writer.write(line.as_bytes()) {
-                panic!("{}", e);
-            }
+        let dump_lines = [
+            "root\t*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19",
+            "admin|md5a6bfec695dcd4c0c095156994823bc28",
+            "guest:unsupported",
+        ];
+        writer.write_all(dump_lines.join("\n").as_bytes()).unwrap();
+        std::mem::drop(writer);
+
+        let mut attacker = mask_attacker("x", vec![]);
+        attacker.database_dump_file_path = Some(temp_file_handler.temp_file_path.clone());
+        let mut targets = attacker.read_targets().unwrap();
+        assert_eq!(2, targets.verifiable.len());
+        assert_eq!(
+            Some(&vec!["admin".to_string()]),
+            targets.accounts.get("md5a6bfec695dcd4c0c095156994823bc28")
+        );
+
+        let words = vec!["password".to_string(), "dragon".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(2, cracked.len());
+        assert!(targets.is_empty());
+    }
+
+    #[test]
+    fn test_attack_database_hashes() {
+        let hashes = vec![
+            "S:8E4B6DE728AE6411A2E276E451BCEA6ABF04B81DD1A2B3C4E5F60718293A".to_string(),
+            "md532e12f215ba27cb750c9e093ce4b5127:postgres".to_string(),
+        ];
+        let attacker = mask_attacker("x", hashes);
+        let mut targets = attacker.read_targets().unwrap();
+        assert_eq!(2, targets.verifiable.len());
+        assert!(targets.salted.is_empty());
+        let cracked = attacker.attack(vec!["password".to_string()].into_iter(), &mut targets);
+        assert_eq!(2, cracked.len());
+
+        // PostgreSQL hashes cannot be verified without the username
+        let attacker = mask_attacker("x", vec!["md532e12f215ba27cb750c9e093ce4b5127".to_string()]);
+        assert!(attacker.read_targets().is_err());
+    }
+
+    #[test]
+    fn test_attack_slow_hashes() {
+        let hashes = vec![
+            "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW".to_string(),
+            "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0$sYIePhT5IXESDKvnouJXtE5pTJ6Znbmef4vViYmc9Uc"
+                .to_string(),
+        ];
+        let attacker = mask_attacker("x", hashes);
+        let mut targets = attacker.read_targets().unwrap();
+        let words = vec!["U*U".to_string(), "password".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(2, cracked.len());
+        assert!(targets.is_empty());
+    }
+
+    #[test]
+    fn test_attack_time_budget() {
+        let hashes =
+            vec!["$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW".to_string()];
+        let mut attacker = mask_attacker("x", hashes);
+        attacker.time_budget = Some(Duration::ZERO);
+        let mut targets = attacker.read_targets().unwrap();
+        // The budget runs out after the first candidate, before the right word is tried
+        let words = vec!["wrong".to_string(), "U*U".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert!(cracked.is_empty());
+        assert_eq!(
+            vec!["$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW".to_string()],
+            targets.exhausted
+        );
+    }
+
+    #[test]
+    fn test_attack_hmac() {
+        // HMAC-SHA256("Jefe", "what do ya want for nothing?")
+        let hashes =
+            vec!["5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843".to_string()];
+        let mut attacker = mask_attacker("x", hashes);
+        attacker.hmac_key =
+            Some(hasher::HmacKey::from_hex(hasher::Algorithm::Sha256, "4a656665").unwrap());
+        let mut targets = attacker.read_targets().unwrap();
+        let words = vec!["what do ya want for nothing?".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(1, cracked.len());
+        assert!(targets.is_empty());
+
+        // Salted targets are rejected when an HMAC key is given
+        let mut attacker = mask_attacker("x", vec!["abc:salt".to_string()]);
+        attacker.hmac_key =
+            Some(hasher::HmacKey::from_hex(hasher::Algorithm::Sha256, "4a656665").unwrap());
+        assert!(attacker.read_targets().is_err());
+    }
+
+    #[test]
+    fn test_attack_account_file_incompatible() {
+        // Account lists are held to the same HMAC and hash expression rules as hashes
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let account_file_path = &temp_file_handler.temp_file_path;
+        fs::write(
+            account_file_path,
+            "alice:f24a0e9ce12069c72f77046ad708b655:abc\n",
+        )
+        .unwrap();
+        let mut attacker = mask_attacker("x", vec![]);
+        attacker.account_file_path = Some(account_file_path.clone());
+        attacker.hmac_key =
+            Some(hasher::HmacKey::from_hex(hasher::Algorithm::Sha256, "4a656665").unwrap());
+        assert!(attacker.read_targets().is_err());
+
+        fs::write(
+            account_file_path,
+            "alice:f24a0e9ce12069c72f77046ad708b655\n",
+        )
+        .unwrap();
+        attacker.hmac_key = None;
+        attacker.pipeline = Some(Pipeline::compile("md5(md5(p).salt)").unwrap());
+        assert!(attacker.read_targets().is_err());
+        attacker.pipeline = Some(Pipeline::compile("md5(md5(p))").unwrap());
+        assert!(attacker.read_targets().is_ok());
+    }
+
+    #[test]
+    fn test_attack_pipeline() {
+        // md5(md5("password") . "abc") and md5(md5("hello") . "salt")
+        let hashes = vec![
+            "f24a0e9ce12069c72f77046ad708b655:abc".to_string(),
+            "665aac580d91744758f20d11364ec88e:salt".to_string(),
+        ];
+        let mut attacker = mask_attacker("x", hashes);
+        attacker.pipeline = Some(Pipeline::compile("md5(md5(p).salt)").unwrap());
+        let mut targets = attacker.read_targets().unwrap();
+        let words = vec!["hello".to_string(), "password".to_string()];
+        let cracked = attacker.attack(words.into_iter(), &mut targets);
+        assert_eq!(2, cracked.len());
+        assert!(targets.is_empty());
+
+        // An unsalted hash cannot be attacked with an expression that needs a salt
+        let mut attacker = mask_attacker("x", vec!["f24a0e9ce12069c72f77046ad708b655".to_string()]);
+        attacker.pipeline = Some(Pipeline::compile("md5(md5(p).salt)").unwrap());
+        assert!(attacker.read_targets().is_err());
+    }
+
+    #[test]
+    fn test_attack_potfile() {
+        let potfile_handler = test_utils::TempFileHandler::new();
+        let mut potfile = Potfile::open(&potfile_handler.temp_file_path).unwrap();
+        let cracked_hash = hasher::hash_word("cracked");
+        potfile.append(&cracked_hash, "cracked").unwrap();
+        potfile
+            .append("$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/", "password")
+            .unwrap();
+
+        let hashes = vec![
+            cracked_hash.clone(),
+            hasher::hash_word("a1"),
+            "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/".to_string(),
+        ];
+        let mut attacker = mask_attacker("a?d", hashes);
+        attacker.potfile_path = Some(potfile_handler.temp_file_path.clone());
+        let mut targets = attacker.read_targets().unwrap();
+        let cracked = targets.take_cracked(&potfile);
+        assert_eq!(2, cracked.len());
+        assert_eq!(vec![hasher::hash_word("a1")], targets.remaining());
+
+        // Newly cracked hashes are added by a run
+        assert_eq!(0, attacker.run());
+        let potfile = Potfile::open(&potfile_handler.temp_file_path).unwrap();
+        assert_eq!(Some("a1"), potfile.lookup(&hasher::hash_word("a1")));
+    }
+
+    #[test]
+    fn test_run_invalid_mask() {
+        let attacker = mask_attacker("?x", vec!["abc".to_string()]);
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, attacker.run());
+    }
+}
+
+#[cfg(test)]
+mod table_verifier_tests {
+    use super::*;
+    use crate::hasher::serialize_hashes;
+    use crate::test_utils;
+
+    #[test]
+    fn test_run() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let temp_file_path = String::from(&temp_file_handler.temp_file_path);
+        let words = vec!["potato".to_string(), "rice".to_string()];
+        let generator = RainbowTableGenerator::new("".to_string(), temp_file_path.clone());
+        generator.write_hashes_to_file(&b"y\n"[..], serialize_hashes(words));
+        assert_eq!(0, TableVerifier::new(temp_file_path.clone()).run());
+
+        // Appending a line behind the header's back is caught
+        let mut content = fs::read_to_string(&temp_file_path).unwrap();
+        content
+            .push_str("salad:c6c3fa689e291bba6f7436ee76dc542ec4678a410a2adbb26bbedfd1e6a8aa85\n");
+        fs::write(&temp_file_path, content).unwrap();
+        assert_eq!(
+            TABLE_VERIFICATION_FAILED_EXIT_CODE,
+            TableVerifier::new(temp_file_path).run()
+        );
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            TableVerifier::new("/abc/defghi/jkl.qwerty".to_string()).run()
+        );
+    }
+}
+
+#[cfg(test)]
+mod table_inspector_tests {
+    use super::*;
+
+    #[test]
+    fn test_run_missing_table() {
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            TableInspector::new("/abc/defghi/jkl.qwerty".to_string()).run()
+        );
+    }
+}
+
+#[cfg(test)]
+mod table_converter_tests {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn test_convert_between_storage_formats() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let text_path = &temp_file_handler.temp_file_path;
+        let word_hashes =
+            test_utils::write_table(text_path, StorageFormat::Text, &["potato", "rice"]);
+        let header = storage::open_table(text_path)
+            .unwrap()
+            .read_header()
+            .unwrap();
+
+        let binary_path = format!("{}.bin", text_path);
+        let sqlite_path = format!("{}.sqlite", text_path);
+        let back_path = format!("{}.txt", text_path);
+        let conversions = [
+            (text_path, &binary_path, StorageFormat::Binary),
+            (&binary_path, &sqlite_path, StorageFormat::Sqlite),
+            (&sqlite_path, &back_path, StorageFormat::Text),
+        ];
+        for (input_path, output_path, storage_format) in conversions {
+            let converter = TableConverter::new(
+                input_path.to_string(),
+                output_path.to_string(),
+                ConvertFormat::Storage(storage_format),
+            );
+            assert_eq!(0, converter.convert("".as_bytes()));
+            assert_eq!(Ok(storage_format), storage::detect_format(output_path));
+            let table = storage::open_table(output_path).unwrap();
+            assert_eq!(Ok(header.clone()), table.read_header());
+            assert_eq!(Ok(word_hashes.clone()), table.read_all());
         }
+        assert_eq!(fs::read(text_path).unwrap(), fs::read(&back_path).unwrap());
+    }
 
-        let temp_file_path = temp_file_handler.temp_file_path.clone();
-        let cracker = HashCracker::new(temp_file_path, expected_hash.to_string());
+    #[test]
+    fn test_convert_not_overwrite() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let text_path = &temp_file_handler.temp_file_path;
+        test_utils::write_table(text_path, StorageFormat::Text, &["potato"]);
+        let output_path = format!("{}.bin", text_path);
+        fs::write(&output_path, "keep me").unwrap();
+        let converter = TableConverter::new(
+            text_path.to_string(),
+            output_path.clone(),
+            ConvertFormat::Storage(StorageFormat::Binary),
+        );
+        assert_eq!(0, converter.convert("n\n".as_bytes()));
+        assert_eq!("keep me", fs::read_to_string(&output_path).unwrap());
+    }
 
-        let return_code = cracker.run();
-        assert_eq!(return_code, 0);
+    #[test]
+    fn test_convert_rainbowcrack() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let text_path = &temp_file_handler.temp_file_path;
+        let word_hashes =
+            test_utils::write_table(text_path, StorageFormat::Text, &["potato", "rice"]);
+        let rt_dir = format!("{}.rt", text_path);
+        let converter = TableConverter::new(
+            text_path.to_string(),
+            rt_dir.clone(),
+            ConvertFormat::RainbowCrack,
+        );
+        assert_eq!(0, converter.convert("".as_bytes()));
+        let rt_path = format!("{}/sha256_loweralpha#4-6_0_2x2_0.rt", rt_dir);
+        assert!(path::Path::new(&rt_path).exists());
 
-        // Test that return code 0 is returned even when hash is uncracked
-        // Word is "absent"
-        let absent_hash =
-            "5ad38304b535c2987dbd24657c1a11b884984ff600d9f389deb0d4e634fee792".to_string();
-        let temp_file_path = temp_file_handler.temp_file_path.clone();
-        let cracker = HashCracker::new(temp_file_path, absent_hash);
-        let return_code = cracker.run();
-        assert_eq!(return_code, 0);
+        let back_path = format!("{}.txt", text_path);
+        let converter = TableConverter::new(
+            rt_path,
+            back_path.clone(),
+            ConvertFormat::Storage(StorageFormat::Text),
+        );
+        assert_eq!(0, converter.convert("".as_bytes()));
+        let mut converted = storage::open_table(&back_path).unwrap().read_all().unwrap();
+        converted.sort_by(|a, b| a.word.cmp(&b.word));
+        assert_eq!(word_hashes, converted);
+        fs::remove_dir_all(&rt_dir).unwrap();
+    }
+
+    #[test]
+    fn test_convert_unsupported() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_path = format!("{}.bin", temp_file_handler.temp_file_path);
+        let rtc_path = format!("{}.rtc", temp_file_handler.temp_file_path);
+        fs::write(&rtc_path, [0u8; 16]).unwrap();
+        for input_path in [rtc_path, String::from("/abc/defghi/jkl.qwerty")] {
+            let converter = TableConverter::new(
+                input_path,
+                output_path.clone(),
+                ConvertFormat::Storage(StorageFormat::Binary),
+            );
+            assert_eq!(
+                reader::FILE_OPERATION_ERROR,
+                converter.convert("".as_bytes())
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod benchmarker_tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        // The slow schemes take a while in debug builds, so only one of them
+        let operator = Benchmarker {
+            hashers: vec![
+                BenchmarkHasher::Digest(hasher::Algorithm::Md5),
+                BenchmarkHasher::Ntlm,
+                BenchmarkHasher::Slow(hasher::slow::SlowScheme::Pbkdf2Sha256),
+            ],
+            threads: 2,
+            duration: Duration::from_millis(5),
+            table_entries: 50,
+            ..Benchmarker::default()
+        };
+        assert_eq!(0, operator.run());
+    }
+}
+
+#[cfg(test)]
+mod table_planner_tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut operator = TablePlanner::new(String::from("numeric"), 1, 7, 0.99);
+        operator.chain_length = 1000;
+        operator.table_count = Some(4);
+        let parameters = plan::ChainParameters {
+            chain_length: 1000,
+            chain_count: 5000,
+            table_count: 4,
+        };
+        let plan = Plan::new(11_111_110, parameters);
+        let stats: HashMap<String, StatValue> =
+            operator.stats(&plan, 1_000_000).into_iter().collect();
+        assert_eq!(Some(&StatValue::Count(11_111_110)), stats.get("keyspace"));
+        assert_eq!(Some(&StatValue::Count(320_000)), stats.get("disk_size"));
+        assert_eq!(
+            Some(&StatValue::Text(String::from("20s"))),
+            stats.get("precomputation_time")
+        );
+        assert_eq!(
+            Some(&StatValue::Text(String::from("2s"))),
+            stats.get("lookup_time")
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let mut operator = TablePlanner::new(String::from("numeric"), 1, 7, 0.99);
+        operator.hash_rate = Some(1_000_000);
+        assert_eq!(0, operator.run());
+        // Too few tables for the success probability
+        operator.table_count = Some(1);
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, operator.run());
+        let operator = TablePlanner::new(String::from("emoji"), 1, 7, 0.99);
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, operator.run());
+    }
+}
+
+#[cfg(test)]
+mod chain_table_generator_tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn generator(output_dir: &str, mode: ChainMode) -> ChainTableGenerator {
+        let mut operator = ChainTableGenerator::new(
+            String::from(output_dir),
+            String::from("md5"),
+            String::from("numeric"),
+            1,
+            3,
+            300,
+        );
+        operator.mode = mode;
+        operator
+    }
+
+    #[test]
+    fn test_generate_and_crack() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.chains", temp_file_handler.temp_file_path);
+        let mode = ChainMode::DistinguishedPoint {
+            mask: 0x7,
+            max_chain_length: 64,
+        };
+        assert_eq!(0, generator(&output_dir, mode).run());
+        let table_path = fs::read_dir(&output_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path()
+            .to_string_lossy()
+            .into_owned();
+        assert!(table_path.ends_with(".dpt"));
+        let hash = hasher::encode_hex(&hasher::Algorithm::Md5.digest(b"0"));
+        assert_eq!(0, ChainCracker::new(table_path.clone(), hash).run());
+
+        // The same table is only written again once overwriting it is confirmed
+        fs::write(&table_path, "not chains").unwrap();
+        assert_eq!(0, generator(&output_dir, mode).generate(&b"n\n"[..]));
+        assert_eq!("not chains", fs::read_to_string(&table_path).unwrap());
+        assert_eq!(
+            INPUT_READ_ERROR,
+            generator(&output_dir, mode).generate(&[0xff_u8, 0xfe][..])
+        );
+        assert_eq!(0, generator(&output_dir, mode).generate(&b"y\n"[..]));
+        assert!(ChainTable::open(&table_path).is_ok());
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_invalid() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.chains", temp_file_handler.temp_file_path);
+        let mut operator = generator(&output_dir, ChainMode::Rainbow { chain_length: 10 });
+        operator.algorithm = String::from("sha512");
+        assert_eq!(CRACK_HASH_RUNTIME_ERROR_EXIT_CODE, operator.run());
+        assert_eq!(
+            reader::FILE_OPERATION_ERROR,
+            ChainCracker::new(temp_file_handler.temp_file_path.clone(), String::from("00")).run()
+        );
     }
 }
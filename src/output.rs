@@ -0,0 +1,496 @@
+use crate::progress::{Progress, ProgressMode};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::str::FromStr;
+
+// Column order for CSV output. Fields a record does not have are left empty
+const CSV_COLUMNS: [&str; 10] = [
+    "event",
+    "hash",
+    "account",
+    "plaintext",
+    "algorithm",
+    "count",
+    "path",
+    "position",
+    "stat",
+    "detail",
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OutputFormat {
+    // Human readable prose, the default
+    Text,
+    // A single JSON array of every record, printed once the command is done
+    Json,
+    // One JSON object per line, printed as soon as it is known
+    Jsonl,
+    // A header row followed by one row per record
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "Invalid output format, expected text, json, jsonl or csv, got: {}",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Event {
+    Cracked,
+    NotCracked,
+    // A slow hash was given up on after exceeding the time budget
+    Exhausted,
+    // Words hashed into a table, or whatever else `detail` names, e.g. chains
+    Generated,
+    Identified,
+    Unidentified,
+    // A `hash:plaintext` pair printed by `show`
+    Shown,
+    // A cracked password used by several accounts, listed in `detail`
+    SharedPassword,
+    // A table that was checked by `verify-table`, with what was found in `detail`
+    Verified,
+    // Something wrong with a table, at `position` when it is about a single entry
+    TableProblem,
+    // One `table-info` value, named by `stat`. Numbers are in `count`, text in `detail`
+    TableStat,
+    // A table written by `convert-table` to `path`, in the format named by `detail`
+    Converted,
+    // Hashes per second of `algorithm` measured by `benchmark`, on the threads in `detail`
+    HashRate,
+    // Nanoseconds a lookup took in the storage format named by `detail`
+    LookupLatency,
+    // One `plan` value, named by `stat`. Numbers are in `count`, text in `detail`
+    PlanStat,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Event::Cracked => "cracked",
+            Event::NotCracked => "not_cracked",
+            Event::Exhausted => "exhausted",
+            Event::Generated => "generated",
+            Event::Identified => "identified",
+            Event::Unidentified => "unidentified",
+            Event::Shown => "shown",
+            Event::SharedPassword => "shared_password",
+            Event::Verified => "verified",
+            Event::TableProblem => "table_problem",
+            Event::TableStat => "table_stat",
+            Event::Converted => "converted",
+            Event::HashRate => "hash_rate",
+            Event::LookupLatency => "lookup_latency",
+            Event::PlanStat => "plan_stat",
+            Event::Warning => "warning",
+            Event::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Record {
+    pub event: Event,
+    pub hash: Option<String>,
+    pub account: Option<String>,
+    pub plaintext: Option<String>,
+    pub algorithm: Option<String>,
+    pub count: Option<usize>,
+    pub path: Option<String>,
+    // Where in a file the record is about, e.g. `line 3`
+    pub position: Option<String>,
+    // The name of a statistic, e.g. `entries`
+    pub stat: Option<String>,
+    // Free form context, e.g. an error message or why a hash was not cracked
+    pub detail: Option<String>,
+    // A result of a command given a single hash, which text output words without the hash
+    pub single_hash: bool,
+}
+
+impl Record {
+    pub fn new(event: Event) -> Record {
+        Record {
+            event,
+            hash: None,
+            account: None,
+            plaintext: None,
+            algorithm: None,
+            count: None,
+            path: None,
+            position: None,
+            stat: None,
+            detail: None,
+            single_hash: false,
+        }
+    }
+
+    fn fields(&self) -> [Option<String>; 10] {
+        // In the same order as CSV_COLUMNS
+        [
+            Some(self.event.to_string()),
+            self.hash.clone(),
+            self.account.clone(),
+            self.plaintext.clone(),
+            self.algorithm.clone(),
+            self.count.map(|count| count.to_string()),
+            self.path.clone(),
+            self.position.clone(),
+            self.stat.clone(),
+            self.detail.clone(),
+        ]
+    }
+
+    fn to_text(&self) -> String {
+        let hash = self.hash.as_deref().unwrap_or_default();
+        let plaintext = self.plaintext.as_deref().unwrap_or_default();
+        let detail = self.detail.as_deref().unwrap_or_default();
+        let text = match (self.event, &self.account) {
+            (Event::Cracked, _) if self.single_hash => {
+                format!("Hash Cracked! The word is: {}", plaintext)
+            }
+            (Event::NotCracked, _) if self.single_hash => {
+                String::from("Sorry, hash not found in the rainbow table!")
+            }
+            (Event::Cracked, Some(account)) => {
+                format!("Account {} recovered! The word is: {}", account, plaintext)
+            }
+            (Event::Cracked, None) => {
+                format!("Hash Cracked! The word for {} is: {}", hash, plaintext)
+            }
+            (Event::NotCracked, Some(account)) => {
+                format!("Sorry, account {} could not be recovered!", account)
+            }
+            (Event::NotCracked, None) => format!("Sorry, hash {} could not be cracked!", hash),
+            (Event::Exhausted, account) => {
+                let target = match account {
+                    Some(account) => format!("account {}", account),
+                    None => format!("hash {}", hash),
+                };
+                format!(
+                    "Sorry, {} could not be cracked within the time budget of {}!",
+                    target, detail
+                )
+            }
+            (Event::Generated, _) => format!(
+                "Generated {} {}",
+                self.count.unwrap_or(0),
+                self.detail.as_deref().unwrap_or("words")
+            ),
+            (Event::Identified, _) => format!(
+                "  {} ({})",
+                self.algorithm.as_deref().unwrap_or_default(),
+                detail
+            ),
+            (Event::Unidentified, _) => format!("Could not identify hash {}", hash),
+            (Event::Shown, _) => format!("{}:{}", hash, plaintext),
+            (Event::SharedPassword, _) => format!(
+                "Password {} is shared by {} accounts: {}",
+                plaintext,
+                self.count.unwrap_or(0),
+                detail
+            ),
+            (Event::Verified, _) => format!(
+                "Verified {} entries in {}: {}",
+                self.count.unwrap_or(0),
+                self.path.as_deref().unwrap_or_default(),
+                detail
+            ),
+            (Event::TableProblem, _) => {
+                let path = self.path.as_deref().unwrap_or_default();
+                match &self.position {
+                    Some(position) => format!("{}, {}: {}", path, position, detail),
+                    None => format!("{}: {}", path, detail),
+                }
+            }
+            (Event::TableStat | Event::PlanStat, _) => {
+                let value = match self.count {
+                    Some(count) => count.to_string(),
+                    None => String::from(detail),
+                };
+                format!("{}: {}", self.stat.as_deref().unwrap_or_default(), value)
+            }
+            (Event::Converted, _) => format!(
+                "Converted {} entries to {} ({})",
+                self.count.unwrap_or(0),
+                self.path.as_deref().unwrap_or_default(),
+                detail
+            ),
+            (Event::HashRate, _) => format!(
+                "{}: {} hashes/s on {}",
+                self.algorithm.as_deref().unwrap_or_default(),
+                self.count.unwrap_or(0),
+                detail
+            ),
+            (Event::LookupLatency, _) => {
+                format!("{} lookup: {} ns", detail, self.count.unwrap_or(0))
+            }
+            (Event::Warning, _) => format!("Warning: {}", detail),
+            (Event::Error, _) => String::from(detail),
+        };
+        // Extra context for results goes on its own line, as it did before
+        match (self.event, &self.detail) {
+            (Event::NotCracked, Some(detail)) => format!("{}\n{}", text, detail),
+            _ => text,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields = CSV_COLUMNS
+            .iter()
+            .zip(self.fields())
+            .filter_map(|(column, value)| {
+                let value = value?;
+                // Counts are the only numeric field
+                match *column {
+                    "count" => Some(format!("\"{}\":{}", column, value)),
+                    _ => Some(format!("\"{}\":{}", column, escape_json(&value))),
+                }
+            })
+            .collect::<Vec<String>>();
+        format!("{{{}}}", fields.join(","))
+    }
+
+    fn to_csv(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|value| escape_csv(value.as_deref().unwrap_or_default()))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+pub fn escape_json(value: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn escape_csv(value: &str) -> String {
+    if !value.contains([',', '"', '\n', '\r']) {
+        return String::from(value);
+    }
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+pub struct Reporter {
+    format: OutputFormat,
+    // Leave out progress and informational messages, keeping only the results
+    pub quiet: bool,
+    csv_header_written: Cell<bool>,
+    // JSON output is a single array, so records are held until `finish`
+    json_records: RefCell<Vec<String>>,
+}
+
+impl Default for Reporter {
+    fn default() -> Reporter {
+        Reporter::new(OutputFormat::Text)
+    }
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat) -> Reporter {
+        Reporter {
+            format,
+            quiet: false,
+            csv_header_written: Cell::new(false),
+            json_records: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn record(&self, record: Record) {
+        match self.format {
+            // Warnings and errors stay off stdout in text mode, as before
+            OutputFormat::Text => match record.event {
+                Event::Warning | Event::Error => eprintln!("{}", record.to_text()),
+                _ => println!("{}", record.to_text()),
+            },
+            OutputFormat::Json => self.json_records.borrow_mut().push(record.to_json()),
+            OutputFormat::Jsonl => println!("{}", record.to_json()),
+            OutputFormat::Csv => {
+                if !self.csv_header_written.replace(true) {
+                    println!("{}", CSV_COLUMNS.join(","));
+                }
+                println!("{}", record.to_csv());
+            }
+        }
+    }
+
+    pub fn info(&self, message: &str) {
+        // Progress chatter only makes sense for humans, and would break the other formats
+        if self.format == OutputFormat::Text && !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    pub fn progress(&self, label: &str, unit: &str, total: Option<u64>) -> Progress {
+        // Shown on stderr whatever the output format, so it never mixes with the records
+        Progress::new(label, unit, total, ProgressMode::detect(self.quiet))
+    }
+
+    pub fn warning(&self, message: &str) {
+        self.record(Record {
+            detail: Some(String::from(message)),
+            ..Record::new(Event::Warning)
+        });
+    }
+
+    pub fn error(&self, message: &str) {
+        self.record(Record {
+            detail: Some(String::from(message)),
+            ..Record::new(Event::Error)
+        });
+    }
+
+    pub fn crack_result(
+        &self,
+        hash: &str,
+        account: Option<&str>,
+        crack_result: &Result<String, ()>,
+    ) {
+        let event = match crack_result {
+            Ok(_) => Event::Cracked,
+            Err(_) => Event::NotCracked,
+        };
+        self.record(Record {
+            hash: Some(String::from(hash)),
+            account: account.map(String::from),
+            plaintext: crack_result.clone().ok(),
+            ..Record::new(event)
+        });
+    }
+
+    pub fn single_crack_result(&self, hash: &str, crack_result: &Result<String, ()>) {
+        // Worded as crack-hash always has, as there is no other hash it could be about
+        self.record(Record {
+            hash: Some(String::from(hash)),
+            plaintext: crack_result.clone().ok(),
+            single_hash: true,
+            ..Record::new(match crack_result {
+                Ok(_) => Event::Cracked,
+                Err(_) => Event::NotCracked,
+            })
+        });
+    }
+
+    pub fn finish(&self) {
+        if self.format != OutputFormat::Json {
+            return;
+        }
+        let records = self.json_records.borrow();
+        if records.is_empty() {
+            println!("[]");
+            return;
+        }
+        println!("[\n  {}\n]", records.join(",\n  "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cracked_record() -> Record {
+        Record {
+            hash: Some("abc".to_string()),
+            plaintext: Some("pass,\"word\"".to_string()),
+            ..Record::new(Event::Cracked)
+        }
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!(Ok(OutputFormat::Jsonl), "JSONL".parse::<OutputFormat>());
+        assert_eq!(Ok(OutputFormat::Csv), "csv".parse::<OutputFormat>());
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            "Hash Cracked! The word for abc is: pass,\"word\"",
+            cracked_record().to_text()
+        );
+        let record = Record {
+            account: Some("root".to_string()),
+            ..cracked_record()
+        };
+        assert_eq!(
+            "Account root recovered! The word is: pass,\"word\"",
+            record.to_text()
+        );
+        let record = Record {
+            hash: Some("abc".to_string()),
+            detail: Some("Neither half was cracked".to_string()),
+            ..Record::new(Event::NotCracked)
+        };
+        assert_eq!(
+            "Sorry, hash abc could not be cracked!\nNeither half was cracked",
+            record.to_text()
+        );
+        let record = Record {
+            single_hash: true,
+            ..cracked_record()
+        };
+        assert_eq!("Hash Cracked! The word is: pass,\"word\"", record.to_text());
+        let record = Record {
+            algorithm: Some("sha256".to_string()),
+            count: Some(1500000),
+            detail: Some("8 threads".to_string()),
+            ..Record::new(Event::HashRate)
+        };
+        assert_eq!("sha256: 1500000 hashes/s on 8 threads", record.to_text());
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            r#"{"event":"cracked","hash":"abc","plaintext":"pass,\"word\""}"#,
+            cracked_record().to_json()
+        );
+        let record = Record {
+            count: Some(3),
+            path: Some("table\ttxt".to_string()),
+            ..Record::new(Event::Generated)
+        };
+        assert_eq!(
+            r#"{"event":"generated","count":3,"path":"table\ttxt"}"#,
+            record.to_json()
+        );
+        assert_eq!("\"\\u0001\"", escape_json("\u{1}"));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        assert_eq!(
+            "cracked,abc,,\"pass,\"\"word\"\"\",,,,,,",
+            cracked_record().to_csv()
+        );
+        assert_eq!(CSV_COLUMNS.len(), cracked_record().fields().len());
+    }
+}
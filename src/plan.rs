@@ -0,0 +1,223 @@
+use crate::rainbowcrack;
+
+pub const DEFAULT_CHAIN_LENGTH: u64 = 3800;
+
+pub fn parse_success_probability(success_probability: &str) -> Result<f64, String> {
+    // Either a fraction, e.g. 0.99, or a percentage, e.g. 99%
+    let parsed = match success_probability.strip_suffix('%') {
+        Some(percentage) => percentage
+            .parse::<f64>()
+            .map(|percentage| percentage / 100.0),
+        None => success_probability.parse::<f64>(),
+    };
+    match parsed {
+        Ok(success_probability) if success_probability > 0.0 && success_probability < 1.0 => {
+            Ok(success_probability)
+        }
+        _ => Err(format!(
+            "Invalid success probability, expected a number between 0 and 1 or a percentage, got: {}",
+            success_probability
+        )),
+    }
+}
+
+fn next_column_chains(keyspace: f64, chains: f64) -> f64 {
+    // Distinct chains left in the next column, once the ones that collided have merged
+    -keyspace * (-chains / keyspace).exp_m1()
+}
+
+pub fn table_success_probability(keyspace: u64, chain_length: u64, chain_count: u64) -> f64 {
+    /*
+        Oechslin: a table of m chains of length t finds a plaintext with probability
+        1 - (1 - m_1/N)(1 - m_2/N)...(1 - m_t/N), where m_1 = m and
+        m_(i+1) = N(1 - e^(-m_i/N)) is how many distinct chains reach column i + 1
+    */
+    let keyspace = keyspace as f64;
+    let mut chains = chain_count as f64;
+    let mut log_miss = 0.0;
+    for _ in 0..chain_length {
+        log_miss += (-(chains / keyspace).min(1.0)).ln_1p();
+        chains = next_column_chains(keyspace, chains);
+    }
+    -log_miss.exp_m1()
+}
+
+pub fn success_probability(keyspace: u64, parameters: &ChainParameters) -> f64 {
+    // Tables use different reduction functions, so each misses independently
+    let table_success_probability =
+        table_success_probability(keyspace, parameters.chain_length, parameters.chain_count);
+    1.0 - (1.0 - table_success_probability).powf(parameters.table_count as f64)
+}
+
+pub fn max_chain_count(keyspace: u64, chain_length: u64) -> u64 {
+    // Oechslin's m_max = 2N / (t + 2), past which further chains mostly merge into existing ones
+    ((2.0 * keyspace as f64 / (chain_length as f64 + 2.0)) as u64).max(1)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct ChainParameters {
+    pub chain_length: u64,
+    // Chains in each table
+    pub chain_count: u64,
+    pub table_count: u64,
+}
+
+pub fn choose_parameters(
+    keyspace: u64,
+    target: f64,
+    chain_length: u64,
+    table_count: Option<u64>,
+) -> Result<ChainParameters, String> {
+    /*
+        The fewest chains per table that reach the target success probability.
+        Without a table count, as few tables as can reach it with at most
+        m_max chains each
+    */
+    if chain_length == 0 {
+        return Err(String::from("Chain length must be above 0"));
+    }
+    let max_chain_count = max_chain_count(keyspace, chain_length);
+    let max_table_success_probability =
+        table_success_probability(keyspace, chain_length, max_chain_count);
+    let table_count = match table_count {
+        Some(0) => return Err(String::from("Table count must be above 0")),
+        Some(table_count) => table_count,
+        None if max_table_success_probability >= 1.0 => 1,
+        None => (((1.0 - target).ln() / (1.0 - max_table_success_probability).ln()).ceil() as u64)
+            .max(1),
+    };
+    let parameters = |chain_count: u64| ChainParameters {
+        chain_length,
+        chain_count,
+        table_count,
+    };
+    if success_probability(keyspace, &parameters(max_chain_count)) < target {
+        return Err(format!(
+            "Chain length {} cannot reach a success probability of {} with {} table(s), use more tables",
+            chain_length, target, table_count
+        ));
+    }
+    // The success probability only grows with the chain count
+    let (mut low, mut high) = (1, max_chain_count);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if success_probability(keyspace, &parameters(middle)) >= target {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    Ok(parameters(low))
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Plan {
+    pub keyspace: u64,
+    pub parameters: ChainParameters,
+    pub success_probability: f64,
+    pub table_success_probability: f64,
+    // Chains expected to be left in each table once chains with the same end are merged
+    pub distinct_chains: u64,
+    // In bytes, as RainbowCrack .rt tables
+    pub disk_size: u64,
+    // Hash and reduce steps to generate every table
+    pub precomputation_hashes: u64,
+    // Hash and reduce steps to look up a hash that is in none of the tables, not counting false alarms
+    pub lookup_hashes: u64,
+    // Chain end searches, one per column of each table
+    pub table_searches: u64,
+}
+
+impl Plan {
+    pub fn new(keyspace: u64, parameters: ChainParameters) -> Plan {
+        let ChainParameters {
+            chain_length,
+            chain_count,
+            table_count,
+        } = parameters;
+        let mut distinct_chains = chain_count as f64;
+        for _ in 1..chain_length {
+            distinct_chains = next_column_chains(keyspace as f64, distinct_chains);
+        }
+        Plan {
+            keyspace,
+            parameters,
+            success_probability: success_probability(keyspace, &parameters),
+            table_success_probability: table_success_probability(
+                keyspace,
+                chain_length,
+                chain_count,
+            ),
+            distinct_chains: distinct_chains.round() as u64,
+            disk_size: (rainbowcrack::CHAIN_LENGTH_BYTES as u64)
+                .saturating_mul(chain_count)
+                .saturating_mul(table_count),
+            precomputation_hashes: chain_count
+                .saturating_mul(chain_length)
+                .saturating_mul(table_count),
+            // Starting from every column, so t + (t - 1) + ... + 1 per table
+            lookup_hashes: (chain_length.saturating_mul(chain_length + 1) / 2)
+                .saturating_mul(table_count),
+            table_searches: chain_length.saturating_mul(table_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_success_probability() {
+        assert_eq!(Ok(0.99), parse_success_probability("0.99"));
+        assert_eq!(Ok(0.9), parse_success_probability("90%"));
+        for success_probability in ["0", "1", "100%", "-0.5", "abc"] {
+            assert!(parse_success_probability(success_probability).is_err());
+        }
+    }
+
+    #[test]
+    fn test_table_success_probability() {
+        // A single column of chains is plain sampling with replacement
+        let probability = table_success_probability(1000, 1, 500);
+        assert!((probability - 0.5).abs() < 1e-9);
+        // Because of merges, a table of m_max chains tops out at about 75%, not 100%
+        let keyspace = 1_000_000;
+        let probability =
+            table_success_probability(keyspace, 1000, max_chain_count(keyspace, 1000));
+        assert!((probability - 0.75).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_choose_parameters() {
+        let keyspace = rainbowcrack::keyspace("loweralpha", 1, 6).unwrap();
+        let parameters = choose_parameters(keyspace, 0.99, 2000, None).unwrap();
+        assert_eq!(2000, parameters.chain_length);
+        assert!(success_probability(keyspace, &parameters) >= 0.99);
+        // One chain fewer falls short
+        let fewer = ChainParameters {
+            chain_count: parameters.chain_count - 1,
+            ..parameters
+        };
+        assert!(success_probability(keyspace, &fewer) < 0.99);
+        // A single table cannot get there, however many chains it has
+        assert!(choose_parameters(keyspace, 0.99, 2000, Some(1)).is_err());
+        assert!(choose_parameters(keyspace, 0.99, 0, None).is_err());
+    }
+
+    #[test]
+    fn test_plan() {
+        let parameters = ChainParameters {
+            chain_length: 100,
+            chain_count: 1000,
+            table_count: 4,
+        };
+        let plan = Plan::new(1_000_000, parameters);
+        assert_eq!(64000, plan.disk_size);
+        assert_eq!(400_000, plan.precomputation_hashes);
+        assert_eq!(20200, plan.lookup_hashes);
+        assert_eq!(400, plan.table_searches);
+        assert!(plan.distinct_chains <= 1000 && plan.distinct_chains > 900);
+        assert!(plan.success_probability > plan.table_success_probability);
+    }
+}
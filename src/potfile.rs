@@ -0,0 +1,204 @@
+use crate::hasher::{decode_hex, encode_hex};
+use crate::reader;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path;
+
+pub const DEFAULT_POTFILE_PATH: &str = "rust-rainbowtable.pot";
+const POTFILE_DELIMITER: char = ':';
+// Plaintexts that would break the line format are written hex encoded, like hashcat does
+const HEX_PLAINTEXT_PREFIX: &str = "$HEX[";
+const HEX_PLAINTEXT_SUFFIX: &str = "]";
+
+fn encode_plaintext(plaintext: &str) -> String {
+    let needs_encoding = plaintext.contains(POTFILE_DELIMITER)
+        || plaintext.starts_with(HEX_PLAINTEXT_PREFIX)
+        || plaintext.chars().any(|c| c.is_control());
+    if !needs_encoding {
+        return String::from(plaintext);
+    }
+    format!(
+        "{}{}{}",
+        HEX_PLAINTEXT_PREFIX,
+        encode_hex(plaintext.as_bytes()),
+        HEX_PLAINTEXT_SUFFIX
+    )
+}
+
+fn decode_plaintext(plaintext: &str) -> Result<String, String> {
+    let hex = match plaintext
+        .strip_prefix(HEX_PLAINTEXT_PREFIX)
+        .and_then(|plaintext| plaintext.strip_suffix(HEX_PLAINTEXT_SUFFIX))
+    {
+        Some(hex) => hex,
+        None => return Ok(String::from(plaintext)),
+    };
+    String::from_utf8(decode_hex(hex)?)
+        .map_err(|_| format!("Plaintext is not valid UTF-8, got: {}", plaintext))
+}
+
+pub fn parse_potfile_line(line: &str) -> Result<(String, String), String> {
+    /*
+        Parse a `hash:plaintext` line. Hashes can contain the delimiter themselves
+        (e.g. `hash:salt` or Oracle's `S:...`), while plaintexts containing it are
+        hex encoded, so the line is split on the last delimiter
+    */
+    match line.rsplit_once(POTFILE_DELIMITER) {
+        Some((hash, plaintext)) if !hash.is_empty() => {
+            Ok((String::from(hash), decode_plaintext(plaintext)?))
+        }
+        _ => Err(format!(
+            "Invalid potfile line, expected hash{}plaintext, got: {}",
+            POTFILE_DELIMITER, line
+        )),
+    }
+}
+
+pub struct Potfile {
+    path: String,
+    // Hash -> plaintext
+    entries: HashMap<String, String>,
+    // Lines that could not be parsed and were left out, and why
+    pub invalid_lines: Vec<String>,
+}
+
+impl Potfile {
+    pub fn open(path: &str) -> Result<Potfile, String> {
+        // A potfile that does not exist yet is simply empty, and gets created on the first append
+        let mut entries: HashMap<String, String> = HashMap::new();
+        let mut invalid_lines: Vec<String> = Vec::new();
+        if path::Path::new(path).exists() {
+            for (line_number, line) in reader::read_words(path)?.iter().enumerate() {
+                if line.is_empty() {
+                    continue;
+                }
+                // One bad line should not cost every other cracked hash
+                match parse_potfile_line(line) {
+                    Ok((hash, plaintext)) => {
+                        entries.insert(hash, plaintext);
+                    }
+                    Err(e) => invalid_lines.push(format!(
+                        "Skipping line {} of potfile {}: {}",
+                        line_number + 1,
+                        path,
+                        e
+                    )),
+                }
+            }
+        }
+        Ok(Potfile {
+            path: String::from(path),
+            entries,
+            invalid_lines,
+        })
+    }
+
+    pub fn lookup(&self, hash: &str) -> Option<&str> {
+        self.entries.get(hash).map(String::as_str)
+    }
+
+    pub fn append(&mut self, hash: &str, plaintext: &str) -> Result<(), String> {
+        // Hashes that are already in the potfile are not written again
+        if self.entries.contains_key(hash) {
+            return Ok(());
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Unable to open potfile {} for writing: {}", self.path, e))?;
+        writeln!(
+            file,
+            "{}{}{}",
+            hash,
+            POTFILE_DELIMITER,
+            encode_plaintext(plaintext)
+        )
+        .map_err(|e| format!("Error while writing to potfile {}: {}", self.path, e))?;
+        self.entries
+            .insert(String::from(hash), String::from(plaintext));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use std::io::{BufWriter, Write};
+
+    #[test]
+    fn test_parse_potfile_line() {
+        let cases = [
+            (
+                "5f4dcc3b5aa765d61d8327deb882cf99:password",
+                "5f4dcc3b5aa765d61d8327deb882cf99",
+                "password",
+            ),
+            ("S:8E4B6DE7:password", "S:8E4B6DE7", "password"),
+            ("abc:$HEX[613a62]", "abc", "a:b"),
+            ("abc:", "abc", ""),
+        ];
+        for (line, expected_hash, expected_plaintext) in cases {
+            assert_eq!(
+                Ok((expected_hash.to_string(), expected_plaintext.to_string())),
+                parse_potfile_line(line)
+            );
+        }
+        assert!(parse_potfile_line("abc").is_err());
+        assert!(parse_potfile_line(":password").is_err());
+        assert!(parse_potfile_line("abc:$HEX[zz]").is_err());
+    }
+
+    #[test]
+    fn test_append_and_lookup() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(file);
+        writer.write_all(b"aaa:first\n").unwrap();
+        std::mem::drop(writer);
+
+        let mut potfile = Potfile::open(&temp_file_handler.temp_file_path).unwrap();
+        assert_eq!(Some("first"), potfile.lookup("aaa"));
+        assert_eq!(None, potfile.lookup("bbb"));
+
+        potfile.append("bbb", "pass:word").unwrap();
+        potfile.append("aaa", "ignored").unwrap();
+        assert_eq!(Some("pass:word"), potfile.lookup("bbb"));
+
+        // Appended entries survive a reopen, and duplicates are not written twice
+        let potfile = Potfile::open(&temp_file_handler.temp_file_path).unwrap();
+        assert_eq!(Some("first"), potfile.lookup("aaa"));
+        assert_eq!(Some("pass:word"), potfile.lookup("bbb"));
+        assert_eq!(
+            Ok(vec![
+                "aaa:first".to_string(),
+                "bbb:$HEX[706173733a776f7264]".to_string()
+            ]),
+            reader::read_words(&temp_file_handler.temp_file_path)
+        );
+    }
+
+    #[test]
+    fn test_open_invalid_lines() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        fs::write(
+            &temp_file_handler.temp_file_path,
+            "aaa:first\nno delimiter\nbbb:$HEX[zz]\nccc:third\n",
+        )
+        .unwrap();
+        let potfile = Potfile::open(&temp_file_handler.temp_file_path).unwrap();
+        assert_eq!(Some("first"), potfile.lookup("aaa"));
+        assert_eq!(Some("third"), potfile.lookup("ccc"));
+        assert_eq!(2, potfile.invalid_lines.len());
+        assert!(potfile.invalid_lines[0].starts_with("Skipping line 2 of potfile"));
+        assert!(potfile.invalid_lines[1].starts_with("Skipping line 3 of potfile"));
+    }
+
+    #[test]
+    fn test_open_missing_potfile() {
+        let potfile = Potfile::open("/abc/defghi/jkl.pot").unwrap();
+        assert_eq!(None, potfile.lookup("aaa"));
+    }
+}
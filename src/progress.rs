@@ -0,0 +1,214 @@
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+// How often the progress line is redrawn on a terminal
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(200);
+// How often a progress line is logged when stderr is not a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ProgressMode {
+    // Nothing is shown, for --quiet
+    Hidden,
+    // A single line on stderr, redrawn in place
+    Terminal,
+    // A line on stderr every LOG_INTERVAL, for logs and pipes
+    Log,
+}
+
+impl ProgressMode {
+    pub fn detect(quiet: bool) -> ProgressMode {
+        if quiet {
+            ProgressMode::Hidden
+        } else if io::stderr().is_terminal() {
+            ProgressMode::Terminal
+        } else {
+            ProgressMode::Log
+        }
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, 0) => format!("{}ms", duration.as_millis()),
+        (0, 0, seconds) => format!("{}s", seconds),
+        (0, minutes, seconds) => format!("{}m {:02}s", minutes, seconds),
+        (hours, minutes, seconds) => format!("{}h {:02}m {:02}s", hours, minutes, seconds),
+    }
+}
+
+/*
+    Words processed and hashes per second of a long running operation, along
+    with how far along it is and when it should be done when its total is known.
+    `processed` counts the words hashed, while `completed` counts towards `total`
+    in whatever unit the total is known in, e.g. bytes of the word file
+*/
+pub struct Progress {
+    label: String,
+    unit: String,
+    mode: ProgressMode,
+    total: Option<u64>,
+    processed: u64,
+    completed: u64,
+    // Where a resumed run started, so that its ETA only counts its own work
+    start_completed: u64,
+    started: Instant,
+    last_shown: Instant,
+    // Whether a line is drawn on the terminal that still needs to be ended
+    drawn: bool,
+}
+
+impl Progress {
+    pub fn new(label: &str, unit: &str, total: Option<u64>, mode: ProgressMode) -> Progress {
+        let now = Instant::now();
+        Progress {
+            label: String::from(label),
+            unit: String::from(unit),
+            mode,
+            total,
+            processed: 0,
+            completed: 0,
+            start_completed: 0,
+            started: now,
+            last_shown: now,
+            drawn: false,
+        }
+    }
+
+    pub fn resume_from(&mut self, completed: u64) {
+        self.completed = completed;
+        self.start_completed = completed;
+    }
+
+    pub fn add(&mut self, count: u64) {
+        self.advance(count, self.completed + count);
+    }
+
+    pub fn advance(&mut self, count: u64, completed: u64) {
+        self.processed += count;
+        self.completed = completed;
+        let interval = match self.mode {
+            ProgressMode::Hidden => return,
+            ProgressMode::Terminal => TERMINAL_INTERVAL,
+            ProgressMode::Log => LOG_INTERVAL,
+        };
+        let now = Instant::now();
+        if now.duration_since(self.last_shown) >= interval {
+            self.last_shown = now;
+            let line = self.line(now.duration_since(self.started), false);
+            self.show(&line);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let line = self.line(self.started.elapsed(), true);
+        self.show(&line);
+        self.end_line();
+    }
+
+    fn show(&mut self, line: &str) {
+        match self.mode {
+            ProgressMode::Hidden => (),
+            ProgressMode::Terminal => {
+                // Clear whatever is left of a longer line drawn before
+                eprint!("\r{}\x1b[K", line);
+                self.drawn = true;
+            }
+            ProgressMode::Log => eprintln!("{}", line),
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.drawn {
+            eprintln!();
+            self.drawn = false;
+        }
+    }
+
+    fn line(&self, elapsed: Duration, finished: bool) -> String {
+        let seconds = elapsed.as_secs_f64();
+        let rate = match seconds > 0.0 {
+            true => self.processed as f64 / seconds,
+            false => 0.0,
+        };
+        let mut line = format!(
+            "{}: {} {} at {:.0} hashes/s",
+            self.label, self.processed, self.unit, rate
+        );
+        if finished {
+            line.push_str(&format!(", done in {}", format_duration(elapsed)));
+            return line;
+        }
+        if let Some(total) = self.total.filter(|total| *total > 0) {
+            let completed = self.completed.min(total);
+            line.push_str(&format!(
+                ", {:.1}%",
+                completed as f64 * 100.0 / total as f64
+            ));
+            let completed_rate = (completed - self.start_completed.min(completed)) as f64 / seconds;
+            if seconds > 0.0 && completed_rate > 0.0 {
+                let remaining = (total - completed) as f64 / completed_rate;
+                line.push_str(&format!(
+                    ", ETA {}",
+                    format_duration(Duration::from_secs_f64(remaining))
+                ));
+            }
+        }
+        line
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        // An error printed after an unfinished progress line starts on a line of its own
+        self.end_line();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!("400ms", format_duration(Duration::from_millis(400)));
+        assert_eq!("59s", format_duration(Duration::from_secs(59)));
+        assert_eq!("2m 03s", format_duration(Duration::from_secs(123)));
+        assert_eq!("1h 00m 05s", format_duration(Duration::from_secs(3605)));
+    }
+
+    #[test]
+    fn test_line() {
+        let mut progress = Progress::new("Generating", "words", Some(1000), ProgressMode::Hidden);
+        progress.add(250);
+        assert_eq!(
+            "Generating: 250 words at 25 hashes/s, 25.0%, ETA 30s",
+            progress.line(Duration::from_secs(10), false)
+        );
+        assert_eq!(
+            "Generating: 250 words at 25 hashes/s, done in 10s",
+            progress.line(Duration::from_secs(10), true)
+        );
+
+        // Without a total, only the words and rate are known
+        let mut progress = Progress::new("Attacking", "candidates", None, ProgressMode::Hidden);
+        progress.add(90);
+        assert_eq!(
+            "Attacking: 90 candidates at 30 hashes/s",
+            progress.line(Duration::from_secs(3), false)
+        );
+    }
+
+    #[test]
+    fn test_resume_from() {
+        // Only the work done since resuming counts towards the rate and ETA
+        let mut progress = Progress::new("Generating", "words", Some(4096), ProgressMode::Hidden);
+        progress.resume_from(2048);
+        progress.advance(100, 3072);
+        assert_eq!(
+            "Generating: 100 words at 10 hashes/s, 75.0%, ETA 10s",
+            progress.line(Duration::from_secs(10), false)
+        );
+    }
+}
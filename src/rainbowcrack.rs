@@ -0,0 +1,540 @@
+use crate::hasher::expression::Pipeline;
+use crate::hasher::{self, Algorithm, WordHash};
+use crate::header::{TableHashing, TableHeader};
+use std::fmt;
+use std::fs;
+use std::path;
+use std::str::FromStr;
+
+pub const RT_EXTENSION: &str = "rt";
+pub const RTC_EXTENSION: &str = "rtc";
+// Each chain is its start and end plaintext index, as little-endian u64s
+pub const CHAIN_LENGTH_BYTES: usize = 16;
+// RainbowCrack spaces the reduction functions of different tables this far apart
+const TABLE_INDEX_REDUCE_OFFSET: u64 = 65536;
+/*
+    One hash per chain, so converting a table never loses words to merges.
+    RainbowCrack's chain length counts the start point, so this is a single reduction
+*/
+const EXPORT_CHAIN_LENGTH: u64 = 2;
+
+// The charsets from RainbowCrack's charset.txt, smallest first
+const CHARSETS: [(&str, &str); 10] = [
+    ("numeric", "0123456789"),
+    ("alpha", "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+    ("loweralpha", "abcdefghijklmnopqrstuvwxyz"),
+    ("alpha-numeric", "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"),
+    ("loweralpha-numeric", "abcdefghijklmnopqrstuvwxyz0123456789"),
+    (
+        "mixalpha",
+        "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    ),
+    (
+        "mixalpha-numeric",
+        "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789",
+    ),
+    (
+        "ascii-32-65-123-4",
+        " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`{|}~",
+    ),
+    (
+        "alpha-numeric-symbol32-space",
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!@#$%^&*()-_+=~`[]{}|\\:;\"'<>,.?/ ",
+    ),
+    (
+        "ascii-32-95",
+        " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~",
+    ),
+];
+
+fn charset_characters(name: &str) -> Result<&'static str, String> {
+    CHARSETS
+        .iter()
+        .find(|(charset_name, _)| *charset_name == name)
+        .map(|(_, characters)| *characters)
+        .ok_or_else(|| {
+            format!(
+                "Unknown RainbowCrack charset, expected one of {}, got: {}",
+                CHARSETS.map(|(charset_name, _)| charset_name).join(", "),
+                name
+            )
+        })
+}
+
+pub fn keyspace(charset: &str, min_length: usize, max_length: usize) -> Result<u64, String> {
+    // How many plaintexts of the charset and lengths there are, as RainbowCrack counts them
+    PlainSpace::new(charset, min_length, max_length).map(|plain_space| plain_space.total)
+}
+
+pub fn is_rainbowcrack_path(path: &str) -> bool {
+    let extension = path::Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    matches!(
+        extension.as_deref(),
+        Some(RT_EXTENSION) | Some(RTC_EXTENSION)
+    )
+}
+
+fn check_not_rtc(path: &str) -> Result<(), String> {
+    // The compressed layout is not documented, RainbowCrack ships converters for it
+    let is_rtc = path::Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(RTC_EXTENSION));
+    if is_rtc {
+        return Err(format!(
+            "RainbowCrack .rtc tables are not supported, convert {} with rtc2rt first, \
+            or convert to .rt and then run rt2rtc",
+            path
+        ));
+    }
+    Ok(())
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TableName {
+    pub algorithm: String,
+    pub charset: String,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub table_index: u64,
+    pub chain_length: u64,
+    pub chain_count: u64,
+    pub part: u64,
+}
+
+impl FromStr for TableName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /*
+            Parse a file name such as `md5_loweralpha#1-7_0_3800x33554432_0.rt`, which
+            is how RainbowCrack finds out how a table was generated
+        */
+        let invalid = || {
+            format!(
+                "Invalid RainbowCrack table name, expected \
+                algorithm_charset#min-max_index_lengthxcount_part.rt, got: {}",
+                s
+            )
+        };
+        let file_name = path::Path::new(s)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(invalid)?;
+        let fields: Vec<&str> = file_name.split('_').collect();
+        let [algorithm, charset_lengths, table_index, chains, part] = fields[..] else {
+            return Err(invalid());
+        };
+        let (charset, lengths) = charset_lengths.split_once('#').ok_or_else(invalid)?;
+        let (min_length, max_length) = lengths.split_once('-').ok_or_else(invalid)?;
+        let (chain_length, chain_count) = chains.split_once('x').ok_or_else(invalid)?;
+        let number = |field: &str| field.parse::<u64>().map_err(|_| invalid());
+        Ok(TableName {
+            algorithm: String::from(algorithm),
+            charset: String::from(charset),
+            min_length: number(min_length)? as usize,
+            max_length: number(max_length)? as usize,
+            table_index: number(table_index)?,
+            chain_length: number(chain_length)?,
+            chain_count: number(chain_count)?,
+            part: number(part)?,
+        })
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}_{}#{}-{}_{}_{}x{}_{}.{}",
+            self.algorithm,
+            self.charset,
+            self.min_length,
+            self.max_length,
+            self.table_index,
+            self.chain_length,
+            self.chain_count,
+            self.part,
+            RT_EXTENSION
+        )
+    }
+}
+
+// Every plaintext of a charset and length range, numbered the way RainbowCrack does
+pub struct PlainSpace {
+    characters: Vec<char>,
+    min_length: usize,
+    max_length: usize,
+    // How many plaintexts are shorter than each length
+    up_to_length: Vec<u64>,
+    total: u64,
+}
+
+impl PlainSpace {
+    pub fn new(charset: &str, min_length: usize, max_length: usize) -> Result<PlainSpace, String> {
+        let characters: Vec<char> = charset_characters(charset)?.chars().collect();
+        if min_length == 0 || min_length > max_length {
+            return Err(format!(
+                "Invalid RainbowCrack plaintext lengths, got: {}-{}",
+                min_length, max_length
+            ));
+        }
+        let too_large = || {
+            format!(
+                "The {} plaintext space of lengths {}-{} does not fit in 64 bits",
+                charset, min_length, max_length
+            )
+        };
+        let mut up_to_length = vec![0u64];
+        let mut length_space: u64 = 1;
+        for length in 1..=max_length {
+            length_space = length_space
+                .checked_mul(characters.len() as u64)
+                .ok_or_else(too_large)?;
+            let shorter = up_to_length[length - 1];
+            up_to_length.push(if length < min_length {
+                0
+            } else {
+                shorter.checked_add(length_space).ok_or_else(too_large)?
+            });
+        }
+        Ok(PlainSpace {
+            characters,
+            min_length,
+            max_length,
+            total: up_to_length[max_length],
+            up_to_length,
+        })
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn index_to_plain(&self, index: u64) -> String {
+        let length = (self.min_length..=self.max_length)
+            .rev()
+            .find(|length| index >= self.up_to_length[length - 1])
+            .unwrap_or(self.min_length);
+        let base = self.characters.len() as u64;
+        let mut remainder = index - self.up_to_length[length - 1];
+        let mut plain = vec![self.characters[0]; length];
+        for character in plain.iter_mut().rev() {
+            *character = self.characters[(remainder % base) as usize];
+            remainder /= base;
+        }
+        plain.into_iter().collect()
+    }
+
+    fn plain_to_index(&self, plain: &str) -> Option<u64> {
+        // None when the plaintext is outside of this space
+        let length = plain.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return None;
+        }
+        let base = self.characters.len() as u64;
+        let mut index: u64 = 0;
+        for character in plain.chars() {
+            let digit = self.characters.iter().position(|c| *c == character)?;
+            index = index * base + digit as u64;
+        }
+        Some(self.up_to_length[length - 1] + index)
+    }
+
+    pub fn reduce(&self, hash: &str, table_index: u64, position: u64) -> Result<u64, String> {
+        // The digest prefix, offset by table and position
+        let offset = TABLE_INDEX_REDUCE_OFFSET
+            .wrapping_mul(table_index)
+            .wrapping_add(position);
+        Ok(digest_prefix(hash)?.wrapping_add(offset) % self.total)
+    }
+}
+
+pub fn digest_prefix(hash: &str) -> Result<u64, String> {
+    // The first 8 digest bytes as a little-endian number
+    let digest = hasher::decode_hex(hash)?;
+    let mut bytes = [0u8; 8];
+    let used = digest.len().min(bytes.len());
+    bytes[..used].copy_from_slice(&digest[..used]);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn algorithm_name(hashing: &TableHashing) -> Result<String, String> {
+    // RainbowCrack hashes the bare plaintext, so only unsalted single hashes carry over
+    let unsupported = || {
+        format!(
+            "RainbowCrack tables only hold lm, md5, sha1 or sha256 hashes of the bare word, got: {}",
+            hashing.name()
+        )
+    };
+    match hashing {
+        TableHashing::Plain => Ok(Algorithm::Sha256.to_string()),
+        TableHashing::Lm => Ok(String::from("lm")),
+        TableHashing::Pipeline(pipeline, _) => match pipeline.single_algorithm() {
+            Some(algorithm @ (Algorithm::Md5 | Algorithm::Sha1 | Algorithm::Sha256)) => {
+                Ok(algorithm.to_string())
+            }
+            _ => Err(unsupported()),
+        },
+        _ => Err(unsupported()),
+    }
+}
+
+pub fn table_hashing(algorithm: &str) -> Result<TableHashing, String> {
+    match algorithm {
+        "sha256" => Ok(TableHashing::Plain),
+        "lm" => Ok(TableHashing::Lm),
+        "md5" | "sha1" => Ok(TableHashing::Pipeline(
+            Pipeline::compile(&format!("{}(p)", algorithm))?,
+            None,
+        )),
+        _ => Err(format!(
+            "Unsupported RainbowCrack table algorithm, expected lm, md5, sha1 or sha256, got: {}",
+            algorithm
+        )),
+    }
+}
+
+fn smallest_charset(word_hashes: &[WordHash]) -> Result<&'static str, String> {
+    CHARSETS
+        .iter()
+        .find(|(_, characters)| {
+            word_hashes
+                .iter()
+                .all(|word_hash| word_hash.word.chars().all(|c| characters.contains(c)))
+        })
+        .map(|(name, _)| *name)
+        .ok_or_else(|| {
+            String::from("No RainbowCrack charset holds every character of the table's words")
+        })
+}
+
+pub fn export_table(
+    header: &TableHeader,
+    word_hashes: &[WordHash],
+    charset: Option<&str>,
+    output_dir: &str,
+) -> Result<String, String> {
+    /*
+        Write the table as a RainbowCrack .rt table in `output_dir`, named the way
+        RainbowCrack expects. Each word becomes a chain of its own, sorted by end point
+        like rtsort does. Returns the path of the written table
+    */
+    let algorithm = algorithm_name(&header.hashing)?;
+    let charset = match charset {
+        Some(charset) => charset_characters(charset).map(|_| charset)?,
+        None => smallest_charset(word_hashes)?,
+    };
+    let lengths = word_hashes
+        .iter()
+        .map(|word_hash| word_hash.word.chars().count());
+    let min_length = lengths.clone().min().unwrap_or(1).max(1);
+    let max_length = lengths.max().unwrap_or(1).max(1);
+    let plain_space = PlainSpace::new(charset, min_length, max_length)?;
+
+    let mut chains = word_hashes
+        .iter()
+        .map(|word_hash| {
+            let start = plain_space.plain_to_index(&word_hash.word).ok_or_else(|| {
+                format!(
+                    "{} is not in the RainbowCrack {} charset",
+                    word_hash.word, charset
+                )
+            })?;
+            Ok((start, plain_space.reduce(&word_hash.hash, 0, 0)?))
+        })
+        .collect::<Result<Vec<(u64, u64)>, String>>()?;
+    chains.sort_by_key(|(_, end)| *end);
+
+    let table_name = TableName {
+        algorithm,
+        charset: String::from(charset),
+        min_length,
+        max_length,
+        table_index: 0,
+        chain_length: EXPORT_CHAIN_LENGTH,
+        chain_count: chains.len() as u64,
+        part: 0,
+    };
+    let table_path = path::Path::new(output_dir)
+        .join(table_name.to_string())
+        .to_string_lossy()
+        .into_owned();
+    let mut content: Vec<u8> = Vec::with_capacity(chains.len() * CHAIN_LENGTH_BYTES);
+    for (start, end) in chains {
+        content.extend(start.to_le_bytes());
+        content.extend(end.to_le_bytes());
+    }
+    fs::create_dir_all(output_dir)
+        .and_then(|_| fs::write(&table_path, content))
+        .map_err(|e| format!("Error while writing hashes to file: {}", e))?;
+    Ok(table_path)
+}
+
+pub fn import_table(table_path: &str) -> Result<(TableHeader, Vec<WordHash>), String> {
+    /*
+        Read a RainbowCrack .rt table by walking every chain from its start point.
+        The file name says how the table was generated, so it must not be renamed
+    */
+    check_not_rtc(table_path)?;
+    let table_name = table_path.parse::<TableName>()?;
+    let hashing = table_hashing(&table_name.algorithm)?;
+    let plain_space = PlainSpace::new(
+        &table_name.charset,
+        table_name.min_length,
+        table_name.max_length,
+    )?;
+    let content = fs::read(table_path).map_err(|e| {
+        format!(
+            "Error opening rainbow table {} for reading: {}",
+            table_path, e
+        )
+    })?;
+    if !content.len().is_multiple_of(CHAIN_LENGTH_BYTES) {
+        return Err(format!(
+            "Invalid RainbowCrack table {}, its size is not a multiple of {} bytes",
+            table_path, CHAIN_LENGTH_BYTES
+        ));
+    }
+
+    let mut word_hashes: Vec<WordHash> = Vec::new();
+    for (chain_number, chain) in content.chunks_exact(CHAIN_LENGTH_BYTES).enumerate() {
+        let start = u64::from_le_bytes(chain[..8].try_into().expect("chains are 16 bytes"));
+        let end = u64::from_le_bytes(chain[8..].try_into().expect("chains are 16 bytes"));
+        let mut index = start;
+        for position in 0..table_name.chain_length.saturating_sub(1) {
+            let word = plain_space.index_to_plain(index);
+            let hash = hashing
+                .hash(&word)
+                .expect("RainbowCrack algorithms need nothing but the word");
+            index = plain_space.reduce(&hash, table_name.table_index, position)?;
+            word_hashes.push(WordHash { word, hash });
+        }
+        if index != end {
+            return Err(format!(
+                "Chain {} of {} does not end where the table says, \
+                its file name may not match how it was generated",
+                chain_number + 1,
+                table_path
+            ));
+        }
+    }
+    let header = TableHeader::new(hashing, &word_hashes);
+    Ok((header, word_hashes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+
+    fn md5_hashing() -> TableHashing {
+        TableHashing::Pipeline(Pipeline::compile("md5(p)").unwrap(), None)
+    }
+
+    #[test]
+    fn test_table_name() {
+        let file_name = "md5_loweralpha#1-7_0_3800x33554432_0.rt";
+        let table_name = format!("/tables/{}", file_name)
+            .parse::<TableName>()
+            .unwrap();
+        assert_eq!("loweralpha", table_name.charset);
+        assert_eq!(7, table_name.max_length);
+        assert_eq!(3800, table_name.chain_length);
+        assert_eq!(file_name, table_name.to_string());
+        assert!("md5_loweralpha_0_3800x1_0.rt".parse::<TableName>().is_err());
+        assert!("md5_loweralpha#1-7_0_3800_0.rt"
+            .parse::<TableName>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_plain_space() {
+        let plain_space = PlainSpace::new("numeric", 1, 3).unwrap();
+        assert_eq!(1110, plain_space.total);
+        assert_eq!("0", plain_space.index_to_plain(0));
+        assert_eq!("00", plain_space.index_to_plain(10));
+        assert_eq!("999", plain_space.index_to_plain(1109));
+        assert_eq!(Some(10), plain_space.plain_to_index("00"));
+        assert_eq!(Some(1109), plain_space.plain_to_index("999"));
+        assert_eq!(None, plain_space.plain_to_index("9a"));
+        assert_eq!(None, plain_space.plain_to_index("1234"));
+
+        // Shorter plaintexts are not part of the space at all
+        let plain_space = PlainSpace::new("numeric", 2, 2).unwrap();
+        assert_eq!(100, plain_space.total);
+        assert_eq!("42", plain_space.index_to_plain(42));
+        assert_eq!(None, plain_space.plain_to_index("4"));
+        assert!(PlainSpace::new("ascii-32-95", 1, 12).is_err());
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.rt", temp_file_handler.temp_file_path);
+        let cases = [
+            (
+                md5_hashing(),
+                vec!["potato", "rice", "noodles"],
+                "loweralpha",
+            ),
+            (
+                TableHashing::Plain,
+                vec!["Pot4to", "r1ce"],
+                "mixalpha-numeric",
+            ),
+            (TableHashing::Lm, vec!["PASSWOR", "D1"], "alpha-numeric"),
+        ];
+        for (hashing, words, charset) in cases {
+            let word_hashes = test_utils::word_hashes(&hashing, &words);
+            let header = TableHeader::new(hashing.clone(), &word_hashes);
+            let table_path = export_table(&header, &word_hashes, None, &output_dir).unwrap();
+            assert!(
+                table_path.contains(&format!("_{}#", charset)),
+                "{}",
+                table_path
+            );
+
+            let (imported_header, mut imported) = import_table(&table_path).unwrap();
+            assert_eq!(hashing, imported_header.hashing);
+            let mut expected = word_hashes.clone();
+            expected.sort_by(|a, b| a.word.cmp(&b.word));
+            imported.sort_by(|a, b| a.word.cmp(&b.word));
+            assert_eq!(expected, imported);
+        }
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_unsupported() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let output_dir = format!("{}.rt", temp_file_handler.temp_file_path);
+        let word_hashes = test_utils::word_hashes(&md5_hashing(), &["potato"]);
+        let salted = TableHashing::Pipeline(Pipeline::compile("md5(p.salt)").unwrap(), None);
+        let header = TableHeader::new(salted, &word_hashes);
+        assert!(export_table(&header, &word_hashes, None, &output_dir).is_err());
+        let header = TableHeader::new(md5_hashing(), &word_hashes);
+        assert!(export_table(&header, &word_hashes, Some("numeric"), &output_dir).is_err());
+        assert!(export_table(&header, &word_hashes, Some("klingon"), &output_dir).is_err());
+    }
+
+    #[test]
+    fn test_import_invalid_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let dir = path::Path::new(&temp_file_handler.temp_file_path)
+            .parent()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(import_table(&format!("{}/md5_numeric#1-2_0_2x1_0.rtc", dir)).is_err());
+        assert!(import_table(&temp_file_handler.temp_file_path).is_err());
+
+        let table_path = format!("{}/md5_numeric#1-2_0_2x1_0.rt", dir);
+        fs::write(&table_path, [0u8; 15]).unwrap();
+        assert!(import_table(&table_path).is_err());
+        // A chain whose end does not match its start
+        fs::write(&table_path, [0u8; 16]).unwrap();
+        assert!(import_table(&table_path).is_err());
+    }
+}
@@ -3,14 +3,13 @@ use std::io::{BufRead, BufReader, Error, ErrorKind};
 
 pub const FILE_OPERATION_ERROR: i32 = 2;
 
-pub fn read_words(fpath: &str) -> Result<Vec<String>, String> {
+pub fn open_word_file(fpath: &str) -> Result<BufReader<fs::File>, String> {
     /*
-        Read the words from the file at `fpath`. Assumes that the words
-        in the file are delimited by newlines
+        Open the word file at `fpath` to read its words one by one, for word
+        lists that are too large to hold in memory
     */
-    // Open file and handle errors
-    let word_file = match fs::File::open(fpath) {
-        Ok(f) => f,
+    match fs::File::open(fpath) {
+        Ok(word_file) => Ok(BufReader::new(word_file)),
         Err(error) => {
             let mut error_base = String::from("Error opening word file for reading: ");
             match error.kind() {
@@ -18,18 +17,42 @@ pub fn read_words(fpath: &str) -> Result<Vec<String>, String> {
                 ErrorKind::PermissionDenied => error_base.push_str("Permission denied"),
                 _ => error_base.push_str("Unknown Error"),
             }
-            return Err(error_base);
+            Err(error_base)
         }
-    };
+    }
+}
 
-    let reader = BufReader::new(word_file);
-    let words: Vec<String> = match reader.lines().collect::<Result<Vec<String>, Error>>() {
+pub fn read_words(fpath: &str) -> Result<Vec<String>, String> {
+    /*
+        Read the words from the file at `fpath`. Assumes that the words
+        in the file are delimited by newlines
+    */
+    let words: Vec<String> = match open_word_file(fpath)?
+        .lines()
+        .collect::<Result<Vec<String>, Error>>()
+    {
         Err(error) => return Err(format!("Error while reading from file: {}", error)),
         Ok(lines) => lines,
     };
     Ok(words)
 }
 
+pub fn read_key_file(fpath: &str) -> Result<Vec<u8>, String> {
+    /*
+        Read the raw bytes of a key file. The contents are used as is,
+        so a trailing newline becomes part of the key
+    */
+    fs::read(fpath).map_err(|error| {
+        let mut error_base = String::from("Error opening key file for reading: ");
+        match error.kind() {
+            ErrorKind::NotFound => error_base.push_str("File not found"),
+            ErrorKind::PermissionDenied => error_base.push_str("Permission denied"),
+            _ => error_base.push_str("Unknown Error"),
+        }
+        error_base
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,7 +81,7 @@ mod tests {
             Err(e) => panic!("{}", e),
         };
 
-        let lines_iter = read_words.into_iter().zip(words.into_iter());
+        let lines_iter = read_words.into_iter().zip(words);
         lines_iter.for_each(|pair| {
             assert_eq!(pair.0, pair.1);
         });
@@ -73,6 +96,25 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_read_key_file() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let temp_file = temp_file_handler.get_file_object(test_utils::FileMode::Write);
+        let mut writer = BufWriter::new(&temp_file);
+        let key: [u8; 4] = [0x00, 0xff, 0x0a, 0x41];
+        writer.write_all(&key).unwrap();
+        std::mem::drop(writer);
+
+        assert_eq!(
+            Ok(key.to_vec()),
+            read_key_file(&temp_file_handler.temp_file_path)
+        );
+        match read_key_file("/abc/defghi/jkl.qwerty") {
+            Err(e) => assert!(e.contains("File not found")),
+            Ok(_) => panic!("Did not fail when it should have failed with 'File not found'"),
+        };
+    }
+
     #[test]
     fn test_read_words_no_permissions() {
         let temp_file_handler = test_utils::TempFileHandler::new();
@@ -0,0 +1,223 @@
+use std::fs;
+use std::path;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.txt";
+const MANIFEST_VERSION: u32 = 1;
+const MANIFEST_DELIMITER: char = '=';
+pub const MAX_SHARD_COUNT: usize = 1 << 16;
+// Shards are picked by at most the first 16 bits, i.e. 4 hex characters, of the digest
+const SHARD_PREFIX_LENGTH: usize = 4;
+
+fn invalid_shard_count(shard_count: &str) -> String {
+    format!(
+        "Invalid shard count, expected a power of two between 2 and {}, got: {}",
+        MAX_SHARD_COUNT, shard_count
+    )
+}
+
+pub fn parse_shard_count(shard_count: &str) -> Result<usize, String> {
+    let parsed = shard_count
+        .parse::<usize>()
+        .map_err(|_| invalid_shard_count(shard_count))?;
+    Ok(ShardManifest::new(parsed)?.shard_count)
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ShardManifest {
+    pub shard_count: usize,
+}
+
+impl ShardManifest {
+    pub fn new(shard_count: usize) -> Result<ShardManifest, String> {
+        // Shards are picked by whole bits of the digest, so their number is a power of two
+        if !shard_count.is_power_of_two() || !(2..=MAX_SHARD_COUNT).contains(&shard_count) {
+            return Err(invalid_shard_count(&shard_count.to_string()));
+        }
+        Ok(ShardManifest { shard_count })
+    }
+
+    fn shard_bits(&self) -> u32 {
+        self.shard_count.trailing_zeros()
+    }
+
+    pub fn shard_index(&self, hash: &str) -> Result<usize, String> {
+        // The leading `shard_bits` bits of the digest pick the shard
+        let prefix = hash
+            .get(..SHARD_PREFIX_LENGTH)
+            .and_then(|prefix| u16::from_str_radix(prefix, 16).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Sharded tables need hex digests of at least {} characters, got: {}",
+                    SHARD_PREFIX_LENGTH, hash
+                )
+            })?;
+        Ok((prefix >> (16 - self.shard_bits())) as usize)
+    }
+
+    pub fn shard_file_name(&self, shard_index: usize) -> String {
+        // Zero padded hex, so the files sort in shard order
+        let width = self.shard_bits().div_ceil(4) as usize;
+        format!("shard-{:0width$x}.txt", shard_index, width = width)
+    }
+
+    pub fn shard_path(&self, table_dir: &str, shard_index: usize) -> String {
+        path::Path::new(table_dir)
+            .join(self.shard_file_name(shard_index))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "version{d}{}\nshards{d}{}\nshard_bits{d}{}\n",
+            MANIFEST_VERSION,
+            self.shard_count,
+            self.shard_bits(),
+            d = MANIFEST_DELIMITER
+        )
+    }
+
+    fn deserialize(manifest: &str) -> Result<ShardManifest, String> {
+        let mut version: Option<u32> = None;
+        let mut shard_count: Option<usize> = None;
+        for line in manifest.lines().filter(|line| !line.trim().is_empty()) {
+            let invalid = || format!("Invalid manifest line, got: {}", line);
+            let (key, value) = line.split_once(MANIFEST_DELIMITER).ok_or_else(invalid)?;
+            match key {
+                "version" => version = Some(value.parse::<u32>().map_err(|_| invalid())?),
+                "shards" => shard_count = Some(parse_shard_count(value)?),
+                // Derived from the shard count, only there for people reading the manifest
+                "shard_bits" => (),
+                _ => return Err(invalid()),
+            }
+        }
+        match (version, shard_count) {
+            (Some(MANIFEST_VERSION), Some(shard_count)) => ShardManifest::new(shard_count),
+            (Some(version), _) if version != MANIFEST_VERSION => Err(format!(
+                "Unsupported manifest version {}, expected {}",
+                version, MANIFEST_VERSION
+            )),
+            _ => Err(String::from(
+                "Manifest is missing its version or shard count",
+            )),
+        }
+    }
+
+    pub fn open(table_dir: &str) -> Result<ShardManifest, String> {
+        let manifest_path = path::Path::new(table_dir).join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Unable to read manifest {}: {}", manifest_path.display(), e))?;
+        ShardManifest::deserialize(&manifest)
+    }
+
+    pub fn write(&self, table_dir: &str) -> Result<(), String> {
+        /*
+            Create the table directory and its manifest. Shards generated
+            independently all write the same manifest, so an existing one is only
+            an error when it disagrees on the layout
+        */
+        if is_sharded_table(table_dir) {
+            let existing = ShardManifest::open(table_dir)?;
+            if existing != *self {
+                return Err(format!(
+                    "{} already holds a table of {} shards, not {}",
+                    table_dir, existing.shard_count, self.shard_count
+                ));
+            }
+        }
+        fs::create_dir_all(table_dir)
+            .map_err(|e| format!("Unable to create table directory {}: {}", table_dir, e))?;
+        let manifest_path = path::Path::new(table_dir).join(MANIFEST_FILE_NAME);
+        fs::write(&manifest_path, self.serialize()).map_err(|e| {
+            format!(
+                "Error while writing manifest {}: {}",
+                manifest_path.display(),
+                e
+            )
+        })
+    }
+}
+
+pub fn is_sharded_table(rainbow_table_path: &str) -> bool {
+    path::Path::new(rainbow_table_path)
+        .join(MANIFEST_FILE_NAME)
+        .is_file()
+}
+
+pub fn resolve_table_path(rainbow_table_path: &str, hash: &str) -> Result<String, String> {
+    /*
+        The file that would hold `hash`: the table itself for single file
+        tables, or the one shard a sharded table puts it in
+    */
+    if !is_sharded_table(rainbow_table_path) {
+        return Ok(String::from(rainbow_table_path));
+    }
+    let manifest = ShardManifest::open(rainbow_table_path)?;
+    let shard_index = manifest.shard_index(&hash.to_lowercase())?;
+    let shard_path = manifest.shard_path(rainbow_table_path, shard_index);
+    if !path::Path::new(&shard_path).exists() {
+        return Err(format!(
+            "Shard {} of {} has not been generated yet",
+            shard_index, rainbow_table_path
+        ));
+    }
+    Ok(shard_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shard_count() {
+        assert_eq!(Ok(16), parse_shard_count("16"));
+        assert_eq!(Ok(MAX_SHARD_COUNT), parse_shard_count("65536"));
+        for shard_count in ["0", "1", "12", "131072", "abc"] {
+            assert!(parse_shard_count(shard_count).is_err(), "{}", shard_count);
+        }
+    }
+
+    #[test]
+    fn test_shard_index() {
+        let manifest = ShardManifest::new(16).unwrap();
+        assert_eq!(Ok(0x5), manifest.shard_index("5e884898da28047151d0"));
+        assert_eq!(Ok(0xf), manifest.shard_index("ffff"));
+        let manifest = ShardManifest::new(1024).unwrap();
+        // 0x5e88 is 0101 1110 1000 1000, of which the first 10 bits are 0x17a
+        assert_eq!(Ok(0x17a), manifest.shard_index("5e884898da28047151d0"));
+        assert!(manifest.shard_index("5e8").is_err());
+        assert!(manifest.shard_index("zzzzzz").is_err());
+    }
+
+    #[test]
+    fn test_shard_file_name() {
+        assert_eq!(
+            "shard-a.txt",
+            ShardManifest::new(16).unwrap().shard_file_name(10)
+        );
+        assert_eq!(
+            "shard-01.txt",
+            ShardManifest::new(32).unwrap().shard_file_name(1)
+        );
+        assert_eq!(
+            "shard-17a.txt",
+            ShardManifest::new(1024).unwrap().shard_file_name(0x17a)
+        );
+    }
+
+    #[test]
+    fn test_serialize_manifest() {
+        let manifest = ShardManifest::new(256).unwrap();
+        assert_eq!(
+            "version=1\nshards=256\nshard_bits=8\n",
+            manifest.serialize()
+        );
+        assert_eq!(
+            Ok(manifest),
+            ShardManifest::deserialize("version=1\nshards=256\n")
+        );
+        assert!(ShardManifest::deserialize("version=2\nshards=256\n").is_err());
+        assert!(ShardManifest::deserialize("shards=256\n").is_err());
+        assert!(ShardManifest::deserialize("version=1\nshards=256\ncolor=blue\n").is_err());
+    }
+}
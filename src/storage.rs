@@ -0,0 +1,608 @@
+use crate::hasher::{self, WordHash};
+use crate::header::{self, TableHeader};
+use crate::reader;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path;
+use std::str::FromStr;
+
+mod binary;
+
+pub use binary::BinaryStorage;
+
+// Every SQLite database starts with this header
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const SQLITE_SCHEMA: &str = "
+    DROP TABLE IF EXISTS word_hashes;
+    CREATE TABLE word_hashes (word TEXT NOT NULL, hash TEXT NOT NULL);
+";
+// Created after the rows are inserted, which is a lot faster than keeping it up to date
+const SQLITE_INDEX: &str = "CREATE INDEX word_hashes_hash ON word_hashes (hash);";
+// The text header's fields, one row each
+const SQLITE_HEADER_SCHEMA: &str = "
+    DROP TABLE IF EXISTS table_header;
+    CREATE TABLE table_header (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum StorageFormat {
+    // One word:hash line per entry
+    Text,
+    // A word_hashes (word, hash) table, indexed on hash
+    Sqlite,
+    // Fixed size records of raw digests, see `binary` for the layout
+    Binary,
+}
+
+impl FromStr for StorageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(StorageFormat::Text),
+            "sqlite" => Ok(StorageFormat::Sqlite),
+            "binary" => Ok(StorageFormat::Binary),
+            _ => Err(format!(
+                "Invalid storage format, expected text, sqlite or binary, got: {}",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StorageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StorageFormat::Text => "text",
+            StorageFormat::Sqlite => "sqlite",
+            StorageFormat::Binary => "binary",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// What `convert-table` writes: one of the storage formats, or a RainbowCrack .rt table
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ConvertFormat {
+    Storage(StorageFormat),
+    RainbowCrack,
+}
+
+impl FromStr for ConvertFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rt" => Ok(ConvertFormat::RainbowCrack),
+            _ => s
+                .parse::<StorageFormat>()
+                .map(ConvertFormat::Storage)
+                .map_err(|_| {
+                    format!(
+                        "Invalid table format, expected text, sqlite, binary or rt, got: {}",
+                        s
+                    )
+                }),
+        }
+    }
+}
+
+impl fmt::Display for ConvertFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertFormat::Storage(storage_format) => write!(f, "{}", storage_format),
+            ConvertFormat::RainbowCrack => write!(f, "rt"),
+        }
+    }
+}
+
+// One entry as it was found in the table, for reporting problems by position
+pub struct TableEntry {
+    // Where the entry is, e.g. `line 3` or `row 3`
+    pub position: String,
+    pub word_hash: Result<WordHash, String>,
+}
+
+// Entries that are produced as they are written, e.g. merged from sorted runs
+pub type WordHashStream<'a> = dyn Iterator<Item = Result<WordHash, String>> + 'a;
+
+pub trait TableStorage {
+    // Replace whatever the table held with `word_hashes`, described by `header`
+    fn write(&self, header: &TableHeader, word_hashes: &[WordHash]) -> Result<(), String> {
+        self.write_stream(header, &mut word_hashes.iter().cloned().map(Ok))
+    }
+    // Like `write`, for tables too large to hold in memory. `header.count` is how many entries follow
+    fn write_stream(
+        &self,
+        header: &TableHeader,
+        word_hashes: &mut WordHashStream,
+    ) -> Result<(), String>;
+    // None for tables written before headers existed
+    fn read_header(&self) -> Result<Option<TableHeader>, String>;
+    // Every entry, including the ones that could not be parsed
+    fn read_entries(&self) -> Result<Vec<TableEntry>, String>;
+    fn read_all(&self) -> Result<Vec<WordHash>, String> {
+        self.read_entries()?
+            .into_iter()
+            .map(|entry| entry.word_hash)
+            .collect()
+    }
+    // The word for `hash`, if the table holds it
+    fn lookup(&self, hash: &str) -> Result<Option<String>, String>;
+    // Any one hash from the table, to tell what its hashes look like
+    fn sample_hash(&self) -> Result<Option<String>, String>;
+}
+
+fn parse_table_line(line: &str, line_number: usize) -> Result<WordHash, String> {
+    // Words may contain the delimiter, hashes are always hex
+    match hasher::split_serialized_hash(line) {
+        Some(word_hash)
+            if !word_hash.hash.is_empty()
+                && word_hash.hash.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            Ok(word_hash)
+        }
+        _ => Err(format!(
+            "Invalid table entry at line {}, expected word{}hash, got: {}",
+            line_number,
+            hasher::HASH_DELIMITER,
+            line
+        )),
+    }
+}
+
+pub struct TextStorage {
+    path: String,
+}
+
+impl TextStorage {
+    pub fn new(path: &str) -> TextStorage {
+        TextStorage {
+            path: String::from(path),
+        }
+    }
+
+    fn first_lines(&self, count: usize) -> Result<Vec<String>, String> {
+        let file = fs::File::open(&self.path).map_err(|e| {
+            format!(
+                "Error opening rainbow table {} for reading: {}",
+                self.path, e
+            )
+        })?;
+        BufReader::new(file)
+            .lines()
+            .take(count)
+            .collect::<Result<Vec<String>, std::io::Error>>()
+            .map_err(|e| format!("Error while reading from file: {}", e))
+    }
+}
+
+impl TableStorage for TextStorage {
+    fn write_stream(
+        &self,
+        header: &TableHeader,
+        word_hashes: &mut WordHashStream,
+    ) -> Result<(), String> {
+        let header_line = header.serialize()?;
+        let file = fs::File::create(&self.path)
+            .map_err(|e| format!("Unable to open file for writing: {}", e))?;
+        let mut writer = BufWriter::new(file);
+        let write_error = |e: std::io::Error| format!("Error while writing hashes to file: {}", e);
+        writeln!(writer, "{}", header_line).map_err(write_error)?;
+        for word_hash in word_hashes {
+            let word_hash = word_hash?;
+            writeln!(
+                writer,
+                "{}{}{}",
+                word_hash.word,
+                hasher::HASH_DELIMITER,
+                word_hash.hash
+            )
+            .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)
+    }
+
+    fn read_header(&self) -> Result<Option<TableHeader>, String> {
+        match self.first_lines(1)?.first() {
+            Some(line) if header::is_header_line(line) => Ok(Some(TableHeader::deserialize(line)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn read_entries(&self) -> Result<Vec<TableEntry>, String> {
+        Ok(reader::read_words(&self.path)?
+            .into_iter()
+            .enumerate()
+            .filter(|(index, line)| *index > 0 || !header::is_header_line(line))
+            .map(|(index, line)| TableEntry {
+                position: format!("line {}", index + 1),
+                word_hash: parse_table_line(&line, index + 1),
+            })
+            .collect())
+    }
+
+    fn lookup(&self, hash: &str) -> Result<Option<String>, String> {
+        // Text tables have no index, so this is a full scan
+        Ok(self
+            .read_all()?
+            .into_iter()
+            .find(|word_hash| word_hash.hash == hash)
+            .map(|word_hash| word_hash.word))
+    }
+
+    fn sample_hash(&self) -> Result<Option<String>, String> {
+        // The first entry is on the first or, after a header, the second line
+        let lines = self.first_lines(2)?;
+        let (line_number, line) = match lines.first() {
+            Some(line) if header::is_header_line(line) => (2, lines.get(1)),
+            first_line => (1, first_line),
+        };
+        line.map(|line| parse_table_line(line, line_number).map(|word_hash| word_hash.hash))
+            .transpose()
+    }
+}
+
+pub struct SqliteStorage {
+    path: String,
+}
+
+impl SqliteStorage {
+    pub fn new(path: &str) -> SqliteStorage {
+        SqliteStorage {
+            path: String::from(path),
+        }
+    }
+
+    fn sqlite_error(&self, e: rusqlite::Error) -> String {
+        format!("SQLite error in {}: {}", self.path, e)
+    }
+
+    fn open_read_only(&self) -> Result<Connection, String> {
+        Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| self.sqlite_error(e))
+    }
+}
+
+impl TableStorage for SqliteStorage {
+    fn write_stream(
+        &self,
+        header: &TableHeader,
+        word_hashes: &mut WordHashStream,
+    ) -> Result<(), String> {
+        // SQLite would refuse to open a text or binary table being overwritten, so it goes first
+        if path::Path::new(&self.path).exists()
+            && detect_format(&self.path)? != StorageFormat::Sqlite
+        {
+            fs::remove_file(&self.path)
+                .map_err(|e| format!("Unable to replace {}: {}", self.path, e))?;
+        }
+        let mut connection = Connection::open(&self.path).map_err(|e| self.sqlite_error(e))?;
+        let transaction = connection.transaction().map_err(|e| self.sqlite_error(e))?;
+        transaction
+            .execute_batch(SQLITE_SCHEMA)
+            .and_then(|_| transaction.execute_batch(SQLITE_HEADER_SCHEMA))
+            .map_err(|e| self.sqlite_error(e))?;
+        {
+            let mut insert = transaction
+                .prepare("INSERT INTO table_header (key, value) VALUES (?1, ?2)")
+                .map_err(|e| self.sqlite_error(e))?;
+            for (key, value) in header.to_fields() {
+                insert
+                    .execute(params![key, value])
+                    .map_err(|e| self.sqlite_error(e))?;
+            }
+        }
+        {
+            let mut insert = transaction
+                .prepare("INSERT INTO word_hashes (word, hash) VALUES (?1, ?2)")
+                .map_err(|e| self.sqlite_error(e))?;
+            for word_hash in word_hashes {
+                let word_hash = word_hash?;
+                insert
+                    .execute(params![word_hash.word, word_hash.hash])
+                    .map_err(|e| self.sqlite_error(e))?;
+            }
+        }
+        transaction
+            .execute_batch(SQLITE_INDEX)
+            .map_err(|e| self.sqlite_error(e))?;
+        transaction.commit().map_err(|e| self.sqlite_error(e))
+    }
+
+    fn read_header(&self) -> Result<Option<TableHeader>, String> {
+        let connection = self.open_read_only()?;
+        let has_header = connection
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'table_header'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| self.sqlite_error(e))?
+            .is_some();
+        if !has_header {
+            return Ok(None);
+        }
+        let mut select = connection
+            .prepare("SELECT key, value FROM table_header")
+            .map_err(|e| self.sqlite_error(e))?;
+        let fields = select
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .and_then(|rows| rows.collect::<Result<Vec<(String, String)>, rusqlite::Error>>())
+            .map_err(|e| self.sqlite_error(e))?;
+        Ok(Some(TableHeader::from_fields(&fields)?))
+    }
+
+    fn read_entries(&self) -> Result<Vec<TableEntry>, String> {
+        let connection = self.open_read_only()?;
+        let mut select = connection
+            .prepare("SELECT rowid, word, hash FROM word_hashes ORDER BY rowid")
+            .map_err(|e| self.sqlite_error(e))?;
+        let rows = select
+            .query_map([], |row| {
+                let rowid: i64 = row.get(0)?;
+                let word_hash = match (row.get(1), row.get(2)) {
+                    (Ok(word), Ok(hash)) => Ok(WordHash { word, hash }),
+                    (Err(e), _) | (_, Err(e)) => {
+                        Err(format!("Invalid table entry at row {}: {}", rowid, e))
+                    }
+                };
+                Ok(TableEntry {
+                    position: format!("row {}", rowid),
+                    word_hash,
+                })
+            })
+            .map_err(|e| self.sqlite_error(e))?;
+        rows.collect::<Result<Vec<TableEntry>, rusqlite::Error>>()
+            .map_err(|e| self.sqlite_error(e))
+    }
+
+    fn lookup(&self, hash: &str) -> Result<Option<String>, String> {
+        self.open_read_only()?
+            .query_row(
+                "SELECT word FROM word_hashes WHERE hash = ?1 LIMIT 1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| self.sqlite_error(e))
+    }
+
+    fn sample_hash(&self) -> Result<Option<String>, String> {
+        self.open_read_only()?
+            .query_row(
+                "SELECT hash FROM word_hashes ORDER BY rowid LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| self.sqlite_error(e))
+    }
+}
+
+pub fn detect_format(path: &str) -> Result<StorageFormat, String> {
+    // Anything that is not an SQLite database or a binary table is taken to be a text table
+    let mut file = fs::File::open(path)
+        .map_err(|e| format!("Error opening rainbow table {} for reading: {}", path, e))?;
+    let mut header = [0u8; SQLITE_MAGIC.len()];
+    let read = file
+        .read(&mut header)
+        .map_err(|e| format!("Error while reading from file: {}", e))?;
+    if &header == SQLITE_MAGIC {
+        Ok(StorageFormat::Sqlite)
+    } else if header[..read].starts_with(binary::BINARY_MAGIC) {
+        Ok(StorageFormat::Binary)
+    } else {
+        Ok(StorageFormat::Text)
+    }
+}
+
+pub fn create_table(path: &str, format: StorageFormat) -> Box<dyn TableStorage> {
+    match format {
+        StorageFormat::Text => Box::new(TextStorage::new(path)),
+        StorageFormat::Sqlite => Box::new(SqliteStorage::new(path)),
+        StorageFormat::Binary => Box::new(BinaryStorage::new(path)),
+    }
+}
+
+pub fn open_table(path: &str) -> Result<Box<dyn TableStorage>, String> {
+    Ok(create_table(path, detect_format(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::TableHashing;
+    use crate::test_utils;
+
+    fn header() -> TableHeader {
+        TableHeader::new(TableHashing::Plain, &word_hashes())
+    }
+
+    fn word_hashes() -> Vec<WordHash> {
+        // e91c... and 209f...
+        test_utils::word_hashes(&TableHashing::Plain, &["potato", "rice"])
+    }
+
+    #[test]
+    fn test_storage_format_from_str() {
+        assert_eq!(Ok(StorageFormat::Sqlite), "SQLite".parse::<StorageFormat>());
+        assert!("csv".parse::<StorageFormat>().is_err());
+        assert_eq!(
+            Ok(ConvertFormat::RainbowCrack),
+            "RT".parse::<ConvertFormat>()
+        );
+        assert_eq!(
+            Ok(ConvertFormat::Storage(StorageFormat::Binary)),
+            "binary".parse::<ConvertFormat>()
+        );
+        assert!("rtc".parse::<ConvertFormat>().is_err());
+    }
+
+    #[test]
+    fn test_write_and_lookup() {
+        for format in [
+            StorageFormat::Text,
+            StorageFormat::Sqlite,
+            StorageFormat::Binary,
+        ] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let path = &temp_file_handler.temp_file_path;
+            create_table(path, format)
+                .write(&header(), &word_hashes())
+                .unwrap();
+            // Writing again replaces the previous entries
+            create_table(path, format)
+                .write(&header(), &word_hashes())
+                .unwrap();
+
+            assert_eq!(Ok(format), detect_format(path));
+            let storage = open_table(path).unwrap();
+            assert_eq!(Ok(Some(header())), storage.read_header());
+            assert_eq!(Ok(word_hashes()), storage.read_all());
+            assert_eq!(
+                Ok(Some("rice".to_string())),
+                storage.lookup(&word_hashes()[1].hash)
+            );
+            assert_eq!(Ok(None), storage.lookup("abc"));
+            assert_eq!(
+                Ok(Some(word_hashes()[0].hash.clone())),
+                storage.sample_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_overwrite_with_sqlite() {
+        // A text or binary table confirmed to be overwritten is replaced by the database
+        for format in [StorageFormat::Text, StorageFormat::Binary] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let path = &temp_file_handler.temp_file_path;
+            create_table(path, format)
+                .write(&header(), &word_hashes())
+                .unwrap();
+            create_table(path, StorageFormat::Sqlite)
+                .write(&header(), &word_hashes())
+                .unwrap();
+            assert_eq!(Ok(StorageFormat::Sqlite), detect_format(path));
+            assert_eq!(Ok(word_hashes()), open_table(path).unwrap().read_all());
+        }
+    }
+
+    #[test]
+    fn test_empty_table() {
+        for format in [
+            StorageFormat::Text,
+            StorageFormat::Sqlite,
+            StorageFormat::Binary,
+        ] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let path = &temp_file_handler.temp_file_path;
+            create_table(path, format)
+                .write(&TableHeader::new(TableHashing::Plain, &[]), &[])
+                .unwrap();
+            let storage = open_table(path).unwrap();
+            assert_eq!(Ok(None), storage.sample_hash());
+            assert_eq!(Ok(None), storage.lookup("abc"));
+        }
+    }
+
+    #[test]
+    fn test_text_table_entries() {
+        // Tables from before headers existed are still read, and bad lines are kept
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        fs::write(path, "po:ta:to:e91c\nnot a table\nrice:zz\n").unwrap();
+        let storage = open_table(path).unwrap();
+        assert_eq!(Ok(None), storage.read_header());
+        assert_eq!(Ok(Some("e91c".to_string())), storage.sample_hash());
+        let entries = storage.read_entries().unwrap();
+        assert_eq!("po:ta:to", entries[0].word_hash.as_ref().unwrap().word);
+        assert_eq!("line 2", entries[1].position);
+        assert!(entries[1].word_hash.is_err());
+        assert!(entries[2].word_hash.is_err());
+        assert!(storage.read_all().is_err());
+    }
+
+    #[test]
+    fn test_binary_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        let mut sorted = word_hashes();
+        sorted.reverse();
+        let sorted_header = TableHeader::new(TableHashing::Plain, &sorted);
+        assert!(sorted_header.sorted);
+        let storage = BinaryStorage::new(path);
+        storage.write(&sorted_header, &sorted).unwrap();
+        // Sorted tables are binary searched, uppercase hashes still match
+        assert_eq!(
+            Ok(Some("potato".to_string())),
+            storage.lookup(&sorted[1].hash.to_uppercase())
+        );
+        assert_eq!(Ok(None), storage.lookup(&"0".repeat(64)));
+
+        // Digests of different lengths do not fit fixed size records
+        let mut mixed = word_hashes();
+        mixed[1].hash = String::from("209f");
+        assert!(storage.write(&header(), &mixed).is_err());
+
+        // A truncated table still has its header, the missing entries are errors
+        let content = fs::read(path).unwrap();
+        fs::write(path, &content[..content.len() - 3]).unwrap();
+        let entries = storage.read_entries().unwrap();
+        assert_eq!("record 1", entries[0].position);
+        assert!(entries[0].word_hash.is_ok());
+        assert!(entries[1].word_hash.is_err());
+        assert_eq!(Ok(Some(sorted_header)), storage.read_header());
+    }
+
+    #[test]
+    fn test_corrupted_binary_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        let word_hashes = word_hashes();
+        let storage = BinaryStorage::new(path);
+        storage.write(&header(), &word_hashes).unwrap();
+        let content = fs::read(path).unwrap();
+        let header_length = u32::from_le_bytes(content[8..12].try_into().unwrap()) as usize;
+        let count_offset = 12 + header_length + 4;
+        let records_offset = count_offset + 8;
+
+        // Entry counts and header lengths past the end of the file are errors, not panics
+        let corrupted = [
+            (count_offset, (1u64 << 62).to_le_bytes().to_vec()),
+            (count_offset, 1000u64.to_le_bytes().to_vec()),
+            (8, u32::MAX.to_le_bytes().to_vec()),
+        ];
+        for (offset, bytes) in corrupted {
+            let mut corrupted_content = content.clone();
+            corrupted_content[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            fs::write(path, &corrupted_content).unwrap();
+            let error = storage.read_header().unwrap_err();
+            assert!(error.starts_with("Invalid binary table"), "{}", error);
+            assert!(storage.read_entries().is_err());
+            assert!(storage.lookup(&word_hashes[0].hash).is_err());
+        }
+
+        // So is a word offset past the end of the file, for its own record
+        let mut corrupted_content = content.clone();
+        let word_offset = records_offset + 32;
+        corrupted_content[word_offset..word_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(path, &corrupted_content).unwrap();
+        let entries = storage.read_entries().unwrap();
+        assert!(entries[0].word_hash.is_err());
+        assert!(entries[1].word_hash.is_ok());
+        assert!(storage
+            .lookup(&word_hashes[0].hash)
+            .unwrap_err()
+            .starts_with("Invalid binary table"));
+    }
+
+    #[test]
+    fn test_open_missing_table() {
+        assert!(open_table("/abc/defghi/jkl.qwerty").is_err());
+    }
+}
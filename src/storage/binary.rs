@@ -0,0 +1,395 @@
+use super::{TableEntry, TableStorage, WordHashStream};
+use crate::hasher::{self, WordHash};
+use crate::header::TableHeader;
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+
+/*
+    Layout, all integers little-endian:
+      magic                "RTTABLE1"
+      u32 header length    followed by the text header line
+      u32 digest length    in bytes, the same for every entry
+      u64 entry count
+      entry count records  digest, u64 word offset, u32 word length
+      words                concatenated, offsets are from the start of this blob
+    Records have a fixed size, so sorted tables are binary searched without
+    reading the rest of the file
+*/
+pub const BINARY_MAGIC: &[u8; 8] = b"RTTABLE1";
+// Word offset and length
+const RECORD_TAIL_LENGTH: usize = 8 + 4;
+
+struct Preamble {
+    header_line: String,
+    digest_length: usize,
+    entry_count: u64,
+    // Where the first record starts
+    records_offset: u64,
+    // Where the words start, right after the last record
+    words_offset: u64,
+}
+
+impl Preamble {
+    fn new(
+        header_line: String,
+        digest_length: usize,
+        entry_count: u64,
+        records_offset: u64,
+    ) -> Option<Preamble> {
+        // None when the records would not even fit in a u64 of bytes
+        let words_offset = entry_count
+            .checked_mul((digest_length + RECORD_TAIL_LENGTH) as u64)?
+            .checked_add(records_offset)?;
+        Some(Preamble {
+            header_line,
+            digest_length,
+            entry_count,
+            records_offset,
+            words_offset,
+        })
+    }
+
+    fn record_length(&self) -> u64 {
+        (self.digest_length + RECORD_TAIL_LENGTH) as u64
+    }
+}
+
+struct Record {
+    digest: Vec<u8>,
+    word_offset: u64,
+    word_length: usize,
+}
+
+pub struct BinaryStorage {
+    path: String,
+}
+
+impl BinaryStorage {
+    pub fn new(path: &str) -> BinaryStorage {
+        BinaryStorage {
+            path: String::from(path),
+        }
+    }
+
+    fn invalid(&self, reason: &str) -> String {
+        format!("Invalid binary table {}: {}", self.path, reason)
+    }
+
+    fn open(&self) -> Result<fs::File, String> {
+        fs::File::open(&self.path).map_err(|e| {
+            format!(
+                "Error opening rainbow table {} for reading: {}",
+                self.path, e
+            )
+        })
+    }
+
+    fn file_length(&self, file: &fs::File) -> Result<u64, String> {
+        file.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(|e| format!("Error while reading from file: {}", e))
+    }
+
+    fn read_exact_at(
+        &self,
+        file: &mut fs::File,
+        offset: u64,
+        length: usize,
+    ) -> Result<Vec<u8>, String> {
+        // Lengths and offsets come from the file, so check them before allocating
+        let file_length = self.file_length(file)?;
+        match offset.checked_add(length as u64) {
+            Some(end) if end <= file_length => {}
+            _ => return Err(self.invalid("the file is cut off")),
+        }
+        let mut buffer = vec![0u8; length];
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut buffer))
+            .map_err(|_| self.invalid("the file is cut off"))?;
+        Ok(buffer)
+    }
+
+    fn read_preamble(&self, file: &mut fs::File) -> Result<Preamble, String> {
+        let magic = self.read_exact_at(file, 0, BINARY_MAGIC.len())?;
+        if magic != BINARY_MAGIC {
+            return Err(self.invalid("missing magic"));
+        }
+        let mut offset = BINARY_MAGIC.len() as u64;
+        let header_length = u32::from_le_bytes(
+            self.read_exact_at(file, offset, 4)?
+                .try_into()
+                .expect("read 4 bytes"),
+        ) as usize;
+        offset += 4;
+        let header_line = String::from_utf8(self.read_exact_at(file, offset, header_length)?)
+            .map_err(|_| self.invalid("the header is not UTF-8"))?;
+        offset += header_length as u64;
+        let digest_length = u32::from_le_bytes(
+            self.read_exact_at(file, offset, 4)?
+                .try_into()
+                .expect("read 4 bytes"),
+        ) as usize;
+        offset += 4;
+        let entry_count = u64::from_le_bytes(
+            self.read_exact_at(file, offset, 8)?
+                .try_into()
+                .expect("read 8 bytes"),
+        );
+        offset += 8;
+        // Every record has to be in the file, only words can be cut off
+        let file_length = self.file_length(file)?;
+        match Preamble::new(header_line, digest_length, entry_count, offset) {
+            Some(preamble) if preamble.words_offset <= file_length => Ok(preamble),
+            _ => Err(self.invalid(&format!(
+                "{} records of {} bytes do not fit in the file, it may be truncated",
+                entry_count,
+                digest_length + RECORD_TAIL_LENGTH
+            ))),
+        }
+    }
+
+    fn parse_record(&self, preamble: &Preamble, bytes: &[u8]) -> Record {
+        let (digest, tail) = bytes.split_at(preamble.digest_length);
+        Record {
+            digest: digest.to_vec(),
+            word_offset: u64::from_le_bytes(tail[..8].try_into().expect("record tail is 12 bytes")),
+            word_length: u32::from_le_bytes(tail[8..].try_into().expect("record tail is 12 bytes"))
+                as usize,
+        }
+    }
+
+    fn read_record(
+        &self,
+        file: &mut fs::File,
+        preamble: &Preamble,
+        index: u64,
+    ) -> Result<Record, String> {
+        let offset = preamble.records_offset + index * preamble.record_length();
+        let bytes = self.read_exact_at(file, offset, preamble.record_length() as usize)?;
+        Ok(self.parse_record(preamble, &bytes))
+    }
+
+    fn read_word(
+        &self,
+        file: &mut fs::File,
+        preamble: &Preamble,
+        record: &Record,
+    ) -> Result<String, String> {
+        let word_offset = preamble
+            .words_offset
+            .checked_add(record.word_offset)
+            .ok_or_else(|| self.invalid("a word is past the end of the file"))?;
+        let bytes = self.read_exact_at(file, word_offset, record.word_length)?;
+        String::from_utf8(bytes).map_err(|_| self.invalid("a word is not UTF-8"))
+    }
+}
+
+fn mixed_digest_error(word_hash: &WordHash) -> String {
+    format!(
+        "Binary tables need digests of a single length, but {} has a {} character digest",
+        word_hash.word,
+        word_hash.hash.len()
+    )
+}
+
+impl TableStorage for BinaryStorage {
+    fn write(&self, header: &TableHeader, word_hashes: &[WordHash]) -> Result<(), String> {
+        // Check every digest up front, so a table that cannot be written is left as it was
+        for word_hash in word_hashes {
+            hasher::decode_hex(&word_hash.hash)?;
+            if word_hash.hash.len() != word_hashes[0].hash.len() {
+                return Err(mixed_digest_error(word_hash));
+            }
+        }
+        self.write_stream(header, &mut word_hashes.iter().cloned().map(Ok))
+    }
+
+    fn write_stream(
+        &self,
+        header: &TableHeader,
+        word_hashes: &mut WordHashStream,
+    ) -> Result<(), String> {
+        /*
+            Records and words are written through two handles on the same file, the
+            words starting right after the last record, which `header.count` places
+        */
+        let header_line = header.serialize()?;
+        let mut word_hashes = word_hashes.peekable();
+        let digest_length = match word_hashes.peek() {
+            Some(Ok(word_hash)) => hasher::decode_hex(&word_hash.hash)?.len(),
+            Some(Err(e)) => return Err(e.clone()),
+            None => 0,
+        };
+        let write_error = |e: std::io::Error| format!("Error while writing hashes to file: {}", e);
+        let file = fs::File::create(&self.path)
+            .map_err(|e| format!("Unable to open file for writing: {}", e))?;
+        // Opened again rather than cloned, since clones share the file position
+        let words_file = fs::OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .map_err(write_error)?;
+        let preamble = Preamble::new(
+            header_line.clone(),
+            digest_length,
+            header.count as u64,
+            // Magic, header length, header, digest length and entry count
+            (BINARY_MAGIC.len() + 4 + header_line.len() + 4 + 8) as u64,
+        )
+        .ok_or_else(|| format!("Too many entries for a binary table: {}", header.count))?;
+        let mut records = BufWriter::new(file);
+        records
+            .write_all(BINARY_MAGIC)
+            .and_then(|_| records.write_all(&(preamble.header_line.len() as u32).to_le_bytes()))
+            .and_then(|_| records.write_all(preamble.header_line.as_bytes()))
+            .and_then(|_| records.write_all(&(digest_length as u32).to_le_bytes()))
+            .and_then(|_| records.write_all(&preamble.entry_count.to_le_bytes()))
+            .map_err(write_error)?;
+        let mut words = BufWriter::new(words_file);
+        words
+            .seek(SeekFrom::Start(preamble.words_offset))
+            .map_err(write_error)?;
+
+        let mut entry_count: u64 = 0;
+        let mut word_offset: u64 = 0;
+        for word_hash in word_hashes {
+            let word_hash = word_hash?;
+            let digest = hasher::decode_hex(&word_hash.hash)?;
+            if digest.len() != digest_length {
+                return Err(mixed_digest_error(&word_hash));
+            }
+            records
+                .write_all(&digest)
+                .and_then(|_| records.write_all(&word_offset.to_le_bytes()))
+                .and_then(|_| records.write_all(&(word_hash.word.len() as u32).to_le_bytes()))
+                .and_then(|_| words.write_all(word_hash.word.as_bytes()))
+                .map_err(write_error)?;
+            word_offset += word_hash.word.len() as u64;
+            entry_count += 1;
+        }
+        if entry_count != preamble.entry_count {
+            return Err(format!(
+                "The table header says it holds {} entries, but {} were written",
+                preamble.entry_count, entry_count
+            ));
+        }
+        records
+            .flush()
+            .and_then(|_| words.flush())
+            .map_err(write_error)
+    }
+
+    fn read_header(&self) -> Result<Option<TableHeader>, String> {
+        let preamble = self.read_preamble(&mut self.open()?)?;
+        Ok(Some(TableHeader::deserialize(&preamble.header_line)?))
+    }
+
+    fn read_entries(&self) -> Result<Vec<TableEntry>, String> {
+        /*
+            Words past the end of the file are entries of their own, so a
+            truncated table can still be told apart from a malformed one.
+            `read_preamble` has checked that every record is in the file
+        */
+        let mut content: Vec<u8> = Vec::new();
+        let mut file = self.open()?;
+        let preamble = self.read_preamble(&mut file)?;
+        file.seek(SeekFrom::Start(0))
+            .and_then(|_| file.read_to_end(&mut content))
+            .map_err(|e| format!("Error while reading from file: {}", e))?;
+        let record_length = preamble.record_length() as usize;
+        let records_offset = preamble.records_offset as usize;
+        let words_offset = preamble.words_offset as usize;
+        let entries = (0..preamble.entry_count as usize)
+            .map(|index| {
+                let position = format!("record {}", index + 1);
+                let cut_off = || {
+                    format!(
+                        "Record {} is cut off, the table may be truncated",
+                        index + 1
+                    )
+                };
+                let record_start = records_offset + index * record_length;
+                let word_hash = content
+                    .get(record_start..record_start + record_length)
+                    .ok_or_else(cut_off)
+                    .map(|bytes| self.parse_record(&preamble, bytes))
+                    .and_then(|record| {
+                        let word = usize::try_from(record.word_offset)
+                            .ok()
+                            .and_then(|word_offset| words_offset.checked_add(word_offset))
+                            .and_then(|word_start| {
+                                content.get(word_start..word_start.checked_add(record.word_length)?)
+                            })
+                            .ok_or_else(cut_off)?;
+                        Ok(WordHash {
+                            word: String::from_utf8(word.to_vec()).map_err(|_| {
+                                format!("The word of record {} is not UTF-8", index + 1)
+                            })?,
+                            hash: hasher::encode_hex(&record.digest),
+                        })
+                    });
+                TableEntry {
+                    position,
+                    word_hash,
+                }
+            })
+            .collect();
+        Ok(entries)
+    }
+
+    fn lookup(&self, hash: &str) -> Result<Option<String>, String> {
+        // Hashes that are not hex, or of another length, cannot be in the table
+        let digest = match hasher::decode_hex(hash) {
+            Ok(digest) => digest,
+            Err(_) => return Ok(None),
+        };
+        let mut file = self.open()?;
+        let preamble = self.read_preamble(&mut file)?;
+        if digest.len() != preamble.digest_length {
+            return Ok(None);
+        }
+        let sorted = TableHeader::deserialize(&preamble.header_line)
+            .map(|header| header.sorted)
+            .unwrap_or(false);
+        let found = if sorted {
+            let (mut low, mut high) = (0, preamble.entry_count);
+            let mut found = None;
+            while low < high {
+                let middle = low + (high - low) / 2;
+                let record = self.read_record(&mut file, &preamble, middle)?;
+                match record.digest.cmp(&digest) {
+                    Ordering::Less => low = middle + 1,
+                    Ordering::Greater => high = middle,
+                    Ordering::Equal => {
+                        found = Some(record);
+                        break;
+                    }
+                }
+            }
+            found
+        } else {
+            let records = self.read_exact_at(
+                &mut file,
+                preamble.records_offset,
+                (preamble.entry_count * preamble.record_length()) as usize,
+            )?;
+            records
+                .chunks_exact(preamble.record_length() as usize)
+                .map(|bytes| self.parse_record(&preamble, bytes))
+                .find(|record| record.digest == digest)
+        };
+        found
+            .map(|record| self.read_word(&mut file, &preamble, &record))
+            .transpose()
+    }
+
+    fn sample_hash(&self) -> Result<Option<String>, String> {
+        let mut file = self.open()?;
+        let preamble = self.read_preamble(&mut file)?;
+        if preamble.entry_count == 0 {
+            return Ok(None);
+        }
+        let record = self.read_record(&mut file, &preamble, 0)?;
+        Ok(Some(hasher::encode_hex(&record.digest)))
+    }
+}
@@ -0,0 +1,366 @@
+use crate::hasher::WordHash;
+use crate::header::{self, TableHashing};
+use crate::shard::{self, ShardManifest};
+use crate::storage;
+use rand::seq::index;
+use std::collections::HashMap;
+use std::path;
+use std::str::FromStr;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Sample {
+    // Re-hash every word
+    Full,
+    // Re-hash this many randomly picked words
+    Random(usize),
+}
+
+impl FromStr for Sample {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Sample::Full),
+            _ => match s.parse::<usize>() {
+                Ok(size) => Ok(Sample::Random(size)),
+                Err(_) => Err(format!(
+                    "Invalid sample, expected full or a number of words, got: {}",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TableProblem {
+    // The file the problem is in, a shard for sharded tables
+    pub path: String,
+    // The entry the problem is about, None when it is about the whole file
+    pub position: Option<String>,
+    pub detail: String,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct TableReport {
+    pub entry_count: usize,
+    pub sorted: bool,
+    pub rehashed_count: usize,
+    pub problems: Vec<TableProblem>,
+    // Checks that had to be skipped, and why
+    pub notes: Vec<String>,
+}
+
+fn sample_indexes(entry_count: usize, sample: Sample) -> Vec<usize> {
+    match sample {
+        Sample::Full => (0..entry_count).collect(),
+        Sample::Random(size) => {
+            let mut indexes =
+                index::sample(&mut rand::thread_rng(), entry_count, size.min(entry_count))
+                    .into_vec();
+            indexes.sort_unstable();
+            indexes
+        }
+    }
+}
+
+fn verify_file(table_path: &str, sample: Sample, report: &mut TableReport) -> Result<(), String> {
+    /*
+        Check a single table file: its header against the entries it holds, the
+        sort order the header claims, duplicate entries and a sample of digests
+    */
+    let mut problems: Vec<TableProblem> = Vec::new();
+    let mut problem = |position: Option<&str>, detail: String| {
+        problems.push(TableProblem {
+            path: String::from(table_path),
+            position: position.map(String::from),
+            detail,
+        })
+    };
+    let table = storage::open_table(table_path)?;
+    let header = match table.read_header() {
+        Ok(header) => header,
+        Err(e) => {
+            problem(Some("header"), e);
+            None
+        }
+    };
+    let entries = table.read_entries()?;
+    let entry_count = entries.len();
+    let mut word_hashes: Vec<(String, WordHash)> = Vec::new();
+    for entry in entries {
+        match entry.word_hash {
+            Ok(word_hash) => word_hashes.push((entry.position, word_hash)),
+            Err(e) => problem(Some(&entry.position), e),
+        }
+    }
+
+    // Only the header can tell whether the table is whole
+    if let Some(header) = &header {
+        if header.count != entry_count {
+            problem(
+                None,
+                format!(
+                    "Header says the table holds {} entries, but it holds {}. It may be truncated",
+                    header.count, entry_count
+                ),
+            );
+        }
+        let checksum = header::checksum(word_hashes.iter().map(|(_, word_hash)| word_hash));
+        if header.checksum != checksum {
+            problem(
+                None,
+                format!(
+                    "Checksum mismatch, the header says {} but the entries give {}",
+                    header.checksum, checksum
+                ),
+            );
+        }
+    }
+
+    let mut sorted = true;
+    let mut first_positions: HashMap<(&str, &str), &str> = HashMap::new();
+    for (i, (position, word_hash)) in word_hashes.iter().enumerate() {
+        if i > 0 && word_hashes[i - 1].1.hash > word_hash.hash {
+            sorted = false;
+            // Out of order entries are only a problem in tables that claim to be sorted
+            if header.as_ref().is_some_and(|header| header.sorted) {
+                problem(
+                    Some(position),
+                    format!("Out of order, sorts before {}", word_hashes[i - 1].0),
+                );
+            }
+        }
+        let key = (word_hash.word.as_str(), word_hash.hash.as_str());
+        match first_positions.get(&key) {
+            Some(first_position) => {
+                problem(Some(position), format!("Duplicate of {}", first_position))
+            }
+            None => {
+                first_positions.insert(key, position);
+            }
+        }
+    }
+
+    match header.as_ref().map(|header| &header.hashing) {
+        None => report.notes.push(format!(
+            "{} has no header, so its count, checksum and digests cannot be checked",
+            table_path
+        )),
+        Some(TableHashing::Hmac(_)) => report.notes.push(format!(
+            "HMAC keys are not stored in tables, so the digests in {} cannot be re-hashed",
+            table_path
+        )),
+        Some(hashing) => {
+            for i in sample_indexes(word_hashes.len(), sample) {
+                let (position, word_hash) = &word_hashes[i];
+                let expected = hashing.hash(&word_hash.word).unwrap_or_default();
+                if expected != word_hash.hash {
+                    problem(
+                        Some(position),
+                        format!(
+                            "Digest does not match word {}, expected {}",
+                            word_hash.word, expected
+                        ),
+                    );
+                }
+                report.rehashed_count += 1;
+            }
+        }
+    }
+    report.entry_count += entry_count;
+    report.sorted &= sorted;
+    report.problems.extend(problems);
+    Ok(())
+}
+
+fn verify_shards(table_dir: &str, sample: Sample, report: &mut TableReport) -> Result<(), String> {
+    // Every shard is checked on its own, and for entries that belong in another shard
+    let manifest = ShardManifest::open(table_dir)?;
+    for shard_index in 0..manifest.shard_count {
+        let shard_path = manifest.shard_path(table_dir, shard_index);
+        if !path::Path::new(&shard_path).exists() {
+            report.notes.push(format!(
+                "Shard {} of {} has not been generated yet",
+                shard_index, table_dir
+            ));
+            continue;
+        }
+        verify_file(&shard_path, sample, report)?;
+        for entry in storage::open_table(&shard_path)?.read_entries()? {
+            let hash = match entry.word_hash {
+                Ok(word_hash) => word_hash.hash,
+                Err(_) => continue,
+            };
+            match manifest.shard_index(&hash) {
+                Ok(expected_index) if expected_index == shard_index => (),
+                Ok(expected_index) => report.problems.push(TableProblem {
+                    path: shard_path.clone(),
+                    position: Some(entry.position),
+                    detail: format!("Belongs in shard {}", expected_index),
+                }),
+                Err(e) => report.problems.push(TableProblem {
+                    path: shard_path.clone(),
+                    position: Some(entry.position),
+                    detail: e,
+                }),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn verify_table(rainbow_table_path: &str, sample: Sample) -> Result<TableReport, String> {
+    /*
+        Check a table, or every shard of a sharded one. Problems with the table
+        are collected in the report, errors are only for tables that cannot be
+        read at all
+    */
+    let mut report = TableReport {
+        sorted: true,
+        ..TableReport::default()
+    };
+    if shard::is_sharded_table(rainbow_table_path) {
+        verify_shards(rainbow_table_path, sample, &mut report)?;
+    } else {
+        verify_file(rainbow_table_path, sample, &mut report)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher;
+    use crate::storage::StorageFormat;
+    use crate::test_utils;
+    use std::fs;
+
+    #[test]
+    fn test_parse_sample() {
+        assert_eq!(Ok(Sample::Full), "full".parse::<Sample>());
+        assert_eq!(Ok(Sample::Random(100)), "100".parse::<Sample>());
+        assert!("some".parse::<Sample>().is_err());
+    }
+
+    #[test]
+    fn test_verify_valid_table() {
+        for format in [StorageFormat::Text, StorageFormat::Sqlite] {
+            let temp_file_handler = test_utils::TempFileHandler::new();
+            let path = &temp_file_handler.temp_file_path;
+            // 209f..., 838f..., c6c3...
+            test_utils::write_table(path, format, &["rice", "noodles", "salad"]);
+            let report = verify_table(path, Sample::Full).unwrap();
+            assert_eq!(Vec::<TableProblem>::new(), report.problems);
+            assert_eq!(3, report.entry_count);
+            assert_eq!(3, report.rehashed_count);
+            assert!(report.sorted);
+            assert_eq!(
+                1,
+                verify_table(path, Sample::Random(1))
+                    .unwrap()
+                    .rehashed_count
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_truncated_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        test_utils::write_table(path, StorageFormat::Text, &["rice", "noodles", "salad"]);
+        // Cut the table off halfway through its last line
+        let content = fs::read_to_string(path).unwrap();
+        fs::write(path, &content[..content.len() - 40]).unwrap();
+
+        let report = verify_table(path, Sample::Full).unwrap();
+        let positions: Vec<Option<&str>> = report
+            .problems
+            .iter()
+            .map(|problem| problem.position.as_deref())
+            .collect();
+        // The checksum no longer matches, and the last line has a shortened digest
+        assert_eq!(vec![None, Some("line 4")], positions);
+        assert!(report.problems[0].detail.starts_with("Checksum mismatch"));
+        assert!(report.problems[1]
+            .detail
+            .starts_with("Digest does not match word salad"));
+    }
+
+    #[test]
+    fn test_verify_table_problems() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        let mut entries =
+            test_utils::write_table(path, StorageFormat::Text, &["rice", "noodles", "salad"]);
+        // Claims to be sorted, but is not, has a duplicate, a wrong digest and a bad line
+        entries.swap(0, 1);
+        entries.push(entries[0].clone());
+        entries[2].hash = hasher::hash_word("potato");
+        let mut content = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+        for word_hash in &entries {
+            content.push_str(&format!("\n{}:{}", word_hash.word, word_hash.hash));
+        }
+        content.push_str("\nnot a table\n");
+        fs::write(path, content).unwrap();
+
+        let report = verify_table(path, Sample::Full).unwrap();
+        let problems: Vec<(Option<&str>, &str)> = report
+            .problems
+            .iter()
+            .map(|problem| (problem.position.as_deref(), &problem.detail[..12]))
+            .collect();
+        assert_eq!(
+            vec![
+                (Some("line 6"), "Invalid tabl"),
+                (None, "Header says "),
+                (None, "Checksum mis"),
+                (Some("line 3"), "Out of order"),
+                (Some("line 5"), "Out of order"),
+                (Some("line 5"), "Duplicate of"),
+                (Some("line 4"), "Digest does "),
+            ],
+            problems
+        );
+        assert!(!report.sorted);
+    }
+
+    #[test]
+    fn test_verify_headerless_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let path = &temp_file_handler.temp_file_path;
+        fs::write(path, "rice:209f\nrice:209f\n").unwrap();
+        let report = verify_table(path, Sample::Full).unwrap();
+        assert_eq!(1, report.problems.len());
+        assert_eq!(Some("line 2".to_string()), report.problems[0].position);
+        assert_eq!(0, report.rehashed_count);
+        assert_eq!(1, report.notes.len());
+    }
+
+    #[test]
+    fn test_verify_sharded_table() {
+        let temp_file_handler = test_utils::TempFileHandler::new();
+        let table_dir = format!("{}.shards", temp_file_handler.temp_file_path);
+        let manifest = ShardManifest::new(2).unwrap();
+        manifest.write(&table_dir).unwrap();
+        // rice (209f...) belongs in shard 0, salad (c6c3...) does not
+        test_utils::write_table(
+            &manifest.shard_path(&table_dir, 0),
+            StorageFormat::Text,
+            &["rice", "salad"],
+        );
+
+        let report = verify_table(&table_dir, Sample::Full).unwrap();
+        assert_eq!(1, report.problems.len());
+        assert_eq!("Belongs in shard 1", report.problems[0].detail);
+        assert_eq!(Some("line 3".to_string()), report.problems[0].position);
+        // Shard 1 was never generated
+        assert_eq!(1, report.notes.len());
+        fs::remove_dir_all(&table_dir).unwrap();
+    }
+}
@@ -16,6 +16,46 @@ fn test_serialize_hashes() {
     assert_eq!(serialized_hashes_vec, expected_vec);
 }
 
+#[test]
+fn test_serialize_hmac_hashes() {
+    let word_vec = vec!["online123".to_string(), "earth616".to_string()];
+    let expected_vec = vec![
+        "online123:e023e082619438f2da78e95340e0ce580d6c7fb8723ffdf37bd8a7f882a04baa".to_string(),
+        "earth616:40cddd6f434de0d4fb5bdc7c5ff9aabdc6e20c1f17aa3bcadc2a4c3297545b7d".to_string(),
+    ];
+    // The key is "key"
+    let hmac_key = hasher::HmacKey::from_hex(hasher::Algorithm::Sha256, "6b6579").unwrap();
+    let serialized_hashes_vec = hasher::serialize_hmac_hashes(word_vec, &hmac_key);
+    assert_eq!(serialized_hashes_vec, expected_vec);
+}
+
+#[test]
+fn test_serialize_pipeline_hashes() {
+    let word_vec = vec!["password".to_string(), "hello".to_string()];
+    let expected_vec = vec![
+        "password:f24a0e9ce12069c72f77046ad708b655".to_string(),
+        "hello:128cd7841b5084e7829b62703912aada".to_string(),
+    ];
+    let pipeline = hasher::expression::Pipeline::compile("md5(md5(p).salt)").unwrap();
+    let serialized_hashes_vec = hasher::serialize_pipeline_hashes(word_vec, &pipeline, Some("abc"));
+    assert_eq!(serialized_hashes_vec, expected_vec);
+}
+
+#[test]
+fn test_serialize_lm_hashes() {
+    let word_vec = vec![
+        "password".to_string(),
+        "PASSWORD".to_string(),
+        "d".to_string(),
+    ];
+    let expected_vec = vec![
+        "PASSWOR:e52cac67419a9a22".to_string(),
+        "D:4a3b108f3fa6cb6d".to_string(),
+    ];
+    let serialized_hashes_vec = hasher::serialize_lm_hashes(word_vec);
+    assert_eq!(serialized_hashes_vec, expected_vec);
+}
+
 #[test]
 fn test_deserialize_hashes() {
     let serialized_hashes = vec![